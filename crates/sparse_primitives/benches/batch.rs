@@ -0,0 +1,50 @@
+//! Batched coarse rasterization
+
+use bench_gen::{Command, Params, PolyIterator};
+use criterion::Criterion;
+use peniko::Fill;
+use sparse_primitives::RenderContext;
+
+const WIDTH: usize = 1024;
+const HEIGHT: usize = 1024;
+const NUM_PATHS: usize = 1000;
+
+pub fn batch(c: &mut Criterion) {
+    let mut g = c.benchmark_group("batch");
+
+    let paths = PolyIterator::new(
+        Params {
+            width: WIDTH,
+            height: HEIGHT,
+            stroke: false,
+            size: 64,
+        },
+        20,
+        true,
+    )
+    .take(NUM_PATHS)
+    .filter_map(|cmd| match cmd {
+        Command::FillPath(path, color, nz) => Some((path, color, nz)),
+        _ => None,
+    })
+    .collect::<Vec<_>>();
+
+    for num_threads in [1, 2, 4, 8] {
+        g.bench_function(format!("poly_20 - {} threads", num_threads), |b| {
+            let mut ctx = RenderContext::new(WIDTH, HEIGHT);
+            ctx.set_batch_threads(num_threads);
+
+            b.iter(|| {
+                ctx.reset();
+
+                for (path, color, nz) in &paths {
+                    ctx.set_paint(*color);
+                    ctx.set_fill_rule(if *nz { Fill::NonZero } else { Fill::EvenOdd });
+                    ctx.fill_path(path);
+                }
+
+                ctx.flush();
+            })
+        });
+    }
+}
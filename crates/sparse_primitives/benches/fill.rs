@@ -1,6 +1,6 @@
 use bench_gen::ColorIter;
 use criterion::Criterion;
-use peniko::Compose;
+use peniko::{BlendMode, Compose, Mix};
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
 use sparse_primitives::execute::Avx2;
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
@@ -15,7 +15,7 @@ pub fn fill(c: &mut Criterion) {
     let mut g = c.benchmark_group("fill");
 
     macro_rules! fill_single {
-        ($name:ident, $compose:path, $exec:ident) => {
+        ($name:ident, $blend_mode:expr, $exec:ident) => {
             g.bench_function(
                 format!("{} - {}", stringify!($name), stringify!($exec)),
                 |b| {
@@ -25,7 +25,7 @@ pub fn fill(c: &mut Criterion) {
                         let mut color = ColorIter::new(false);
 
                         for _ in 0..FILL_ITERS {
-                            fine.fill(0, 254, &color.next().unwrap().into(), $compose);
+                            fine.fill(0, 254, &color.next().unwrap().into(), $blend_mode);
                         }
                     })
                 },
@@ -34,14 +34,15 @@ pub fn fill(c: &mut Criterion) {
     }
 
     macro_rules! fill {
-        ($name:ident, $compose:path) => {
-            fill_single!($name, $compose, Scalar);
+        ($name:ident, $blend_mode:expr) => {
+            fill_single!($name, $blend_mode, Scalar);
             #[cfg(all(target_arch = "aarch64", feature = "simd"))]
-            fill_single!($name, $compose, Neon);
+            fill_single!($name, $blend_mode, Neon);
             #[cfg(all(target_arch = "x86_64", feature = "simd"))]
-            fill_single!($name, $compose, Avx2);
+            fill_single!($name, $blend_mode, Avx2);
         };
     }
 
-    fill!(src_over, Compose::SrcOver);
+    fill!(src_over, BlendMode::new(Mix::Normal, Compose::SrcOver));
+    fill!(multiply, BlendMode::new(Mix::Multiply, Compose::SrcOver));
 }
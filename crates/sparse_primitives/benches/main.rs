@@ -1,4 +1,6 @@
+mod batch;
 mod fill;
+mod rect;
 mod render_strips;
 mod sorting;
 mod strip;
@@ -11,4 +13,6 @@ criterion_group!(s, sorting::sorting);
 criterion_group!(f, fill::fill);
 criterion_group!(st, strip::strip);
 criterion_group!(rs, render_strips::_render_strips);
-criterion_main!(tg, s, f, st, rs);
+criterion_group!(r, rect::rect);
+criterion_group!(b, batch::batch);
+criterion_main!(tg, s, f, st, rs, r, b);
@@ -0,0 +1,86 @@
+//! Rectangle filling
+
+use bench_gen::{Command, Params, RectIterator, RectType};
+use criterion::Criterion;
+use peniko::kurbo::{Rect, Shape};
+use sparse_primitives::execute::ExecutionMode;
+use sparse_primitives::RenderContext;
+
+const WIDTH: usize = 1024;
+const HEIGHT: usize = 1024;
+const NUM_RECTS: usize = 1000;
+
+pub fn rect(c: &mut Criterion) {
+    let mut g = c.benchmark_group("rect");
+
+    macro_rules! rect_single {
+        ($name:ident, $rect_type:expr, $size:expr, $mode:ident) => {
+            let rects = RectIterator::new(
+                Params {
+                    width: WIDTH,
+                    height: HEIGHT,
+                    stroke: false,
+                    size: $size,
+                },
+                $rect_type,
+            )
+            .take(NUM_RECTS)
+            .filter_map(|cmd| match cmd {
+                Command::FillRect(rect, color) => Some((rect, color)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+            let fill = |ctx: &mut RenderContext, rect: &Rect, as_path: bool| {
+                if as_path {
+                    ctx.fill_path(&rect.to_path(0.1));
+                } else {
+                    ctx.fill_rect(rect);
+                }
+            };
+
+            for as_path in [false, true] {
+                g.bench_function(
+                    format!(
+                        "{}_{} - {} - {}",
+                        stringify!($name),
+                        $size,
+                        if as_path { "path" } else { "rect" },
+                        stringify!($mode)
+                    ),
+                    |b| {
+                        let mut ctx = RenderContext::new_with_execution_mode(
+                            WIDTH,
+                            HEIGHT,
+                            ExecutionMode::$mode,
+                        );
+
+                        b.iter(|| {
+                            ctx.reset();
+
+                            for (rect, color) in &rects {
+                                ctx.set_paint(*color);
+                                fill(&mut ctx, rect, as_path);
+                            }
+                        })
+                    },
+                );
+            }
+        };
+    }
+
+    macro_rules! rect {
+        ($name:ident, $rect_type:expr) => {
+            for size in [8, 64, 256] {
+                rect_single!($name, $rect_type, size, Scalar);
+                #[cfg(all(target_arch = "aarch64", feature = "simd"))]
+                rect_single!($name, $rect_type, size, Neon);
+                #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+                rect_single!($name, $rect_type, size, Avx2);
+            }
+        };
+    }
+
+    rect!(aligned, RectType::Aligned);
+    rect!(unaligned, RectType::Unaligned);
+}
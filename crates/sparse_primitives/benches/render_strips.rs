@@ -6,6 +6,7 @@ use sparse_primitives::execute::Avx2;
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
 use sparse_primitives::execute::Neon;
 use sparse_primitives::execute::Scalar;
+use sparse_primitives::flatten::DEFAULT_TOLERANCE;
 use sparse_primitives::kurbo::{Affine, BezPath, Stroke};
 use sparse_primitives::strip::render_strips;
 use sparse_primitives::tiling::{FlatLine, Tile, Tiles};
@@ -37,6 +38,7 @@ pub fn _render_strips(c: &mut Criterion) {
                                     &mut strip_buf,
                                     &mut alpha_buf,
                                     Fill::NonZero,
+                                    true,
                                 );
                             }
                         })
@@ -76,7 +78,7 @@ fn read_from_file(name: &str) -> Vec<Tiles> {
         let l = line.unwrap();
         let path = BezPath::from_svg(&l).unwrap();
         let mut line_buf = vec![];
-        sparse_primitives::flatten::fill(&path, Affine::IDENTITY, &mut line_buf);
+        sparse_primitives::flatten::fill(&path, Affine::IDENTITY, DEFAULT_TOLERANCE, &mut line_buf);
         let mut tiles = Tiles::new();
         tiles.make_tiles(&line_buf);
         tiles.sort_tiles();
@@ -97,6 +99,7 @@ fn read_from_file(name: &str) -> Vec<Tiles> {
             &path,
             &Stroke::new(3.0),
             Affine::IDENTITY,
+            DEFAULT_TOLERANCE,
             &mut line_buf,
         );
 
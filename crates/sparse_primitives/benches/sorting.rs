@@ -1,6 +1,7 @@
 use criterion::measurement::WallTime;
 use criterion::{BatchSize, BenchmarkGroup, Criterion};
 use peniko::kurbo::{Affine, BezPath, Stroke};
+use sparse_primitives::flatten::DEFAULT_TOLERANCE;
 use sparse_primitives::tiling::{FlatLine, Tiles};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -21,7 +22,7 @@ pub(crate) fn flattened_from_file(name: &str) -> Vec<Vec<FlatLine>> {
         let l = line.unwrap();
         let path = BezPath::from_svg(&l).unwrap();
         let mut temp_buf = vec![];
-        sparse_primitives::flatten::fill(&path, Affine::IDENTITY, &mut temp_buf);
+        sparse_primitives::flatten::fill(&path, Affine::IDENTITY, DEFAULT_TOLERANCE, &mut temp_buf);
         buf.push(temp_buf);
     }
 
@@ -38,6 +39,7 @@ pub(crate) fn flattened_from_file(name: &str) -> Vec<Vec<FlatLine>> {
             &path,
             &Stroke::new(3.0),
             Affine::IDENTITY,
+            DEFAULT_TOLERANCE,
             &mut temp_buf,
         );
         buf.push(temp_buf);
@@ -1,6 +1,6 @@
 use bench_gen::ColorIter;
 use criterion::Criterion;
-use peniko::Compose;
+use peniko::{BlendMode, Compose, Mix};
 use rand::rngs::StdRng;
 use rand::RngCore;
 use rand::SeedableRng;
@@ -19,7 +19,7 @@ pub fn strip(c: &mut Criterion) {
     let mut g = c.benchmark_group("strip");
 
     macro_rules! strip_single {
-        ($name:ident, $compose:path, $exec:ident) => {
+        ($name:ident, $blend_mode:expr, $exec:ident) => {
             let mut alphas = vec![];
             let mut rng = StdRng::from_seed(SEED);
 
@@ -41,7 +41,7 @@ pub fn strip(c: &mut Criterion) {
                                 WIDE_TILE_WIDTH,
                                 &alphas,
                                 &color.next().unwrap().into(),
-                                $compose,
+                                $blend_mode,
                             );
                         }
                     })
@@ -51,14 +51,15 @@ pub fn strip(c: &mut Criterion) {
     }
 
     macro_rules! strip {
-        ($name:ident, $compose:path) => {
-            strip_single!($name, $compose, Scalar);
+        ($name:ident, $blend_mode:expr) => {
+            strip_single!($name, $blend_mode, Scalar);
             #[cfg(all(target_arch = "aarch64", feature = "simd"))]
-            strip_single!($name, $compose, Neon);
+            strip_single!($name, $blend_mode, Neon);
             #[cfg(all(target_arch = "x86_64", feature = "simd"))]
-            strip_single!($name, $compose, Avx2);
+            strip_single!($name, $blend_mode, Avx2);
         };
     }
 
-    strip!(src_over, Compose::SrcOver);
+    strip!(src_over, BlendMode::new(Mix::Normal, Compose::SrcOver));
+    strip!(multiply, BlendMode::new(Mix::Multiply, Compose::SrcOver));
 }
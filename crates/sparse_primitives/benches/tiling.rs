@@ -5,7 +5,7 @@ use criterion::{BenchmarkGroup, Criterion};
 use peniko::kurbo::{Affine, BezPath, Stroke};
 use rand::prelude::StdRng;
 use rand::{Rng, SeedableRng};
-use sparse_primitives::flatten;
+use sparse_primitives::flatten::{self, DEFAULT_TOLERANCE};
 use sparse_primitives::tiling::{FlatLine, Point, Tiles};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -86,7 +86,7 @@ fn read_from_file(name: &str) -> Vec<Vec<FlatLine>> {
         let l = line.unwrap();
         let path = BezPath::from_svg(&l).unwrap();
         let mut temp_buf = vec![];
-        flatten::fill(&path, Affine::IDENTITY, &mut temp_buf);
+        flatten::fill(&path, Affine::IDENTITY, DEFAULT_TOLERANCE, &mut temp_buf);
         buf.push(temp_buf);
     }
 
@@ -99,7 +99,13 @@ fn read_from_file(name: &str) -> Vec<Vec<FlatLine>> {
         let mut temp_buf = vec![];
         // Obviously not 100% accurate since the stroke width isn't always the same, but good
         // enough for benching purposes.
-        flatten::stroke(&path, &Stroke::new(3.0), Affine::IDENTITY, &mut temp_buf);
+        flatten::stroke(
+            &path,
+            &Stroke::new(3.0),
+            Affine::IDENTITY,
+            DEFAULT_TOLERANCE,
+            &mut temp_buf,
+        );
         buf.push(temp_buf);
     }
 
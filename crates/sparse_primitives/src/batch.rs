@@ -0,0 +1,213 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Batched coarse rasterization.
+//!
+//! Flattening, tiling and generating the strips of a path doesn't depend on any other path,
+//! so for scenes with many paths this work can be spread across multiple threads. Only the
+//! generation of the wide tile commands needs to happen sequentially, in the order in which
+//! the paths were submitted, so that painter's order is preserved.
+
+use crate::execute::KernelExecutor;
+use crate::paint::Paint;
+use crate::render::InnerContext;
+use crate::strip::{render_strips, Strip};
+use crate::tiling::{cull_lines, FlatLine, Tiles};
+use peniko::kurbo::{Affine, BezPath, Stroke};
+use peniko::{BlendMode, Fill};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A path that has been submitted while batching, together with the state of the render
+/// context at the time of submission.
+pub(crate) struct BatchedPath {
+    path: BezPath,
+    op: PathOp,
+    transform: Affine,
+    /// The paint, already transformed by `transform`.
+    paint: Paint,
+    blend_mode: BlendMode,
+    tolerance: f64,
+    anti_alias: bool,
+}
+
+enum PathOp {
+    Fill(Fill),
+    Stroke(Stroke),
+}
+
+impl PathOp {
+    fn fill_rule(&self) -> Fill {
+        match self {
+            PathOp::Fill(fill_rule) => *fill_rule,
+            PathOp::Stroke(_) => Fill::NonZero,
+        }
+    }
+}
+
+/// The strips and alpha values of a single path. The alpha indices of the strips
+/// start at 0.
+struct PathStrips {
+    strips: Vec<Strip>,
+    alphas: Vec<u32>,
+}
+
+/// The buffers of a worker thread, which are reused for all paths it processes.
+#[derive(Default)]
+struct Worker {
+    line_buf: Vec<FlatLine>,
+    tiles: Tiles,
+}
+
+impl Worker {
+    fn process<KE: KernelExecutor>(
+        &mut self,
+        path: &BatchedPath,
+        width: usize,
+        height: usize,
+    ) -> PathStrips {
+        match &path.op {
+            PathOp::Fill(_) => crate::flatten::fill_in_viewport(
+                &path.path,
+                path.transform,
+                path.tolerance,
+                width,
+                height,
+                &mut self.line_buf,
+            ),
+            PathOp::Stroke(stroke) => crate::flatten::stroke(
+                &path.path,
+                stroke,
+                path.transform,
+                path.tolerance,
+                &mut self.line_buf,
+            ),
+        }
+
+        cull_lines(&mut self.line_buf, width, height);
+        self.tiles.make_tiles(&self.line_buf);
+        self.tiles.sort_tiles();
+
+        let mut strips = vec![];
+        let mut alphas = vec![];
+        render_strips::<KE>(
+            &self.tiles,
+            &mut strips,
+            &mut alphas,
+            path.op.fill_rule(),
+            path.anti_alias,
+        );
+
+        PathStrips { strips, alphas }
+    }
+}
+
+impl<KE: KernelExecutor> InnerContext<KE> {
+    pub(crate) fn set_batch_threads(&mut self, num_threads: usize) {
+        self.flush();
+        self.batch_threads = num_threads;
+    }
+
+    /// Record a path instead of rendering it immediately, if batching is enabled.
+    ///
+    /// Returns `false` if batching is disabled.
+    pub(crate) fn batch_path(&mut self, path: &BezPath, stroke: bool) -> bool {
+        if self.batch_threads <= 1 {
+            return false;
+        }
+
+        let (op, paint) = if stroke {
+            (
+                PathOp::Stroke(self.state.stroke.clone()),
+                self.stroke_paint(),
+            )
+        } else {
+            (
+                PathOp::Fill(self.state.fill_rule),
+                self.state.paint.transformed(self.state.transform),
+            )
+        };
+
+        self.batch.push(BatchedPath {
+            path: path.clone(),
+            op,
+            transform: self.state.transform,
+            paint,
+            blend_mode: self.state.blend_mode,
+            tolerance: self.state.tolerance,
+            anti_alias: self.state.anti_alias,
+        });
+
+        true
+    }
+
+    /// Process all batched paths and generate their commands.
+    pub(crate) fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.batch);
+        let results = process_batch::<KE>(&batch, self.width, self.height, self.batch_threads);
+
+        for (path, result) in batch.into_iter().zip(results) {
+            // The alpha indices of the path start at 0, so they need to be offset by the
+            // alpha values of all previous paths.
+            let offset = self.alphas.len() as u32;
+            self.alphas.extend(result.alphas);
+            self.strip_buf.clear();
+            self.strip_buf
+                .extend(result.strips.into_iter().map(|strip| Strip {
+                    col: strip.col + offset,
+                    ..strip
+                }));
+
+            self.generate_commands(path.op.fill_rule(), path.paint, path.blend_mode);
+        }
+    }
+}
+
+/// Generate the strips of all paths in `batch` using `num_threads` threads.
+///
+/// The results are in the same order as the paths.
+fn process_batch<KE: KernelExecutor>(
+    batch: &[BatchedPath],
+    width: usize,
+    height: usize,
+    num_threads: usize,
+) -> Vec<PathStrips> {
+    let mut results = (0..batch.len()).map(|_| None).collect::<Vec<_>>();
+    // Paths can differ a lot in complexity, so instead of splitting the batch into fixed
+    // chunks, each thread picks the next unprocessed path once it's done.
+    let next = AtomicUsize::new(0);
+
+    std::thread::scope(|s| {
+        let handles = (0..num_threads.min(batch.len()))
+            .map(|_| {
+                s.spawn(|| {
+                    let mut worker = Worker::default();
+                    let mut processed = vec![];
+
+                    loop {
+                        let ix = next.fetch_add(1, Ordering::Relaxed);
+
+                        let Some(path) = batch.get(ix) else {
+                            break;
+                        };
+
+                        processed.push((ix, worker.process::<KE>(path, width, height)));
+                    }
+
+                    processed
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            for (ix, result) in handle.join().unwrap() {
+                results[ix] = Some(result);
+            }
+        }
+    });
+
+    results.into_iter().map(Option::unwrap).collect()
+}
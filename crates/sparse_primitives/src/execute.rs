@@ -36,7 +36,9 @@ impl Default for ExecutionMode {
     }
 }
 
-pub trait KernelExecutor: fine::Compose + strip::Render {}
+/// The kernels for a specific instruction set. Implementors are marker types, so they can
+/// be shared between threads.
+pub trait KernelExecutor: fine::Compose + fine::Gradient + strip::Render + Send + Sync {}
 
 pub struct Scalar;
 
@@ -1,13 +1,16 @@
 use crate::execute::Avx2;
 use crate::fine;
-use crate::fine::COLOR_COMPONENTS;
+use crate::fine::gradient::{ColorRamp, LinearPos, RadialPos, SweepPos};
+use crate::fine::{scalar, Factor, COLOR_COMPONENTS};
+use crate::util::avx2::{div_255, splat_alpha};
+use std::arch::x86_64::*;
 
 impl fine::Compose for Avx2 {
     fn compose_fill(target: &mut [u8], cs: &[u8; COLOR_COMPONENTS], compose: peniko::Compose) {
         unsafe {
             match compose {
                 peniko::Compose::SrcOver => fill::src_over(target, cs),
-                _ => unimplemented!(),
+                _ => fill::porter_duff(target, cs, compose),
             }
         }
     }
@@ -21,15 +24,81 @@ impl fine::Compose for Avx2 {
         unsafe {
             match compose {
                 peniko::Compose::SrcOver => strip::src_over(target, cs, alphas),
-                _ => unimplemented!(),
+                _ => strip::porter_duff(target, cs, alphas, compose),
             }
         }
     }
+
+    fn compose_fill_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        unsafe {
+            match compose {
+                peniko::Compose::SrcOver => fill::src_over_buf(target, src),
+                _ => fill::porter_duff_buf(target, src, compose),
+            }
+        }
+    }
+
+    fn compose_strip_buf(target: &mut [u8], src: &[u8], alphas: &[u32], compose: peniko::Compose) {
+        unsafe {
+            match compose {
+                peniko::Compose::SrcOver => strip::src_over_buf(target, src, alphas),
+                _ => strip::porter_duff_buf(target, src, alphas, compose),
+            }
+        }
+    }
+
+    fn mix(src: &mut [u8], cb: &[u8], mix: peniko::Mix) {
+        match mix {
+            // The non-separable modes need to look at all components of a pixel at once,
+            // which doesn't map well to SIMD, so we use the scalar version for them.
+            peniko::Mix::Hue
+            | peniko::Mix::Saturation
+            | peniko::Mix::Color
+            | peniko::Mix::Luminosity => scalar::mix::blend(src, cb, mix),
+            _ => unsafe { mix::blend(src, cb, mix) },
+        }
+    }
+}
+
+impl fine::Gradient for Avx2 {
+    fn linear_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::linear(buf, ramp, extend, pos, x, y) }
+    }
+
+    fn radial_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::radial(buf, ramp, extend, pos, x, y) }
+    }
+
+    fn sweep_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::sweep(buf, ramp, extend, pos, x, y) }
+    }
 }
 
 mod fill {
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
-    use crate::util::avx2::{div_255, splat_x8};
+    use super::{load_x4, porter_duff_x4, store_x4};
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use crate::util::avx2::{div_255, splat_alpha, splat_x8};
     use std::arch::x86_64::*;
 
     /// SAFETY: The CPU needs to support the target feature `avx2`.
@@ -72,11 +141,90 @@ mod fill {
             _mm_storeu_si128(cb.as_mut_ptr() as *mut __m128i, im4);
         }
     }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn src_over_buf(target: &mut [u8], src: &[u8]) {
+        let stride = TOTAL_STRIP_HEIGHT * 2;
+        let remainder = target.len() % stride;
+        let (head, tail) = target.split_at_mut(target.len() - remainder);
+        let (src_head, src_tail) = src.split_at(src.len() - remainder);
+        let max = _mm256_set1_epi16(255);
+
+        for (cb, cs) in head
+            .chunks_exact_mut(stride)
+            .zip(src_head.chunks_exact(stride))
+        {
+            let cb_vals = _mm256_loadu_si256(cb.as_ptr() as *const __m256i);
+            let cs_vals = _mm256_loadu_si256(cs.as_ptr() as *const __m256i);
+            let cb_lo = _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<0>(cb_vals));
+            let cb_hi = _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<1>(cb_vals));
+            let cs_lo = _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<0>(cs_vals));
+            let cs_hi = _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<1>(cs_vals));
+            let inv_as_lo = _mm256_sub_epi16(max, splat_alpha(cs_lo));
+            let inv_as_hi = _mm256_sub_epi16(max, splat_alpha(cs_hi));
+
+            let dived_lo = div_255(_mm256_mullo_epi16(cb_lo, inv_as_lo));
+            let dived_hi = div_255(_mm256_mullo_epi16(cb_hi, inv_as_hi));
+            let added_lo = _mm256_add_epi16(cs_lo, dived_lo);
+            let added_hi = _mm256_add_epi16(cs_hi, dived_hi);
+            let packed = _mm256_packus_epi16(added_lo, added_hi);
+            let switched = _mm256_permute4x64_epi64::<0b11011000>(packed);
+            _mm256_storeu_si256(cb.as_mut_ptr() as *mut __m256i, switched);
+        }
+
+        for (cb, cs) in tail
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src_tail.chunks_exact(TOTAL_STRIP_HEIGHT))
+        {
+            let cb_vals = _mm256_cvtepu8_epi16(_mm_loadu_si128(cb.as_ptr() as *const __m128i));
+            let cs_vals = _mm256_cvtepu8_epi16(_mm_loadu_si128(cs.as_ptr() as *const __m128i));
+            let inv_as = _mm256_sub_epi16(max, splat_alpha(cs_vals));
+            let im1 = div_255(_mm256_mullo_epi16(cb_vals, inv_as));
+            let im2 = _mm256_add_epi16(cs_vals, im1);
+            let im3 = _mm_packus_epi16(
+                _mm256_extracti128_si256::<0>(im2),
+                _mm256_extracti128_si256::<1>(im2),
+            );
+            _mm_storeu_si128(cb.as_mut_ptr() as *mut __m128i, im3);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+        let cs = splat_x8(cs);
+
+        for cb in target.chunks_exact_mut(TOTAL_STRIP_HEIGHT) {
+            let res = porter_duff_x4(load_x4(cb), cs, fa, fb);
+            store_x4(cb, res);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn porter_duff_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for (cb, cs) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+        {
+            let res = porter_duff_x4(load_x4(cb), load_x4(cs), fa, fb);
+            store_x4(cb, res);
+        }
+    }
 }
 
 mod strip {
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
-    use crate::util::avx2::div_255;
+    use super::{lerp_x4, load_mask_x4, load_x4, porter_duff_x4, store_x4};
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use crate::util::avx2::{div_255, splat_alpha, splat_x8};
     use crate::util::scalar::splat_x4;
     use std::arch::x86_64::*;
 
@@ -104,4 +252,628 @@ mod strip {
             _mm_storeu_si128(cb.as_mut_ptr() as *mut __m128i, im4);
         }
     }
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn src_over_buf(target: &mut [u8], src: &[u8], alphas: &[u32]) {
+        let shuffle_mask = _mm_set_epi8(3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1, 0, 0, 0, 0);
+        let max = _mm256_set1_epi16(255);
+
+        for ((cb, cs), masks) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            let cb_ = _mm256_cvtepu8_epi16(_mm_loadu_si128(cb.as_ptr() as *const __m128i));
+            let cs_ = _mm256_cvtepu8_epi16(_mm_loadu_si128(cs.as_ptr() as *const __m128i));
+            let am = _mm256_cvtepu8_epi16(_mm_shuffle_epi8(
+                _mm_set1_epi32(*masks as i32),
+                shuffle_mask,
+            ));
+            let inv_as_am =
+                _mm256_sub_epi16(max, div_255(_mm256_mullo_epi16(am, splat_alpha(cs_))));
+            let im1 = _mm256_mullo_epi16(cb_, inv_as_am);
+            let im2 = _mm256_mullo_epi16(cs_, am);
+            let im3 = div_255(_mm256_add_epi16(im1, im2));
+            let im4 = _mm_packus_epi16(
+                _mm256_extracti128_si256::<0>(im3),
+                _mm256_extracti128_si256::<1>(im3),
+            );
+            _mm_storeu_si128(cb.as_mut_ptr() as *mut __m128i, im4);
+        }
+    }
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+        let cs = splat_x8(cs);
+
+        for (cb, masks) in target.chunks_exact_mut(TOTAL_STRIP_HEIGHT).zip(alphas) {
+            let cb_ = load_x4(cb);
+            let res = lerp_x4(cb_, porter_duff_x4(cb_, cs, fa, fb), load_mask_x4(*masks));
+            store_x4(cb, res);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn porter_duff_buf(
+        target: &mut [u8],
+        src: &[u8],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for ((cb, cs), masks) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            let cb_ = load_x4(cb);
+            let res = lerp_x4(
+                cb_,
+                porter_duff_x4(cb_, load_x4(cs), fa, fb),
+                load_mask_x4(*masks),
+            );
+            store_x4(cb, res);
+        }
+    }
+}
+
+mod mix {
+    // This mirrors the scalar version operation by operation, see the comments there.
+    // Each vector holds two pixels.
+
+    use std::arch::x86_64::*;
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn blend(src: &mut [u8], backdrop: &[u8], mix: peniko::Mix) {
+        let one = _mm256_set1_ps(1.0);
+
+        for (cs, cb) in src.chunks_exact_mut(8).zip(backdrop.chunks_exact(8)) {
+            let s = load_x2(cs);
+            let b = load_x2(cb);
+            let sa = _mm256_permute_ps::<0xFF>(s);
+            let ba = _mm256_permute_ps::<0xFF>(b);
+            let ab = _mm256_div_ps(ba, _mm256_set1_ps(255.0));
+
+            let mixed = separable(unpremultiply(b, ba), unpremultiply(s, sa), mix);
+            let res = _mm256_add_ps(
+                _mm256_mul_ps(s, _mm256_sub_ps(one, ab)),
+                _mm256_mul_ps(_mm256_mul_ps(sa, ab), mixed),
+            );
+            let res = _mm256_max_ps(_mm256_min_ps(res, sa), _mm256_setzero_ps());
+            // Keep the alpha channel of the source.
+            let res = _mm256_blend_ps::<0b10001000>(res, s);
+
+            store_x2(cs, res);
+        }
+    }
+
+    /// Load 2 pixels and convert them to f32.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn load_x2(buf: &[u8]) -> __m256 {
+        debug_assert!(buf.len() >= 8);
+
+        _mm256_cvtepi32_ps(_mm256_cvtepu8_epi32(_mm_loadl_epi64(
+            buf.as_ptr() as *const __m128i
+        )))
+    }
+
+    /// Round 2 pixels to u8 and store them.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn store_x2(buf: &mut [u8], val: __m256) {
+        debug_assert!(buf.len() >= 8);
+
+        let ints = _mm256_cvttps_epi32(_mm256_add_ps(val, _mm256_set1_ps(0.5)));
+        let packed = _mm256_packus_epi16(_mm256_packus_epi32(ints, ints), _mm256_setzero_si256());
+        let res = _mm_unpacklo_epi32(
+            _mm256_castsi256_si128(packed),
+            _mm256_extracti128_si256::<1>(packed),
+        );
+        _mm_storel_epi64(buf.as_mut_ptr() as *mut __m128i, res);
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn unpremultiply(c: __m256, a: __m256) -> __m256 {
+        let res = _mm256_min_ps(_mm256_div_ps(c, a), _mm256_set1_ps(1.0));
+
+        _mm256_and_ps(res, _mm256_cmp_ps::<_CMP_GT_OQ>(a, _mm256_setzero_ps()))
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn separable(cb: __m256, cs: __m256, mix: peniko::Mix) -> __m256 {
+        use peniko::Mix;
+
+        let zero = _mm256_setzero_ps();
+        let half = _mm256_set1_ps(0.5);
+        let one = _mm256_set1_ps(1.0);
+        let two = _mm256_set1_ps(2.0);
+
+        match mix {
+            Mix::Multiply => _mm256_mul_ps(cb, cs),
+            Mix::Screen => screen(cb, cs),
+            Mix::Overlay => hard_light(cs, cb),
+            Mix::Darken => _mm256_min_ps(cb, cs),
+            Mix::Lighten => _mm256_max_ps(cb, cs),
+            Mix::ColorDodge => {
+                let res = _mm256_min_ps(_mm256_div_ps(cb, _mm256_sub_ps(one, cs)), one);
+                let res = _mm256_blendv_ps(res, one, _mm256_cmp_ps::<_CMP_GE_OQ>(cs, one));
+
+                _mm256_blendv_ps(res, zero, _mm256_cmp_ps::<_CMP_EQ_OQ>(cb, zero))
+            }
+            Mix::ColorBurn => {
+                let res = _mm256_sub_ps(
+                    one,
+                    _mm256_min_ps(_mm256_div_ps(_mm256_sub_ps(one, cb), cs), one),
+                );
+                let res = _mm256_blendv_ps(res, zero, _mm256_cmp_ps::<_CMP_LE_OQ>(cs, zero));
+
+                _mm256_blendv_ps(res, one, _mm256_cmp_ps::<_CMP_GE_OQ>(cb, one))
+            }
+            Mix::HardLight => hard_light(cb, cs),
+            Mix::SoftLight => {
+                let low = _mm256_sub_ps(
+                    cb,
+                    _mm256_mul_ps(
+                        _mm256_mul_ps(_mm256_sub_ps(one, _mm256_mul_ps(two, cs)), cb),
+                        _mm256_sub_ps(one, cb),
+                    ),
+                );
+
+                let poly = _mm256_mul_ps(
+                    _mm256_add_ps(
+                        _mm256_mul_ps(
+                            _mm256_sub_ps(
+                                _mm256_mul_ps(_mm256_set1_ps(16.0), cb),
+                                _mm256_set1_ps(12.0),
+                            ),
+                            cb,
+                        ),
+                        _mm256_set1_ps(4.0),
+                    ),
+                    cb,
+                );
+                let d = _mm256_blendv_ps(
+                    _mm256_sqrt_ps(cb),
+                    poly,
+                    _mm256_cmp_ps::<_CMP_LE_OQ>(cb, _mm256_set1_ps(0.25)),
+                );
+                let high = _mm256_add_ps(
+                    cb,
+                    _mm256_mul_ps(
+                        _mm256_sub_ps(_mm256_mul_ps(two, cs), one),
+                        _mm256_sub_ps(d, cb),
+                    ),
+                );
+
+                _mm256_blendv_ps(high, low, _mm256_cmp_ps::<_CMP_LE_OQ>(cs, half))
+            }
+            Mix::Difference => {
+                // Clear the sign bit.
+                _mm256_andnot_ps(_mm256_set1_ps(-0.0), _mm256_sub_ps(cb, cs))
+            }
+            Mix::Exclusion => _mm256_sub_ps(
+                _mm256_add_ps(cb, cs),
+                _mm256_mul_ps(_mm256_mul_ps(two, cb), cs),
+            ),
+            _ => cs,
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn screen(cb: __m256, cs: __m256) -> __m256 {
+        _mm256_sub_ps(_mm256_add_ps(cb, cs), _mm256_mul_ps(cb, cs))
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn hard_light(cb: __m256, cs: __m256) -> __m256 {
+        let cs2 = _mm256_mul_ps(_mm256_set1_ps(2.0), cs);
+        let low = _mm256_mul_ps(cb, cs2);
+        let high = screen(cb, _mm256_sub_ps(cs2, _mm256_set1_ps(1.0)));
+
+        _mm256_blendv_ps(
+            high,
+            low,
+            _mm256_cmp_ps::<_CMP_LE_OQ>(cs, _mm256_set1_ps(0.5)),
+        )
+    }
+}
+
+/// Load 4 pixels and widen them to 16x u16.
+///
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn load_x4(buf: &[u8]) -> __m256i {
+    debug_assert!(buf.len() >= 16);
+
+    _mm256_cvtepu8_epi16(_mm_loadu_si128(buf.as_ptr() as *const __m128i))
+}
+
+/// Narrow 16x u16 to u8 and store them as 4 pixels.
+///
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn store_x4(buf: &mut [u8], val: __m256i) {
+    debug_assert!(buf.len() >= 16);
+
+    let packed = _mm_packus_epi16(
+        _mm256_extracti128_si256::<0>(val),
+        _mm256_extracti128_si256::<1>(val),
+    );
+    _mm_storeu_si128(buf.as_mut_ptr() as *mut __m128i, packed);
+}
+
+/// Broadcast the 4 coverage values of a strip column to the components of each pixel.
+///
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn load_mask_x4(masks: u32) -> __m256i {
+    let shuffle_mask = _mm_set_epi8(3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1, 0, 0, 0, 0);
+
+    _mm256_cvtepu8_epi16(_mm_shuffle_epi8(_mm_set1_epi32(masks as i32), shuffle_mask))
+}
+
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn factor_x4(factor: Factor, _as: __m256i, ab: __m256i) -> __m256i {
+    let max = _mm256_set1_epi16(255);
+
+    match factor {
+        Factor::Zero => _mm256_setzero_si256(),
+        Factor::One => max,
+        Factor::SrcAlpha => _as,
+        Factor::InvSrcAlpha => _mm256_sub_epi16(max, _as),
+        Factor::DestAlpha => ab,
+        Factor::InvDestAlpha => _mm256_sub_epi16(max, ab),
+    }
+}
+
+/// Compose 4 pixels using the Porter-Duff factors `fa` and `fb`.
+///
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn porter_duff_x4(cb: __m256i, cs: __m256i, fa: Factor, fb: Factor) -> __m256i {
+    let _as = splat_alpha(cs);
+    let ab = splat_alpha(cb);
+    let im1 = div_255(_mm256_mullo_epi16(cs, factor_x4(fa, _as, ab)));
+    let im2 = div_255(_mm256_mullo_epi16(cb, factor_x4(fb, _as, ab)));
+
+    _mm256_min_epu16(_mm256_add_epi16(im1, im2), _mm256_set1_epi16(255))
+}
+
+/// Linearly interpolate between the backdrop and the composed color, using the
+/// coverage `am` of each pixel.
+///
+/// SAFETY: The CPU needs to support the target feature `avx2`.
+#[target_feature(enable = "avx2")]
+unsafe fn lerp_x4(cb: __m256i, co: __m256i, am: __m256i) -> __m256i {
+    let inv_am = _mm256_sub_epi16(_mm256_set1_epi16(255), am);
+
+    div_255(_mm256_add_epi16(
+        _mm256_mullo_epi16(cb, inv_am),
+        _mm256_mullo_epi16(co, am),
+    ))
+}
+
+mod gradient {
+    use crate::fine::gradient::{ColorRamp, LinearPos, RadialPos, SweepPos};
+    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use peniko::Extend;
+    use std::arch::x86_64::*;
+
+    /// We evaluate two columns (i.e. 8 pixels) at once.
+    const STRIDE: usize = 2 * TOTAL_STRIP_HEIGHT;
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn linear(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    ) {
+        let ys = row_coords(y);
+        let (a, b, c) = (
+            _mm256_set1_ps(pos.a),
+            _mm256_set1_ps(pos.b),
+            _mm256_set1_ps(pos.c),
+        );
+        let defined = _mm256_castsi256_ps(_mm256_set1_epi32(-1));
+
+        for (i, chunk) in buf.chunks_mut(STRIDE).enumerate() {
+            let xs = column_coords(x + 2 * i);
+            let t = _mm256_add_ps(_mm256_add_ps(_mm256_mul_ps(a, xs), _mm256_mul_ps(b, ys)), c);
+
+            store(chunk, shade(ramp, extend, t, defined));
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn radial(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    ) {
+        let ys = row_coords(y);
+        let [ia, ib, ic, id, ie, if_] = pos.inv;
+        let zero = _mm256_setzero_ps();
+        let r0 = _mm256_set1_ps(pos.start_radius);
+        let dr = _mm256_set1_ps(pos.dr);
+        let a = _mm256_set1_ps(pos.a);
+        let r0_dr = _mm256_set1_ps(pos.start_radius * pos.dr);
+        let r0_r0 = _mm256_set1_ps(pos.start_radius * pos.start_radius);
+
+        // Whether the radius of the circle at position `t` is non-negative.
+        let is_valid =
+            |t: __m256| _mm256_cmp_ps::<_CMP_GE_OQ>(_mm256_add_ps(r0, _mm256_mul_ps(t, dr)), zero);
+
+        for (i, chunk) in buf.chunks_mut(STRIDE).enumerate() {
+            let xs = column_coords(x + 2 * i);
+            let px = _mm256_sub_ps(
+                _mm256_add_ps(
+                    _mm256_add_ps(
+                        _mm256_mul_ps(_mm256_set1_ps(ia), xs),
+                        _mm256_mul_ps(_mm256_set1_ps(ic), ys),
+                    ),
+                    _mm256_set1_ps(ie),
+                ),
+                _mm256_set1_ps(pos.start_center[0]),
+            );
+            let py = _mm256_sub_ps(
+                _mm256_add_ps(
+                    _mm256_add_ps(
+                        _mm256_mul_ps(_mm256_set1_ps(ib), xs),
+                        _mm256_mul_ps(_mm256_set1_ps(id), ys),
+                    ),
+                    _mm256_set1_ps(if_),
+                ),
+                _mm256_set1_ps(pos.start_center[1]),
+            );
+
+            // See `RadialPos::t` for the derivation.
+            let b = _mm256_add_ps(
+                _mm256_add_ps(
+                    _mm256_mul_ps(px, _mm256_set1_ps(pos.dc[0])),
+                    _mm256_mul_ps(py, _mm256_set1_ps(pos.dc[1])),
+                ),
+                r0_dr,
+            );
+            let c = _mm256_sub_ps(
+                _mm256_add_ps(_mm256_mul_ps(px, px), _mm256_mul_ps(py, py)),
+                r0_r0,
+            );
+
+            let (t, defined) = if pos.a == 0.0 {
+                let t = _mm256_div_ps(c, _mm256_mul_ps(_mm256_set1_ps(2.0), b));
+                let defined = _mm256_and_ps(_mm256_cmp_ps::<_CMP_NEQ_OQ>(b, zero), is_valid(t));
+
+                (t, defined)
+            } else {
+                let discriminant = _mm256_sub_ps(_mm256_mul_ps(b, b), _mm256_mul_ps(a, c));
+                let sqrt = _mm256_sqrt_ps(discriminant);
+                let t1 = _mm256_div_ps(_mm256_add_ps(b, sqrt), a);
+                let t2 = _mm256_div_ps(_mm256_sub_ps(b, sqrt), a);
+                let t1_larger = _mm256_cmp_ps::<_CMP_GT_OQ>(t1, t2);
+                let large = _mm256_blendv_ps(t2, t1, t1_larger);
+                let small = _mm256_blendv_ps(t1, t2, t1_larger);
+                let large_valid = is_valid(large);
+                let defined = _mm256_and_ps(
+                    _mm256_cmp_ps::<_CMP_GE_OQ>(discriminant, zero),
+                    _mm256_or_ps(large_valid, is_valid(small)),
+                );
+
+                (_mm256_blendv_ps(small, large, large_valid), defined)
+            };
+
+            store(chunk, shade(ramp, extend, t, defined));
+        }
+    }
+
+    /// There is no vectorized version of `atan2`, so the positions are computed per pixel
+    /// and only the color lookup is vectorized.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn sweep(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    ) {
+        for (i, chunk) in buf.chunks_mut(STRIDE).enumerate() {
+            let mut ts = [0.0; 8];
+            let mut defined = [0; 8];
+
+            for lane in 0..8 {
+                let px = (x + 2 * i + lane / 4) as f32 + 0.5;
+                let py = (y + lane % 4) as f32 + 0.5;
+
+                if let Some(t) = pos.t(px, py) {
+                    ts[lane] = t;
+                    defined[lane] = -1;
+                }
+            }
+
+            let t = _mm256_loadu_ps(ts.as_ptr());
+            let defined = _mm256_loadu_si256(defined.as_ptr() as *const __m256i);
+
+            store(chunk, shade(ramp, extend, t, _mm256_castsi256_ps(defined)));
+        }
+    }
+
+    /// The y coordinates of the pixel centers of two columns.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn row_coords(y: usize) -> __m256 {
+        let [y0, y1, y2, y3] = [0, 1, 2, 3].map(|row| (y + row) as f32 + 0.5);
+
+        _mm256_setr_ps(y0, y1, y2, y3, y0, y1, y2, y3)
+    }
+
+    /// The x coordinates of the pixel centers of the columns `x` and `x + 1`.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn column_coords(x: usize) -> __m256 {
+        let x0 = x as f32 + 0.5;
+        let x1 = (x + 1) as f32 + 0.5;
+
+        _mm256_setr_ps(x0, x0, x0, x0, x1, x1, x1, x1)
+    }
+
+    /// Store the pixels, which might only be a single column at the end of the buffer.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(chunk: &mut [u8], pixels: __m256i) {
+        if chunk.len() == STRIDE {
+            _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, pixels);
+        } else {
+            debug_assert_eq!(chunk.len(), TOTAL_STRIP_HEIGHT);
+
+            _mm_storeu_si128(
+                chunk.as_mut_ptr() as *mut __m128i,
+                _mm256_castsi256_si128(pixels),
+            );
+        }
+    }
+
+    /// Map the positions `t` according to the extend mode, look up their colors in the ramp
+    /// and convert them to RGBA8. Pixels that aren't `defined` are set to transparent.
+    ///
+    /// This mirrors `extend` and `ColorRamp::eval`, so that the results are identical.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn shade(ramp: &ColorRamp, extend: Extend, t: __m256, defined: __m256) -> __m256i {
+        let zero = _mm256_setzero_ps();
+        let one = _mm256_set1_ps(1.0);
+        let two = _mm256_set1_ps(2.0);
+
+        // Note that the order of the operands of `min` and `max` matters for NaN values,
+        // which need to be propagated in the same way as in the scalar version.
+        let t = match extend {
+            Extend::Pad => _mm256_min_ps(one, _mm256_max_ps(zero, t)),
+            Extend::Repeat => _mm256_sub_ps(t, _mm256_floor_ps(t)),
+            Extend::Reflect => {
+                // Computing the remainder like this is exact, so it yields the same
+                // result as `rem_euclid`.
+                let half = _mm256_mul_ps(t, _mm256_set1_ps(0.5));
+                let r = _mm256_sub_ps(
+                    t,
+                    _mm256_mul_ps(two, _mm256_round_ps::<_MM_FROUND_TO_ZERO>(half)),
+                );
+                let r = _mm256_blendv_ps(
+                    r,
+                    _mm256_add_ps(r, two),
+                    _mm256_cmp_ps::<_CMP_LT_OQ>(r, zero),
+                );
+
+                _mm256_blendv_ps(
+                    r,
+                    _mm256_sub_ps(two, r),
+                    _mm256_cmp_ps::<_CMP_GT_OQ>(r, one),
+                )
+            }
+        };
+
+        let Some((first_offset, first_color)) = ramp.stops.first() else {
+            return _mm256_setzero_si256();
+        };
+        let (_, last_color) = ramp.stops[ramp.stops.len() - 1];
+
+        // Pixels whose position lies beyond the last stop (or is NaN) keep the last color.
+        let mut color = [zero; COLOR_COMPONENTS];
+        let mut done = _mm256_cmp_ps::<_CMP_LE_OQ>(t, _mm256_set1_ps(*first_offset));
+
+        for i in 0..COLOR_COMPONENTS {
+            color[i] = _mm256_blendv_ps(
+                _mm256_set1_ps(last_color[i]),
+                _mm256_set1_ps(first_color[i]),
+                done,
+            );
+        }
+
+        for w in ramp.stops.windows(2) {
+            if _mm256_movemask_ps(done) == 0xff {
+                break;
+            }
+
+            let (o0, c0) = &w[0];
+            let (o1, c1) = &w[1];
+            let in_segment =
+                _mm256_andnot_ps(done, _mm256_cmp_ps::<_CMP_LE_OQ>(t, _mm256_set1_ps(*o1)));
+            let mut segment = [zero; COLOR_COMPONENTS];
+
+            if o1 - o0 <= 0.0 {
+                for i in 0..COLOR_COMPONENTS {
+                    segment[i] = _mm256_set1_ps(c1[i]);
+                }
+            } else {
+                let f = _mm256_div_ps(
+                    _mm256_sub_ps(t, _mm256_set1_ps(*o0)),
+                    _mm256_set1_ps(o1 - o0),
+                );
+
+                for i in 0..COLOR_COMPONENTS {
+                    segment[i] = _mm256_add_ps(
+                        _mm256_set1_ps(c0[i]),
+                        _mm256_mul_ps(_mm256_set1_ps(c1[i] - c0[i]), f),
+                    );
+                }
+
+                for i in 0..COLOR_COMPONENTS - 1 {
+                    segment[i] = _mm256_min_ps(segment[i], segment[3]);
+                }
+            }
+
+            for i in 0..COLOR_COMPONENTS {
+                color[i] = _mm256_blendv_ps(color[i], segment[i], in_segment);
+            }
+
+            done = _mm256_or_ps(done, in_segment);
+        }
+
+        let to_u8 = |c: __m256| {
+            let c = _mm256_add_ps(_mm256_mul_ps(c, _mm256_set1_ps(255.0)), _mm256_set1_ps(0.5));
+            // Saturate like an `as u8` cast, which maps NaN to 0.
+            let c = _mm256_min_ps(_mm256_max_ps(c, zero), _mm256_set1_ps(255.0));
+
+            _mm256_cvttps_epi32(c)
+        };
+
+        let pixels = _mm256_or_si256(
+            _mm256_or_si256(to_u8(color[0]), _mm256_slli_epi32::<8>(to_u8(color[1]))),
+            _mm256_or_si256(
+                _mm256_slli_epi32::<16>(to_u8(color[2])),
+                _mm256_slli_epi32::<24>(to_u8(color[3])),
+            ),
+        );
+
+        _mm256_and_si256(pixels, _mm256_castps_si256(defined))
+    }
 }
@@ -0,0 +1,331 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Per-pixel evaluation of gradients.
+
+use crate::color::Srgb;
+use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+use crate::kurbo::Affine;
+use crate::paint::{LinearGradient, Paint, RadialGradient, SweepGradient};
+use peniko::{ColorStop, Extend};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// The color stops of a gradient, converted to premultiplied RGBA.
+#[derive(Debug, Default)]
+pub struct ColorRamp {
+    /// The paint the ramp has been built from. Holding on to it keeps its allocation
+    /// alive, so that a different paint can never end up at the same address.
+    source: Option<Paint>,
+    pub(crate) stops: Vec<(f32, [f32; COLOR_COMPONENTS])>,
+}
+
+impl ColorRamp {
+    /// Reset the ramp to the color stops of the given gradient paint. This is a no-op
+    /// if the ramp has already been built from the same paint.
+    pub(crate) fn set(&mut self, paint: &Paint) {
+        let stops: &[ColorStop] = match (paint, &self.source) {
+            (Paint::LinearGradient(g), Some(Paint::LinearGradient(s))) if Arc::ptr_eq(g, s) => {
+                return;
+            }
+            (Paint::RadialGradient(g), Some(Paint::RadialGradient(s))) if Arc::ptr_eq(g, s) => {
+                return;
+            }
+            (Paint::SweepGradient(g), Some(Paint::SweepGradient(s))) if Arc::ptr_eq(g, s) => {
+                return;
+            }
+            (Paint::LinearGradient(g), _) => &g.stops,
+            (Paint::RadialGradient(g), _) => &g.stops,
+            (Paint::SweepGradient(g), _) => &g.stops,
+            (Paint::Solid(_) | Paint::Image(_), _) => &[],
+        };
+
+        self.stops.clear();
+        self.stops.extend(stops.iter().map(|s| {
+            let color = s.color.to_alpha_color::<Srgb>().premultiply();
+            (s.offset, color.components)
+        }));
+        self.source = Some(paint.clone());
+    }
+
+    /// Evaluate the ramp at position `t`, which should already have been mapped
+    /// into the range [0, 1] according to the extend mode.
+    pub(crate) fn eval(&self, t: f32) -> [u8; COLOR_COMPONENTS] {
+        let Some(first) = self.stops.first() else {
+            return [0; COLOR_COMPONENTS];
+        };
+
+        if t <= first.0 {
+            return to_rgba8(&first.1);
+        }
+
+        for w in self.stops.windows(2) {
+            let (o0, c0) = &w[0];
+            let (o1, c1) = &w[1];
+
+            if t <= *o1 {
+                // Stops at the same offset create a hard transition.
+                if o1 - o0 <= 0.0 {
+                    return to_rgba8(c1);
+                }
+
+                let f = (t - o0) / (o1 - o0);
+                let mut color = [0.0; COLOR_COMPONENTS];
+
+                for i in 0..COLOR_COMPONENTS {
+                    color[i] = c0[i] + (c1[i] - c0[i]) * f;
+                }
+
+                // Make sure rounding errors don't cause the color channels to exceed the
+                // alpha channel, which the compositing kernels rely on.
+                for i in 0..COLOR_COMPONENTS - 1 {
+                    color[i] = color[i].min(color[3]);
+                }
+
+                return to_rgba8(&color);
+            }
+        }
+
+        to_rgba8(&self.stops[self.stops.len() - 1].1)
+    }
+}
+
+/// Map a gradient position into the range [0, 1] according to the extend mode.
+#[inline(always)]
+pub(crate) fn extend(t: f32, extend: Extend) -> f32 {
+    match extend {
+        Extend::Pad => t.clamp(0.0, 1.0),
+        Extend::Repeat => t - t.floor(),
+        Extend::Reflect => {
+            let t = t.rem_euclid(2.0);
+
+            if t > 1.0 {
+                2.0 - t
+            } else {
+                t
+            }
+        }
+    }
+}
+
+/// The gradient position as an affine function of the pixel coordinates, i.e.
+/// `t = a * x + b * y + c`.
+pub struct LinearPos {
+    pub(crate) a: f32,
+    pub(crate) b: f32,
+    pub(crate) c: f32,
+}
+
+impl LinearPos {
+    /// Returns `None` if the gradient is degenerate or the transform isn't invertible,
+    /// in which case nothing should be drawn.
+    pub(crate) fn new(gradient: &LinearGradient) -> Option<Self> {
+        let inv = invert(gradient.transform)?;
+        let d = gradient.end - gradient.start;
+        let dd = d.hypot2();
+
+        if dd == 0.0 {
+            return None;
+        }
+
+        // Project the point from paint space (which we get by applying the inverse
+        // transform to the pixel coordinates) onto the gradient vector.
+        let [ia, ib, ic, id, ie, if_] = inv.as_coeffs();
+        let a = (ia * d.x + ib * d.y) / dd;
+        let b = (ic * d.x + id * d.y) / dd;
+        let c = ((ie - gradient.start.x) * d.x + (if_ - gradient.start.y) * d.y) / dd;
+
+        Some(Self {
+            a: a as f32,
+            b: b as f32,
+            c: c as f32,
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn t(&self, x: f32, y: f32) -> Option<f32> {
+        Some(self.a * x + self.b * y + self.c)
+    }
+}
+
+/// Solves for the gradient position of a two-point conical gradient.
+pub struct RadialPos {
+    /// The inverse transform, mapping from pixel coordinates to gradient space.
+    pub(crate) inv: [f32; 6],
+    pub(crate) start_center: [f32; 2],
+    /// The vector from the start center to the end center.
+    pub(crate) dc: [f32; 2],
+    pub(crate) start_radius: f32,
+    /// The difference between the end radius and the start radius.
+    pub(crate) dr: f32,
+    /// The (constant) quadratic coefficient of the equation we need to solve.
+    pub(crate) a: f32,
+}
+
+impl RadialPos {
+    /// Returns `None` if the transform isn't invertible, in which case nothing
+    /// should be drawn.
+    pub(crate) fn new(gradient: &RadialGradient) -> Option<Self> {
+        let inv = invert(gradient.transform)?.as_coeffs().map(|c| c as f32);
+        let dc = gradient.end_center - gradient.start_center;
+        let dr = gradient.end_radius - gradient.start_radius;
+        let a = dc.hypot2() as f32 - dr * dr;
+
+        Some(Self {
+            inv,
+            start_center: [
+                gradient.start_center.x as f32,
+                gradient.start_center.y as f32,
+            ],
+            dc: [dc.x as f32, dc.y as f32],
+            start_radius: gradient.start_radius,
+            dr,
+            a,
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn t(&self, x: f32, y: f32) -> Option<f32> {
+        let [ia, ib, ic, id, ie, if_] = self.inv;
+        let px = ia * x + ic * y + ie - self.start_center[0];
+        let py = ib * x + id * y + if_ - self.start_center[1];
+
+        // We are looking for the largest `t` such that the point lies on the circle
+        // with center `c0 + t * dc` and radius `r0 + t * dr`, i.e.
+        // `|p - t * dc|^2 = (r0 + t * dr)^2`. Expanding yields the quadratic equation
+        // `a * t^2 - 2 * b * t + c = 0`.
+        let b = px * self.dc[0] + py * self.dc[1] + self.start_radius * self.dr;
+        let c = px * px + py * py - self.start_radius * self.start_radius;
+        let is_valid = |t: f32| self.start_radius + t * self.dr >= 0.0;
+
+        if self.a == 0.0 {
+            if b == 0.0 {
+                return None;
+            }
+
+            let t = c / (2.0 * b);
+
+            return is_valid(t).then_some(t);
+        }
+
+        let discriminant = b * b - self.a * c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrt = discriminant.sqrt();
+        let t1 = (b + sqrt) / self.a;
+        let t2 = (b - sqrt) / self.a;
+        let (large, small) = if t1 > t2 { (t1, t2) } else { (t2, t1) };
+
+        if is_valid(large) {
+            Some(large)
+        } else if is_valid(small) {
+            Some(small)
+        } else {
+            None
+        }
+    }
+}
+
+/// Computes the gradient position of a sweep gradient from the angle of a point.
+pub struct SweepPos {
+    /// The inverse transform, mapping from pixel coordinates to gradient space.
+    inv: [f32; 6],
+    center: [f32; 2],
+    start_angle: f32,
+    /// Either 1.0 or -1.0, depending on the direction of the sweep.
+    direction: f32,
+    /// The reciprocal of the absolute angle between the start and end angle.
+    inv_span: f32,
+}
+
+impl SweepPos {
+    /// Returns `None` if the gradient is degenerate or the transform isn't invertible,
+    /// in which case nothing should be drawn.
+    pub(crate) fn new(gradient: &SweepGradient) -> Option<Self> {
+        let inv = invert(gradient.transform)?.as_coeffs().map(|c| c as f32);
+        let span = gradient.end_angle - gradient.start_angle;
+
+        if span == 0.0 || !span.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            inv,
+            center: [gradient.center.x as f32, gradient.center.y as f32],
+            start_angle: gradient.start_angle,
+            direction: span.signum(),
+            inv_span: 1.0 / span.abs(),
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn t(&self, x: f32, y: f32) -> Option<f32> {
+        let [ia, ib, ic, id, ie, if_] = self.inv;
+        let px = ia * x + ic * y + ie - self.center[0];
+        let py = ib * x + id * y + if_ - self.center[1];
+
+        // Measure the angle relative to the start angle in the direction of the sweep,
+        // so that the seam always lies at the start angle, regardless of how
+        // the angles are represented.
+        let angle = (self.direction * (py.atan2(px) - self.start_angle)).rem_euclid(TAU);
+
+        // `rem_euclid` can return exactly `TAU` due to rounding, which would otherwise
+        // wrongly end up on the other side of the seam.
+        let angle = if angle >= TAU { 0.0 } else { angle };
+
+        Some(angle * self.inv_span)
+    }
+}
+
+/// Evaluate a gradient for each pixel in `buf`, which has the same layout as the
+/// scratch buffer. `x` and `y` are the absolute pixel coordinates of the top-left pixel.
+///
+/// Pixels for which the gradient is undefined are set to transparent.
+pub(crate) fn fill_gradient(
+    buf: &mut [u8],
+    ramp: &ColorRamp,
+    ext: Extend,
+    x: usize,
+    y: usize,
+    pos: impl Fn(f32, f32) -> Option<f32>,
+) {
+    for (col, column) in buf.chunks_exact_mut(TOTAL_STRIP_HEIGHT).enumerate() {
+        // Sample at the pixel centers.
+        let px = (x + col) as f32 + 0.5;
+
+        for (row, pixel) in column.chunks_exact_mut(COLOR_COMPONENTS).enumerate() {
+            let py = (y + row) as f32 + 0.5;
+
+            let color = match pos(px, py) {
+                Some(t) => ramp.eval(extend(t, ext)),
+                None => [0; COLOR_COMPONENTS],
+            };
+
+            pixel.copy_from_slice(&color);
+        }
+    }
+}
+
+/// Invert a transform, returning `None` if it isn't invertible.
+pub(crate) fn invert(transform: Affine) -> Option<Affine> {
+    let det = transform.determinant();
+
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+
+    Some(transform.inverse())
+}
+
+#[inline(always)]
+fn to_rgba8(color: &[f32; COLOR_COMPONENTS]) -> [u8; COLOR_COMPONENTS] {
+    [
+        (color[0] * 255.0 + 0.5) as u8,
+        (color[1] * 255.0 + 0.5) as u8,
+        (color[2] * 255.0 + 0.5) as u8,
+        (color[3] * 255.0 + 0.5) as u8,
+    ]
+}
@@ -0,0 +1,187 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Per-pixel sampling of images.
+
+use crate::fine::gradient::invert;
+use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+use crate::paint::Image;
+use crate::pixmap::Pixmap;
+use peniko::{Extend, ImageQuality};
+
+/// Sample an image for each pixel in `buf`, which has the same layout as the
+/// scratch buffer. `x` and `y` are the absolute pixel coordinates of the top-left pixel.
+pub(crate) fn fill_image(buf: &mut [u8], image: &Image, x: usize, y: usize) {
+    let pixmap = image.pixmap();
+
+    let inv = match invert(image.transform) {
+        Some(inv) if pixmap.width > 0 && pixmap.height > 0 => inv,
+        _ => {
+            buf.fill(0);
+            return;
+        }
+    };
+
+    let [a, b, c, d, e, f] = inv.as_coeffs().map(|c| c as f32);
+    let alpha = image.alpha.clamp(0.0, 1.0);
+    let sampler = Sampler {
+        pixmap,
+        x_extend: image.x_extend,
+        y_extend: image.y_extend,
+    };
+
+    for (col, column) in buf.chunks_exact_mut(TOTAL_STRIP_HEIGHT).enumerate() {
+        // Sample at the pixel centers.
+        let px = (x + col) as f32 + 0.5;
+
+        for (row, pixel) in column.chunks_exact_mut(COLOR_COMPONENTS).enumerate() {
+            let py = (y + row) as f32 + 0.5;
+            let u = a * px + c * py + e;
+            let v = b * px + d * py + f;
+
+            // The inverse transform can overflow `f32` for extreme downscaling, in which case
+            // there is no meaningful position to sample.
+            if !(u.is_finite() && v.is_finite()) {
+                pixel.fill(0);
+                continue;
+            }
+
+            let mut color = match image.quality {
+                ImageQuality::Low => sampler.nearest(u, v),
+                ImageQuality::Medium => sampler.bilinear(u, v),
+                ImageQuality::High => sampler.bicubic(u, v),
+            };
+
+            if alpha != 1.0 {
+                color = color.map(|c| (c as f32 * alpha + 0.5) as u8);
+            }
+
+            pixel.copy_from_slice(&color);
+        }
+    }
+}
+
+struct Sampler<'a> {
+    pixmap: &'a Pixmap,
+    x_extend: Extend,
+    y_extend: Extend,
+}
+
+impl Sampler<'_> {
+    #[inline(always)]
+    fn texel(&self, x: i32, y: i32) -> &[u8] {
+        let x = extend(x, self.pixmap.width, self.x_extend);
+        let y = extend(y, self.pixmap.height, self.y_extend);
+        let idx = (y * self.pixmap.width + x) * COLOR_COMPONENTS;
+
+        &self.pixmap.buf[idx..][..COLOR_COMPONENTS]
+    }
+
+    fn nearest(&self, u: f32, v: f32) -> [u8; COLOR_COMPONENTS] {
+        let mut color = [0; COLOR_COMPONENTS];
+        color.copy_from_slice(self.texel(u.floor() as i32, v.floor() as i32));
+
+        color
+    }
+
+    fn bilinear(&self, u: f32, v: f32) -> [u8; COLOR_COMPONENTS] {
+        // Texel centers lie at half-integer coordinates.
+        let (u, v) = (u - 0.5, v - 0.5);
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+
+        self.weighted(x0 as i32, y0 as i32, &[1.0 - fx, fx], &[1.0 - fy, fy])
+    }
+
+    fn bicubic(&self, u: f32, v: f32) -> [u8; COLOR_COMPONENTS] {
+        let (u, v) = (u - 0.5, v - 0.5);
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        let wx = [-1.0, 0.0, 1.0, 2.0].map(|o| mitchell(fx - o));
+        let wy = [-1.0, 0.0, 1.0, 2.0].map(|o| mitchell(fy - o));
+
+        self.weighted(
+            (x0 as i32).saturating_sub(1),
+            (y0 as i32).saturating_sub(1),
+            &wx,
+            &wy,
+        )
+    }
+
+    /// Compute the weighted sum of the texels in the rectangle starting at `(x0, y0)`.
+    ///
+    /// The conversion of huge sample coordinates to `i32` saturates, so the offsets need
+    /// to saturate as well. At that magnitude, `f32` can't distinguish neighboring texels
+    /// anyway.
+    fn weighted(&self, x0: i32, y0: i32, wx: &[f32], wy: &[f32]) -> [u8; COLOR_COMPONENTS] {
+        let mut color = [0.0f32; COLOR_COMPONENTS];
+
+        for (j, wy) in wy.iter().enumerate() {
+            for (i, wx) in wx.iter().enumerate() {
+                let texel = self.texel(x0.saturating_add(i as i32), y0.saturating_add(j as i32));
+                let w = wx * wy;
+
+                for c in 0..COLOR_COMPONENTS {
+                    color[c] += texel[c] as f32 * w;
+                }
+            }
+        }
+
+        // Filters with negative lobes can over- and undershoot, so we need to make sure
+        // that the result is still a valid premultiplied color.
+        let alpha = color[3].clamp(0.0, 255.0);
+
+        [
+            (color[0].clamp(0.0, alpha) + 0.5) as u8,
+            (color[1].clamp(0.0, alpha) + 0.5) as u8,
+            (color[2].clamp(0.0, alpha) + 0.5) as u8,
+            (alpha + 0.5) as u8,
+        ]
+    }
+}
+
+/// Map a texel coordinate into the range `0..size` according to the extend mode.
+#[inline(always)]
+fn extend(coord: i32, size: usize, extend: Extend) -> usize {
+    let size = size as i32;
+
+    let coord = match extend {
+        Extend::Pad => coord.clamp(0, size - 1),
+        Extend::Repeat => coord.rem_euclid(size),
+        Extend::Reflect => {
+            let coord = coord.rem_euclid(2 * size);
+
+            if coord >= size {
+                2 * size - 1 - coord
+            } else {
+                coord
+            }
+        }
+    };
+
+    coord as usize
+}
+
+/// The Mitchell-Netravali filter with `B = C = 1/3`.
+#[inline(always)]
+fn mitchell(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+
+    let x = x.abs();
+
+    if x < 1.0 {
+        ((12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B))
+            / 6.0
+    } else if x < 2.0 {
+        ((-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    } else {
+        0.0
+    }
+}
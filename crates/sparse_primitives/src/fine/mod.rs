@@ -5,14 +5,22 @@
 
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
 pub(crate) mod avx2;
+pub(crate) mod gradient;
+pub(crate) mod image;
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
 pub(crate) mod neon;
 pub(crate) mod scalar;
 
 use crate::execute::KernelExecutor;
+use crate::fine::gradient::{ColorRamp, LinearPos, RadialPos, SweepPos};
+use crate::fine::image::fill_image;
 use crate::paint::Paint;
+use crate::pixmap::unpremultiply;
+use crate::target::{AlphaMode, PixelFormat, TargetLayout};
+use crate::util::scalar::div_255;
 use crate::util::ColorExt;
-use crate::wide_tile::{Cmd, STRIP_HEIGHT, WIDE_TILE_WIDTH};
+use crate::wide_tile::{Cmd, MaskType, STRIP_HEIGHT, WIDE_TILE_WIDTH};
+use peniko::{BlendMode, Extend, Mix};
 use std::marker::PhantomData;
 
 pub(crate) const COLOR_COMPONENTS: usize = 4;
@@ -29,29 +37,153 @@ pub trait Compose {
         alphas: &[u32],
         compose: peniko::Compose,
     );
+    /// Like `compose_fill`, but with a separate source color for each pixel. `src` has
+    /// the same layout and length as `target`.
+    fn compose_fill_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose);
+    /// Like `compose_strip`, but with a separate source color for each pixel. `src` has
+    /// the same layout and length as `target`.
+    fn compose_strip_buf(target: &mut [u8], src: &[u8], alphas: &[u32], compose: peniko::Compose);
+    /// Blend the source colors in `src` with the backdrop `cb` according to the mix mode,
+    /// replacing them with the mixed source colors. The alpha channel stays the same, so
+    /// the result can then be composed like any other source.
+    ///
+    /// See https://www.w3.org/TR/compositing-1/#blending for the formulas.
+    fn mix(src: &mut [u8], cb: &[u8], mix: peniko::Mix);
+}
+
+/// Evaluation of gradients. `buf` has the same layout as the scratch buffer, and `x` and
+/// `y` are the absolute pixel coordinates of its top-left pixel. Pixels for which the
+/// gradient is undefined are set to transparent.
+///
+/// All implementations need to produce exactly the same result as `fill_gradient`.
+pub trait Gradient {
+    fn linear_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    );
+    fn radial_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    );
+    fn sweep_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    );
+}
+
+/// A factor in the Porter-Duff compositing equation `co = cs * Fa + cb * Fb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Factor {
+    Zero,
+    One,
+    SrcAlpha,
+    InvSrcAlpha,
+    DestAlpha,
+    InvDestAlpha,
+}
+
+impl Factor {
+    /// Evaluate the factor, given the source and backdrop alpha as u8 values.
+    #[inline(always)]
+    pub(crate) fn eval(self, _as: u16, ab: u16) -> u16 {
+        match self {
+            Factor::Zero => 0,
+            Factor::One => 255,
+            Factor::SrcAlpha => _as,
+            Factor::InvSrcAlpha => 255 - _as,
+            Factor::DestAlpha => ab,
+            Factor::InvDestAlpha => 255 - ab,
+        }
+    }
+}
+
+/// Return the factors `Fa` and `Fb` for a compose operator.
+///
+/// See https://www.w3.org/TR/compositing-1/#porterduffcompositingoperators for the
+/// formulas. `Plus` and `PlusLighter` both add the source and backdrop, clamping the
+/// result.
+pub(crate) fn porter_duff_factors(compose: peniko::Compose) -> (Factor, Factor) {
+    use peniko::Compose;
+
+    match compose {
+        Compose::Clear => (Factor::Zero, Factor::Zero),
+        Compose::Copy => (Factor::One, Factor::Zero),
+        Compose::Dest => (Factor::Zero, Factor::One),
+        Compose::SrcOver => (Factor::One, Factor::InvSrcAlpha),
+        Compose::DestOver => (Factor::InvDestAlpha, Factor::One),
+        Compose::SrcIn => (Factor::DestAlpha, Factor::Zero),
+        Compose::DestIn => (Factor::Zero, Factor::SrcAlpha),
+        Compose::SrcOut => (Factor::InvDestAlpha, Factor::Zero),
+        Compose::DestOut => (Factor::Zero, Factor::InvSrcAlpha),
+        Compose::SrcAtop => (Factor::DestAlpha, Factor::InvSrcAlpha),
+        Compose::DestAtop => (Factor::InvDestAlpha, Factor::SrcAlpha),
+        Compose::Xor => (Factor::InvDestAlpha, Factor::InvSrcAlpha),
+        Compose::Plus | Compose::PlusLighter => (Factor::One, Factor::One),
+    }
 }
 
 pub struct Fine<'a, T: KernelExecutor> {
-    pub(crate) width: usize,
-    pub(crate) height: usize,
+    /// The size and pixel layout of `out_buf`.
+    pub(crate) layout: TargetLayout,
     pub(crate) out_buf: &'a mut [u8],
     pub(crate) scratch: ScratchBuf,
+    /// The coordinates of the wide tile that is currently being rendered.
+    pub(crate) wide_coords: (usize, usize),
+    /// Holds the per-pixel colors of non-solid paints.
+    pub(crate) paint_buf: ScratchBuf,
+    pub(crate) color_ramp: ColorRamp,
+    /// The buffers below the current scratch buffer, which have been pushed for layers.
+    pub(crate) layers: Vec<ScratchBuf>,
     phantom_data: PhantomData<T>,
 }
 
 impl<'a, KE: KernelExecutor> Fine<'a, KE> {
     pub fn new(width: usize, height: usize, out_buf: &'a mut [u8]) -> Self {
+        let layout = TargetLayout {
+            width,
+            height,
+            stride: width * COLOR_COMPONENTS,
+            format: PixelFormat::Rgba8,
+            alpha_mode: AlphaMode::Premultiplied,
+        };
+
+        Self::new_with_layout(layout, out_buf)
+    }
+
+    /// Create a new fine rasterizer that writes into a buffer with the given layout.
+    pub(crate) fn new_with_layout(layout: TargetLayout, out_buf: &'a mut [u8]) -> Self {
         let scratch = [0; SCRATCH_BUF_SIZE];
+        let paint_buf = [0; SCRATCH_BUF_SIZE];
 
         Self {
-            width,
-            height,
+            layout,
             out_buf,
             scratch,
+            wide_coords: (0, 0),
+            paint_buf,
+            color_ramp: ColorRamp::default(),
+            layers: vec![],
             phantom_data: PhantomData::default(),
         }
     }
 
+    /// Set the coordinates of the wide tile that is rendered next.
+    pub fn set_coords(&mut self, x: usize, y: usize) {
+        self.wide_coords = (x, y);
+    }
+
     #[inline(never)]
     pub fn clear(&mut self, premul_color: [u8; 4]) {
         if premul_color[0] == premul_color[1]
@@ -67,25 +199,149 @@ impl<'a, KE: KernelExecutor> Fine<'a, KE> {
         }
     }
 
+    /// Copy the current wide tile into the output buffer, with its top-left pixel at
+    /// `(x, y)`. Pixels that lie outside of the output buffer are skipped.
     #[inline(never)]
-    pub(crate) fn pack(&mut self, x: usize, y: usize) {
-        pack(self.out_buf, &self.scratch, self.width, self.height, x, y);
+    pub(crate) fn pack(&mut self, x: isize, y: isize) {
+        pack(self.out_buf, &self.scratch, &self.layout, x, y);
     }
 
-    pub(crate) fn run_cmd(&mut self, cmd: &Cmd, alphas: &[u32], compose: peniko::Compose) {
+    pub(crate) fn run_cmd(&mut self, cmd: &Cmd, alphas: &[u32]) {
         match cmd {
             Cmd::Fill(f) => {
-                self.fill(f.x as usize, f.width as usize, &f.paint, compose);
+                self.fill(f.x as usize, f.width as usize, &f.paint, f.blend_mode);
             }
             Cmd::Strip(s) => {
                 let aslice = &alphas[s.alpha_ix..];
-                self.strip(s.x as usize, s.width as usize, aslice, &s.paint, compose);
+                self.strip(
+                    s.x as usize,
+                    s.width as usize,
+                    aslice,
+                    &s.paint,
+                    s.blend_mode,
+                );
+            }
+            Cmd::PushBuf => self.push_buf(),
+            Cmd::PushClipBuf => self.push_clip_buf(),
+            Cmd::PopBuf(p) => self.pop_buf(p.blend_mode, p.alpha),
+            Cmd::ClipFill(c) => self.clip_fill(c.x as usize, c.width as usize),
+            Cmd::ClipStrip(c) => {
+                let aslice = &alphas[c.alpha_ix..];
+                self.clip_strip(c.x as usize, c.width as usize, aslice);
             }
+            Cmd::DropBuf => self.drop_buf(),
+            Cmd::PopMask(m) => self.pop_mask(*m),
         }
     }
 
+    /// Save the current scratch buffer and start with a transparent one.
+    pub fn push_buf(&mut self) {
+        self.layers.push(self.scratch);
+        self.scratch.fill(0);
+    }
+
+    /// Save the current scratch buffer and continue drawing into a copy of it.
+    pub fn push_clip_buf(&mut self) {
+        self.layers.push(self.scratch);
+    }
+
+    /// Composite the current scratch buffer into the one that was saved last, using the
+    /// given blend mode and opacity.
+    pub fn pop_buf(&mut self, blend_mode: BlendMode, alpha: f32) {
+        let parent = self.layers.pop().expect("no buffer to pop");
+
+        // The contents of the layer become the source color.
+        self.paint_buf = self.scratch;
+        self.scratch = parent;
+
+        if alpha != 1.0 {
+            let alpha = (alpha.clamp(0.0, 1.0) * 255.0 + 0.5) as u16;
+
+            for c in &mut self.paint_buf {
+                *c = div_255(*c as u16 * alpha) as u8;
+            }
+        }
+
+        if !is_normal(blend_mode.mix) {
+            KE::mix(&mut self.paint_buf, &self.scratch, blend_mode.mix);
+        }
+
+        KE::compose_fill_buf(&mut self.scratch, &self.paint_buf, blend_mode.compose);
+    }
+
+    /// Copy the columns `x..x + width` of the current scratch buffer into the one that was
+    /// saved last.
+    pub fn clip_fill(&mut self, x: usize, width: usize) {
+        let parent = self.layers.last_mut().expect("no buffer to composite into");
+        let src = &self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+        let target = &mut parent[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+        KE::compose_fill_buf(target, src, peniko::Compose::Copy);
+    }
+
+    /// Like `clip_fill`, but interpolating between the two buffers using the coverage
+    /// in `alphas`.
+    pub fn clip_strip(&mut self, x: usize, width: usize, alphas: &[u32]) {
+        debug_assert!(alphas.len() >= width);
+
+        let parent = self.layers.last_mut().expect("no buffer to composite into");
+        let src = &self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+        let target = &mut parent[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+        KE::compose_strip_buf(target, src, alphas, peniko::Compose::Copy);
+    }
+
+    /// Discard the current scratch buffer and continue with the one that was saved last.
+    pub fn drop_buf(&mut self) {
+        self.scratch = self.layers.pop().expect("no buffer to pop");
+    }
+
+    /// Modulate the current scratch buffer by the mask in the buffer that was saved last,
+    /// and composite it into the buffer below the mask.
+    pub fn pop_mask(&mut self, mask_type: MaskType) {
+        let mask = self.layers.pop().expect("no mask to pop");
+        let parent = self.layers.pop().expect("no buffer to pop");
+
+        self.paint_buf = self.scratch;
+        self.scratch = parent;
+
+        for (c, m) in self
+            .paint_buf
+            .chunks_exact_mut(COLOR_COMPONENTS)
+            .zip(mask.chunks_exact(COLOR_COMPONENTS))
+        {
+            let m = match mask_type {
+                MaskType::Alpha => m[3] as u16,
+                // The components are premultiplied, so this already includes the alpha
+                // channel. The coefficients add up to 255.
+                MaskType::Luminance => {
+                    div_255(54 * m[0] as u16 + 183 * m[1] as u16 + 18 * m[2] as u16)
+                }
+            };
+
+            for c in c {
+                *c = div_255(*c as u16 * m) as u8;
+            }
+        }
+
+        KE::compose_fill_buf(&mut self.scratch, &self.paint_buf, peniko::Compose::SrcOver);
+    }
+
     #[inline(never)]
-    pub fn fill(&mut self, x: usize, width: usize, paint: &Paint, compose: peniko::Compose) {
+    pub fn fill(&mut self, x: usize, width: usize, paint: &Paint, blend_mode: BlendMode) {
+        let compose = blend_mode.compose;
+
+        if !is_normal(blend_mode.mix) {
+            self.paint_mixed(x, width, paint, blend_mode.mix);
+
+            let src = &self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+            let target = &mut self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+            KE::compose_fill_buf(target, src, compose);
+
+            return;
+        }
+
         match paint {
             Paint::Solid(c) => {
                 let color = c.premultiply().to_rgba8_fast();
@@ -93,8 +349,14 @@ impl<'a, KE: KernelExecutor> Fine<'a, KE> {
                 let target =
                     &mut self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
 
-                // If color is completely opaque we can just memcopy the colors.
-                if color[3] == 255 {
+                // If the source replaces the backdrop, we can just memcopy the colors.
+                let replaces = match compose {
+                    peniko::Compose::Copy => true,
+                    peniko::Compose::SrcOver => color[3] == 255,
+                    _ => false,
+                };
+
+                if replaces {
                     for t in target.chunks_exact_mut(COLOR_COMPONENTS) {
                         t.copy_from_slice(&color);
                     }
@@ -104,6 +366,15 @@ impl<'a, KE: KernelExecutor> Fine<'a, KE> {
 
                 KE::compose_fill(target, &color, compose);
             }
+            _ => {
+                self.paint_non_solid(x, width, paint);
+
+                let src = &self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+                let target =
+                    &mut self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+                KE::compose_fill_buf(target, src, compose);
+            }
         }
     }
 
@@ -114,10 +385,23 @@ impl<'a, KE: KernelExecutor> Fine<'a, KE> {
         width: usize,
         alphas: &[u32],
         paint: &Paint,
-        compose: peniko::Compose,
+        blend_mode: BlendMode,
     ) {
         debug_assert!(alphas.len() >= width);
 
+        let compose = blend_mode.compose;
+
+        if !is_normal(blend_mode.mix) {
+            self.paint_mixed(x, width, paint, blend_mode.mix);
+
+            let src = &self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+            let target = &mut self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+            KE::compose_strip_buf(target, src, alphas, compose);
+
+            return;
+        }
+
         match paint {
             Paint::Solid(s) => {
                 let color = s.premultiply().to_rgba8_fast();
@@ -127,30 +411,114 @@ impl<'a, KE: KernelExecutor> Fine<'a, KE> {
 
                 KE::compose_strip(target, &color, alphas, compose);
             }
+            _ => {
+                self.paint_non_solid(x, width, paint);
+
+                let src = &self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+                let target =
+                    &mut self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+                KE::compose_strip_buf(target, src, alphas, compose);
+            }
+        }
+    }
+
+    /// Evaluate a paint for the columns `x..x + width` of the current wide tile, blend it
+    /// with the backdrop in the scratch buffer and store the result at the start of
+    /// `paint_buf`.
+    fn paint_mixed(&mut self, x: usize, width: usize, paint: &Paint, mix: Mix) {
+        match paint {
+            Paint::Solid(c) => {
+                let color = c.premultiply().to_rgba8_fast();
+
+                for p in
+                    self.paint_buf[..TOTAL_STRIP_HEIGHT * width].chunks_exact_mut(COLOR_COMPONENTS)
+                {
+                    p.copy_from_slice(&color);
+                }
+            }
+            _ => self.paint_non_solid(x, width, paint),
+        }
+
+        let src = &mut self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+        let cb = &self.scratch[x * TOTAL_STRIP_HEIGHT..][..TOTAL_STRIP_HEIGHT * width];
+
+        KE::mix(src, cb, mix);
+    }
+
+    /// Evaluate a non-solid paint for the columns `x..x + width` of the current wide tile
+    /// and store the result at the start of `paint_buf`.
+    fn paint_non_solid(&mut self, x: usize, width: usize, paint: &Paint) {
+        let buf = &mut self.paint_buf[..TOTAL_STRIP_HEIGHT * width];
+        let px = self.wide_coords.0 * WIDE_TILE_WIDTH + x;
+        let py = self.wide_coords.1 * STRIP_HEIGHT;
+
+        match paint {
+            Paint::Solid(_) => unreachable!(),
+            Paint::LinearGradient(g) => match LinearPos::new(g) {
+                Some(pos) => {
+                    self.color_ramp.set(paint);
+                    KE::linear_gradient(buf, &self.color_ramp, g.extend, &pos, px, py);
+                }
+                None => buf.fill(0),
+            },
+            Paint::RadialGradient(g) => match RadialPos::new(g) {
+                Some(pos) => {
+                    self.color_ramp.set(paint);
+                    KE::radial_gradient(buf, &self.color_ramp, g.extend, &pos, px, py);
+                }
+                None => buf.fill(0),
+            },
+            Paint::SweepGradient(g) => match SweepPos::new(g) {
+                Some(pos) => {
+                    self.color_ramp.set(paint);
+                    KE::sweep_gradient(buf, &self.color_ramp, g.extend, &pos, px, py);
+                }
+                None => buf.fill(0),
+            },
+            Paint::Image(i) => fill_image(buf, i, px, py),
         }
     }
 }
 
-fn pack(out_buf: &mut [u8], scratch: &ScratchBuf, width: usize, height: usize, x: usize, y: usize) {
-    let base_ix = (y * STRIP_HEIGHT * width + x * WIDE_TILE_WIDTH) * COLOR_COMPONENTS;
+/// Whether the source color is used as is, without blending it with the backdrop.
+fn is_normal(mix: Mix) -> bool {
+    matches!(mix, Mix::Normal | Mix::Clip)
+}
+
+fn pack(out_buf: &mut [u8], scratch: &ScratchBuf, layout: &TargetLayout, x: isize, y: isize) {
+    // Make sure we don't process columns and rows outside the range of the output buffer.
+    let col0 = (-x).clamp(0, WIDE_TILE_WIDTH as isize) as usize;
+    let col1 = (layout.width as isize - x).clamp(0, WIDE_TILE_WIDTH as isize) as usize;
+    let row0 = (-y).clamp(0, STRIP_HEIGHT as isize) as usize;
+    let row1 = (layout.height as isize - y).clamp(0, STRIP_HEIGHT as isize) as usize;
 
-    // Make sure we don't process rows outside the range of the pixmap.
-    let max_height = (height - y * STRIP_HEIGHT).min(STRIP_HEIGHT);
+    if col0 >= col1 {
+        return;
+    }
 
-    for j in 0..max_height {
-        let line_ix = base_ix + j * width * COLOR_COMPONENTS;
+    let target_len = (col1 - col0) * COLOR_COMPONENTS;
 
-        // Make sure we don't process columns outside the range of the pixmap.
-        let max_width = (width - x * WIDE_TILE_WIDTH).min(WIDE_TILE_WIDTH);
-        let target_len = max_width * COLOR_COMPONENTS;
+    for j in row0..row1 {
+        let line_ix = (y + j as isize) as usize * layout.stride
+            + (x + col0 as isize) as usize * COLOR_COMPONENTS;
         // This helps the compiler to understand that any access to `dest` cannot
         // be out of bounds, and thus saves corresponding checks in the for loop.
         let dest = &mut out_buf[line_ix..][..target_len];
 
-        for i in 0..max_width {
+        for (i, d) in (col0..col1).zip(dest.chunks_exact_mut(COLOR_COMPONENTS)) {
             let src = &scratch[(i * STRIP_HEIGHT + j) * COLOR_COMPONENTS..][..COLOR_COMPONENTS];
-            dest[i * COLOR_COMPONENTS..][..COLOR_COMPONENTS]
-                .copy_from_slice(&src[..COLOR_COMPONENTS]);
+            d.copy_from_slice(src);
+        }
+
+        if layout.alpha_mode == AlphaMode::Unpremultiplied {
+            unpremultiply(dest);
+        }
+
+        if layout.format == PixelFormat::Bgra8 {
+            for pixel in dest.chunks_exact_mut(COLOR_COMPONENTS) {
+                pixel.swap(0, 2);
+            }
         }
     }
 }
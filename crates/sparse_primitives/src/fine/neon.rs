@@ -1,13 +1,16 @@
 use crate::execute::{Neon, Scalar};
 use crate::fine;
-use crate::fine::{scalar, COLOR_COMPONENTS};
+use crate::fine::gradient::{ColorRamp, LinearPos, RadialPos, SweepPos};
+use crate::fine::{scalar, Factor, COLOR_COMPONENTS};
+use crate::util::neon::div_255;
+use std::arch::aarch64::*;
 
 impl fine::Compose for Neon {
     fn compose_fill(target: &mut [u8], cs: &[u8; COLOR_COMPONENTS], compose: peniko::Compose) {
         unsafe {
             match compose {
                 peniko::Compose::SrcOver => fill::src_over(target, cs),
-                _ => unimplemented!(),
+                _ => fill::porter_duff(target, cs, compose),
             }
         }
     }
@@ -21,14 +24,80 @@ impl fine::Compose for Neon {
         unsafe {
             match compose {
                 peniko::Compose::SrcOver => strip::src_over(target, cs, alphas),
-                _ => unimplemented!(),
+                _ => strip::porter_duff(target, cs, alphas, compose),
             }
         }
     }
+
+    fn compose_fill_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        unsafe {
+            match compose {
+                peniko::Compose::SrcOver => fill::src_over_buf(target, src),
+                _ => fill::porter_duff_buf(target, src, compose),
+            }
+        }
+    }
+
+    fn compose_strip_buf(target: &mut [u8], src: &[u8], alphas: &[u32], compose: peniko::Compose) {
+        unsafe {
+            match compose {
+                peniko::Compose::SrcOver => strip::src_over_buf(target, src, alphas),
+                _ => strip::porter_duff_buf(target, src, alphas, compose),
+            }
+        }
+    }
+
+    fn mix(src: &mut [u8], cb: &[u8], mix: peniko::Mix) {
+        match mix {
+            // The non-separable modes need to look at all components of a pixel at once,
+            // which doesn't map well to SIMD, so we use the scalar version for them.
+            peniko::Mix::Hue
+            | peniko::Mix::Saturation
+            | peniko::Mix::Color
+            | peniko::Mix::Luminosity => scalar::mix::blend(src, cb, mix),
+            _ => unsafe { mix::blend(src, cb, mix) },
+        }
+    }
+}
+
+impl fine::Gradient for Neon {
+    fn linear_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::linear(buf, ramp, extend, pos, x, y) }
+    }
+
+    fn radial_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::radial(buf, ramp, extend, pos, x, y) }
+    }
+
+    fn sweep_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    ) {
+        unsafe { gradient::sweep(buf, ramp, extend, pos, x, y) }
+    }
 }
 
 mod fill {
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use super::{porter_duff_x4, ALPHA_INDICES};
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
     use crate::util::scalar::splat_x4;
 
     use crate::util::neon::{div_255, inv};
@@ -59,11 +128,66 @@ mod fill {
             vst1q_u8(cb.as_mut_ptr(), res);
         }
     }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn src_over_buf(target: &mut [u8], src: &[u8]) {
+        let alpha_idx = vld1q_u8(ALPHA_INDICES.as_ptr());
+
+        for (cb, cs) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+        {
+            let cs_vals = vld1q_u8(cs.as_ptr());
+            let cb_vals = vld1q_u8(cb.as_ptr());
+            let inv_as = vsubq_u8(vdupq_n_u8(255), vqtbl1q_u8(cs_vals, alpha_idx));
+
+            let low = div_255(vmull_u8(vget_low_u8(cb_vals), vget_low_u8(inv_as)));
+            let high = div_255(vmull_high_u8(cb_vals, inv_as));
+            let res = vaddq_u8(cs_vals, vcombine_u8(vmovn_u16(low), vmovn_u16(high)));
+
+            vst1q_u8(cb.as_mut_ptr(), res);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+        let cs = vld1q_u8(splat_x4(cs).as_ptr());
+
+        for cb in target.chunks_exact_mut(TOTAL_STRIP_HEIGHT) {
+            let res = porter_duff_x4(vld1q_u8(cb.as_ptr()), cs, fa, fb);
+            vst1q_u8(cb.as_mut_ptr(), res);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn porter_duff_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for (cb, cs) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+        {
+            let res = porter_duff_x4(vld1q_u8(cb.as_ptr()), vld1q_u8(cs.as_ptr()), fa, fb);
+            vst1q_u8(cb.as_mut_ptr(), res);
+        }
+    }
 }
 
+/// Table indices for broadcasting the alpha channel of 4 pixels to all of their components.
+const ALPHA_INDICES: [u8; 16] = [3, 3, 3, 3, 7, 7, 7, 7, 11, 11, 11, 11, 15, 15, 15, 15];
+/// Table indices for broadcasting each byte of a packed `u32` mask to 4 bytes.
+const MASK_INDICES: [u8; 16] = [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3];
+
 mod strip {
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use super::{lerp_x4, load_mask_x4, porter_duff_x4, ALPHA_INDICES, MASK_INDICES};
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
     use crate::util::neon::{div_255, inv};
+    use crate::util::scalar::splat_x4;
     use std::arch::aarch64::*;
 
     /// SAFETY: The CPU needs to support the target feature `neon`.
@@ -96,4 +220,558 @@ mod strip {
             }
         }
     }
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn src_over_buf(target: &mut [u8], src: &[u8], alphas: &[u32]) {
+        let alpha_idx = vld1q_u8(ALPHA_INDICES.as_ptr());
+        let mask_idx = vld1q_u8(MASK_INDICES.as_ptr());
+        let max = vdupq_n_u16(255);
+
+        for ((cb, cs), a) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            let cs_vals = vld1q_u8(cs.as_ptr());
+            let cb_vals = vld1q_u8(cb.as_ptr());
+            let as_vals = vqtbl1q_u8(cs_vals, alpha_idx);
+            let am = vqtbl1q_u8(vreinterpretq_u8_u32(vdupq_n_u32(*a)), mask_idx);
+
+            let am_low = vmovl_u8(vget_low_u8(am));
+            let am_high = vmovl_u8(vget_high_u8(am));
+            let inv_low = vsubq_u16(
+                max,
+                div_255(vmull_u8(vget_low_u8(am), vget_low_u8(as_vals))),
+            );
+            let inv_high = vsubq_u16(max, div_255(vmull_high_u8(am, as_vals)));
+
+            let im1_low = vmulq_u16(vmovl_u8(vget_low_u8(cb_vals)), inv_low);
+            let im1_high = vmulq_u16(vmovl_u8(vget_high_u8(cb_vals)), inv_high);
+            let im2_low = vmulq_u16(vmovl_u8(vget_low_u8(cs_vals)), am_low);
+            let im2_high = vmulq_u16(vmovl_u8(vget_high_u8(cs_vals)), am_high);
+            let res_low = vmovn_u16(div_255(vaddq_u16(im1_low, im2_low)));
+            let res_high = vmovn_u16(div_255(vaddq_u16(im1_high, im2_high)));
+
+            vst1q_u8(cb.as_mut_ptr(), vcombine_u8(res_low, res_high));
+        }
+    }
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+        let cs = vld1q_u8(splat_x4(cs).as_ptr());
+
+        for (cb, a) in target.chunks_exact_mut(TOTAL_STRIP_HEIGHT).zip(alphas) {
+            let cb_vals = vld1q_u8(cb.as_ptr());
+            let res = lerp_x4(
+                cb_vals,
+                porter_duff_x4(cb_vals, cs, fa, fb),
+                load_mask_x4(*a),
+            );
+            vst1q_u8(cb.as_mut_ptr(), res);
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn porter_duff_buf(
+        target: &mut [u8],
+        src: &[u8],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for ((cb, cs), a) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            let cb_vals = vld1q_u8(cb.as_ptr());
+            let co = porter_duff_x4(cb_vals, vld1q_u8(cs.as_ptr()), fa, fb);
+            let res = lerp_x4(cb_vals, co, load_mask_x4(*a));
+            vst1q_u8(cb.as_mut_ptr(), res);
+        }
+    }
+}
+
+mod mix {
+    // This mirrors the scalar version operation by operation, see the comments there.
+    // Each vector holds one pixel.
+
+    use std::arch::aarch64::*;
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn blend(src: &mut [u8], backdrop: &[u8], mix: peniko::Mix) {
+        let one = vdupq_n_f32(1.0);
+
+        for (cs, cb) in src.chunks_exact_mut(16).zip(backdrop.chunks_exact(16)) {
+            let s = load_x4(cs);
+            let b = load_x4(cb);
+            let mut res = [vdupq_n_f32(0.0); 4];
+
+            for i in 0..4 {
+                let s = s[i];
+                let b = b[i];
+                let sa = vdupq_laneq_f32::<3>(s);
+                let ba = vdupq_laneq_f32::<3>(b);
+                let ab = vdivq_f32(ba, vdupq_n_f32(255.0));
+
+                let mixed = separable(unpremultiply(b, ba), unpremultiply(s, sa), mix);
+                let r = vaddq_f32(
+                    vmulq_f32(s, vsubq_f32(one, ab)),
+                    vmulq_f32(vmulq_f32(sa, ab), mixed),
+                );
+                let r = vmaxq_f32(vminq_f32(r, sa), vdupq_n_f32(0.0));
+                // Keep the alpha channel of the source.
+                res[i] = vsetq_lane_f32::<3>(vgetq_lane_f32::<3>(s), r);
+            }
+
+            store_x4(cs, res);
+        }
+    }
+
+    /// Load 4 pixels and convert them to f32.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn load_x4(buf: &[u8]) -> [float32x4_t; 4] {
+        let vals = vld1q_u8(buf.as_ptr());
+        let low = vmovl_u8(vget_low_u8(vals));
+        let high = vmovl_u8(vget_high_u8(vals));
+
+        [
+            vcvtq_f32_u32(vmovl_u16(vget_low_u16(low))),
+            vcvtq_f32_u32(vmovl_u16(vget_high_u16(low))),
+            vcvtq_f32_u32(vmovl_u16(vget_low_u16(high))),
+            vcvtq_f32_u32(vmovl_u16(vget_high_u16(high))),
+        ]
+    }
+
+    /// Round 4 pixels to u8 and store them.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn store_x4(buf: &mut [u8], vals: [float32x4_t; 4]) {
+        let half = vdupq_n_f32(0.5);
+        let [p0, p1, p2, p3] = vals.map(|v| vmovn_u32(vcvtq_u32_f32(vaddq_f32(v, half))));
+
+        let res = vcombine_u8(
+            vmovn_u16(vcombine_u16(p0, p1)),
+            vmovn_u16(vcombine_u16(p2, p3)),
+        );
+        vst1q_u8(buf.as_mut_ptr(), res);
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn unpremultiply(c: float32x4_t, a: float32x4_t) -> float32x4_t {
+        let res = vminq_f32(vdivq_f32(c, a), vdupq_n_f32(1.0));
+
+        vbslq_f32(vcgtq_f32(a, vdupq_n_f32(0.0)), res, vdupq_n_f32(0.0))
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn separable(cb: float32x4_t, cs: float32x4_t, mix: peniko::Mix) -> float32x4_t {
+        use peniko::Mix;
+
+        let zero = vdupq_n_f32(0.0);
+        let half = vdupq_n_f32(0.5);
+        let one = vdupq_n_f32(1.0);
+        let two = vdupq_n_f32(2.0);
+
+        match mix {
+            Mix::Multiply => vmulq_f32(cb, cs),
+            Mix::Screen => screen(cb, cs),
+            Mix::Overlay => hard_light(cs, cb),
+            Mix::Darken => vminq_f32(cb, cs),
+            Mix::Lighten => vmaxq_f32(cb, cs),
+            Mix::ColorDodge => {
+                let res = vminq_f32(vdivq_f32(cb, vsubq_f32(one, cs)), one);
+                let res = vbslq_f32(vcgeq_f32(cs, one), one, res);
+
+                vbslq_f32(vceqq_f32(cb, zero), zero, res)
+            }
+            Mix::ColorBurn => {
+                let res = vsubq_f32(one, vminq_f32(vdivq_f32(vsubq_f32(one, cb), cs), one));
+                let res = vbslq_f32(vcleq_f32(cs, zero), zero, res);
+
+                vbslq_f32(vcgeq_f32(cb, one), one, res)
+            }
+            Mix::HardLight => hard_light(cb, cs),
+            Mix::SoftLight => {
+                let low = vsubq_f32(
+                    cb,
+                    vmulq_f32(
+                        vmulq_f32(vsubq_f32(one, vmulq_f32(two, cs)), cb),
+                        vsubq_f32(one, cb),
+                    ),
+                );
+
+                let poly = vmulq_f32(
+                    vaddq_f32(
+                        vmulq_f32(
+                            vsubq_f32(vmulq_f32(vdupq_n_f32(16.0), cb), vdupq_n_f32(12.0)),
+                            cb,
+                        ),
+                        vdupq_n_f32(4.0),
+                    ),
+                    cb,
+                );
+                let d = vbslq_f32(vcleq_f32(cb, vdupq_n_f32(0.25)), poly, vsqrtq_f32(cb));
+                let high = vaddq_f32(
+                    cb,
+                    vmulq_f32(vsubq_f32(vmulq_f32(two, cs), one), vsubq_f32(d, cb)),
+                );
+
+                vbslq_f32(vcleq_f32(cs, half), low, high)
+            }
+            Mix::Difference => vabsq_f32(vsubq_f32(cb, cs)),
+            Mix::Exclusion => vsubq_f32(vaddq_f32(cb, cs), vmulq_f32(vmulq_f32(two, cb), cs)),
+            _ => cs,
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn screen(cb: float32x4_t, cs: float32x4_t) -> float32x4_t {
+        vsubq_f32(vaddq_f32(cb, cs), vmulq_f32(cb, cs))
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn hard_light(cb: float32x4_t, cs: float32x4_t) -> float32x4_t {
+        let cs2 = vmulq_f32(vdupq_n_f32(2.0), cs);
+        let low = vmulq_f32(cb, cs2);
+        let high = screen(cb, vsubq_f32(cs2, vdupq_n_f32(1.0)));
+
+        vbslq_f32(vcleq_f32(cs, vdupq_n_f32(0.5)), low, high)
+    }
+}
+
+/// Broadcast the 4 coverage values of a strip column to the components of each pixel.
+///
+/// SAFETY: The CPU needs to support the target feature `neon`.
+#[inline]
+unsafe fn load_mask_x4(masks: u32) -> uint8x16_t {
+    vqtbl1q_u8(
+        vreinterpretq_u8_u32(vdupq_n_u32(masks)),
+        vld1q_u8(MASK_INDICES.as_ptr()),
+    )
+}
+
+/// SAFETY: The CPU needs to support the target feature `neon`.
+#[inline]
+unsafe fn factor_x4(factor: Factor, _as: uint8x16_t, ab: uint8x16_t) -> uint8x16_t {
+    let max = vdupq_n_u8(255);
+
+    match factor {
+        Factor::Zero => vdupq_n_u8(0),
+        Factor::One => max,
+        Factor::SrcAlpha => _as,
+        Factor::InvSrcAlpha => vsubq_u8(max, _as),
+        Factor::DestAlpha => ab,
+        Factor::InvDestAlpha => vsubq_u8(max, ab),
+    }
+}
+
+/// Compose 4 pixels using the Porter-Duff factors `fa` and `fb`.
+///
+/// SAFETY: The CPU needs to support the target feature `neon`.
+#[inline]
+unsafe fn porter_duff_x4(cb: uint8x16_t, cs: uint8x16_t, fa: Factor, fb: Factor) -> uint8x16_t {
+    let alpha_idx = vld1q_u8(ALPHA_INDICES.as_ptr());
+    let _as = vqtbl1q_u8(cs, alpha_idx);
+    let ab = vqtbl1q_u8(cb, alpha_idx);
+    let fa = factor_x4(fa, _as, ab);
+    let fb = factor_x4(fb, _as, ab);
+
+    let low = vaddq_u16(
+        div_255(vmull_u8(vget_low_u8(cs), vget_low_u8(fa))),
+        div_255(vmull_u8(vget_low_u8(cb), vget_low_u8(fb))),
+    );
+    let high = vaddq_u16(
+        div_255(vmull_high_u8(cs, fa)),
+        div_255(vmull_high_u8(cb, fb)),
+    );
+
+    // Saturate in case the result exceeds 255.
+    vcombine_u8(vqmovn_u16(low), vqmovn_u16(high))
+}
+
+/// Linearly interpolate between the backdrop and the composed color, using the
+/// coverage `am` of each pixel.
+///
+/// SAFETY: The CPU needs to support the target feature `neon`.
+#[inline]
+unsafe fn lerp_x4(cb: uint8x16_t, co: uint8x16_t, am: uint8x16_t) -> uint8x16_t {
+    let inv_am = vsubq_u8(vdupq_n_u8(255), am);
+
+    let low = vmlal_u8(
+        vmull_u8(vget_low_u8(cb), vget_low_u8(inv_am)),
+        vget_low_u8(co),
+        vget_low_u8(am),
+    );
+    let high = vmlal_high_u8(vmull_high_u8(cb, inv_am), co, am);
+
+    vcombine_u8(vmovn_u16(div_255(low)), vmovn_u16(div_255(high)))
+}
+
+mod gradient {
+    use crate::fine::gradient::{ColorRamp, LinearPos, RadialPos, SweepPos};
+    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use peniko::Extend;
+    use std::arch::aarch64::*;
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn linear(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    ) {
+        let ys = row_coords(y);
+        let defined = vdupq_n_u32(u32::MAX);
+
+        for (i, column) in buf.chunks_exact_mut(TOTAL_STRIP_HEIGHT).enumerate() {
+            let xs = column_coords(x + i);
+            let t = vaddq_f32(
+                vaddq_f32(vmulq_n_f32(xs, pos.a), vmulq_n_f32(ys, pos.b)),
+                vdupq_n_f32(pos.c),
+            );
+
+            store(column, shade(ramp, extend, t, defined));
+        }
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn radial(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    ) {
+        let ys = row_coords(y);
+        let [ia, ib, ic, id, ie, if_] = pos.inv;
+        let zero = vdupq_n_f32(0.0);
+        let r0 = vdupq_n_f32(pos.start_radius);
+        let a = vdupq_n_f32(pos.a);
+        let r0_dr = vdupq_n_f32(pos.start_radius * pos.dr);
+        let r0_r0 = vdupq_n_f32(pos.start_radius * pos.start_radius);
+
+        // Whether the radius of the circle at position `t` is non-negative.
+        let is_valid = |t: float32x4_t| vcgeq_f32(vaddq_f32(r0, vmulq_n_f32(t, pos.dr)), zero);
+
+        for (i, column) in buf.chunks_exact_mut(TOTAL_STRIP_HEIGHT).enumerate() {
+            let xs = column_coords(x + i);
+            let px = vsubq_f32(
+                vaddq_f32(
+                    vaddq_f32(vmulq_n_f32(xs, ia), vmulq_n_f32(ys, ic)),
+                    vdupq_n_f32(ie),
+                ),
+                vdupq_n_f32(pos.start_center[0]),
+            );
+            let py = vsubq_f32(
+                vaddq_f32(
+                    vaddq_f32(vmulq_n_f32(xs, ib), vmulq_n_f32(ys, id)),
+                    vdupq_n_f32(if_),
+                ),
+                vdupq_n_f32(pos.start_center[1]),
+            );
+
+            // See `RadialPos::t` for the derivation.
+            let b = vaddq_f32(
+                vaddq_f32(vmulq_n_f32(px, pos.dc[0]), vmulq_n_f32(py, pos.dc[1])),
+                r0_dr,
+            );
+            let c = vsubq_f32(vaddq_f32(vmulq_f32(px, px), vmulq_f32(py, py)), r0_r0);
+
+            let (t, defined) = if pos.a == 0.0 {
+                let t = vdivq_f32(c, vmulq_n_f32(b, 2.0));
+                let defined = vandq_u32(vmvnq_u32(vceqq_f32(b, zero)), is_valid(t));
+
+                (t, defined)
+            } else {
+                let discriminant = vsubq_f32(vmulq_f32(b, b), vmulq_f32(a, c));
+                let sqrt = vsqrtq_f32(discriminant);
+                let t1 = vdivq_f32(vaddq_f32(b, sqrt), a);
+                let t2 = vdivq_f32(vsubq_f32(b, sqrt), a);
+                let t1_larger = vcgtq_f32(t1, t2);
+                let large = vbslq_f32(t1_larger, t1, t2);
+                let small = vbslq_f32(t1_larger, t2, t1);
+                let large_valid = is_valid(large);
+                let defined = vandq_u32(
+                    vcgeq_f32(discriminant, zero),
+                    vorrq_u32(large_valid, is_valid(small)),
+                );
+
+                (vbslq_f32(large_valid, large, small), defined)
+            };
+
+            store(column, shade(ramp, extend, t, defined));
+        }
+    }
+
+    /// There is no vectorized version of `atan2`, so the positions are computed per pixel
+    /// and only the color lookup is vectorized.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    pub(crate) unsafe fn sweep(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    ) {
+        for (i, column) in buf.chunks_exact_mut(TOTAL_STRIP_HEIGHT).enumerate() {
+            let mut ts = [0.0; 4];
+            let mut defined = [0; 4];
+            let px = (x + i) as f32 + 0.5;
+
+            for row in 0..4 {
+                let py = (y + row) as f32 + 0.5;
+
+                if let Some(t) = pos.t(px, py) {
+                    ts[row] = t;
+                    defined[row] = u32::MAX;
+                }
+            }
+
+            let t = vld1q_f32(ts.as_ptr());
+            let defined = vld1q_u32(defined.as_ptr());
+
+            store(column, shade(ramp, extend, t, defined));
+        }
+    }
+
+    /// The y coordinates of the pixel centers of a column.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn row_coords(y: usize) -> float32x4_t {
+        let coords = [0, 1, 2, 3].map(|row| (y + row) as f32 + 0.5);
+
+        vld1q_f32(coords.as_ptr())
+    }
+
+    /// The x coordinates of the pixel centers of a column.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn column_coords(x: usize) -> float32x4_t {
+        vdupq_n_f32(x as f32 + 0.5)
+    }
+
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn store(column: &mut [u8], pixels: uint32x4_t) {
+        vst1q_u8(column.as_mut_ptr(), vreinterpretq_u8_u32(pixels));
+    }
+
+    /// Map the positions `t` according to the extend mode, look up their colors in the ramp
+    /// and convert them to RGBA8. Pixels that aren't `defined` are set to transparent.
+    ///
+    /// This mirrors `extend` and `ColorRamp::eval`, so that the results are identical.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    #[inline]
+    unsafe fn shade(
+        ramp: &ColorRamp,
+        extend: Extend,
+        t: float32x4_t,
+        defined: uint32x4_t,
+    ) -> uint32x4_t {
+        let zero = vdupq_n_f32(0.0);
+        let one = vdupq_n_f32(1.0);
+        let two = vdupq_n_f32(2.0);
+
+        // `vmin` and `vmax` propagate NaN values, just like `clamp` in the scalar version.
+        let t = match extend {
+            Extend::Pad => vminq_f32(vmaxq_f32(t, zero), one),
+            Extend::Repeat => vsubq_f32(t, vrndmq_f32(t)),
+            Extend::Reflect => {
+                // Computing the remainder like this is exact, so it yields the same
+                // result as `rem_euclid`.
+                let r = vsubq_f32(t, vmulq_f32(two, vrndq_f32(vmulq_n_f32(t, 0.5))));
+                let r = vbslq_f32(vcltq_f32(r, zero), vaddq_f32(r, two), r);
+
+                vbslq_f32(vcgtq_f32(r, one), vsubq_f32(two, r), r)
+            }
+        };
+
+        let Some((first_offset, first_color)) = ramp.stops.first() else {
+            return vdupq_n_u32(0);
+        };
+        let (_, last_color) = ramp.stops[ramp.stops.len() - 1];
+
+        // Pixels whose position lies beyond the last stop (or is NaN) keep the last color.
+        let mut color = [zero; COLOR_COMPONENTS];
+        let mut done = vcleq_f32(t, vdupq_n_f32(*first_offset));
+
+        for i in 0..COLOR_COMPONENTS {
+            color[i] = vbslq_f32(
+                done,
+                vdupq_n_f32(first_color[i]),
+                vdupq_n_f32(last_color[i]),
+            );
+        }
+
+        for w in ramp.stops.windows(2) {
+            if vminvq_u32(done) == u32::MAX {
+                break;
+            }
+
+            let (o0, c0) = &w[0];
+            let (o1, c1) = &w[1];
+            let in_segment = vbicq_u32(vcleq_f32(t, vdupq_n_f32(*o1)), done);
+            let mut segment = [zero; COLOR_COMPONENTS];
+
+            if o1 - o0 <= 0.0 {
+                for i in 0..COLOR_COMPONENTS {
+                    segment[i] = vdupq_n_f32(c1[i]);
+                }
+            } else {
+                let f = vdivq_f32(vsubq_f32(t, vdupq_n_f32(*o0)), vdupq_n_f32(o1 - o0));
+
+                for i in 0..COLOR_COMPONENTS {
+                    segment[i] = vaddq_f32(vdupq_n_f32(c0[i]), vmulq_n_f32(f, c1[i] - c0[i]));
+                }
+
+                for i in 0..COLOR_COMPONENTS - 1 {
+                    segment[i] = vminq_f32(segment[i], segment[3]);
+                }
+            }
+
+            for i in 0..COLOR_COMPONENTS {
+                color[i] = vbslq_f32(in_segment, segment[i], color[i]);
+            }
+
+            done = vorrq_u32(done, in_segment);
+        }
+
+        // The conversion saturates like an `as u8` cast and maps NaN to 0.
+        let to_u8 = |c: float32x4_t| {
+            let c = vaddq_f32(vmulq_n_f32(c, 255.0), vdupq_n_f32(0.5));
+
+            vminq_u32(vcvtq_u32_f32(c), vdupq_n_u32(255))
+        };
+
+        let pixels = vorrq_u32(
+            vorrq_u32(to_u8(color[0]), vshlq_n_u32::<8>(to_u8(color[1]))),
+            vorrq_u32(
+                vshlq_n_u32::<16>(to_u8(color[2])),
+                vshlq_n_u32::<24>(to_u8(color[3])),
+            ),
+        );
+
+        vandq_u32(pixels, defined)
+    }
 }
@@ -1,12 +1,14 @@
 use crate::execute::Scalar;
 use crate::fine;
-use crate::fine::COLOR_COMPONENTS;
+use crate::fine::gradient::{fill_gradient, ColorRamp, LinearPos, RadialPos, SweepPos};
+use crate::fine::{Factor, COLOR_COMPONENTS};
+use crate::util::scalar::div_255;
 
 impl fine::Compose for Scalar {
     fn compose_fill(target: &mut [u8], cs: &[u8; COLOR_COMPONENTS], compose: peniko::Compose) {
         match compose {
             peniko::Compose::SrcOver => fill::src_over(target, cs),
-            _ => unimplemented!(),
+            _ => fill::porter_duff(target, cs, compose),
         }
     }
 
@@ -18,16 +20,70 @@ impl fine::Compose for Scalar {
     ) {
         match compose {
             peniko::Compose::SrcOver => strip::src_over(target, cs, alphas),
-            _ => unimplemented!(),
+            _ => strip::porter_duff(target, cs, alphas, compose),
         }
     }
+
+    fn compose_fill_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        match compose {
+            peniko::Compose::SrcOver => fill::src_over_buf(target, src),
+            _ => fill::porter_duff_buf(target, src, compose),
+        }
+    }
+
+    fn compose_strip_buf(target: &mut [u8], src: &[u8], alphas: &[u32], compose: peniko::Compose) {
+        match compose {
+            peniko::Compose::SrcOver => strip::src_over_buf(target, src, alphas),
+            _ => strip::porter_duff_buf(target, src, alphas, compose),
+        }
+    }
+
+    fn mix(src: &mut [u8], cb: &[u8], mix: peniko::Mix) {
+        mix::blend(src, cb, mix);
+    }
+}
+
+impl fine::Gradient for Scalar {
+    fn linear_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &LinearPos,
+        x: usize,
+        y: usize,
+    ) {
+        fill_gradient(buf, ramp, extend, x, y, |x, y| pos.t(x, y));
+    }
+
+    fn radial_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &RadialPos,
+        x: usize,
+        y: usize,
+    ) {
+        fill_gradient(buf, ramp, extend, x, y, |x, y| pos.t(x, y));
+    }
+
+    fn sweep_gradient(
+        buf: &mut [u8],
+        ramp: &ColorRamp,
+        extend: peniko::Extend,
+        pos: &SweepPos,
+        x: usize,
+        y: usize,
+    ) {
+        fill_gradient(buf, ramp, extend, x, y, |x, y| pos.t(x, y));
+    }
 }
 
 pub(crate) mod fill {
     // See https://www.w3.org/TR/compositing-1/#porterduffcompositingoperators for the
     // formulas.
 
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use super::porter_duff_pixel;
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
     use crate::util::scalar::div_255;
 
     pub(crate) fn src_over(target: &mut [u8], cs: &[u8; COLOR_COMPONENTS]) {
@@ -43,10 +99,49 @@ pub(crate) mod fill {
             }
         }
     }
+
+    pub(crate) fn src_over_buf(target: &mut [u8], src: &[u8]) {
+        for (cb, cs) in target
+            .chunks_exact_mut(COLOR_COMPONENTS)
+            .zip(src.chunks_exact(COLOR_COMPONENTS))
+        {
+            let _as = cs[3] as u16;
+
+            for i in 0..COLOR_COMPONENTS {
+                cb[i] = cs[i] + div_255(cb[i] as u16 * (255 - _as)) as u8;
+            }
+        }
+    }
+
+    pub(crate) fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for cb in target.chunks_exact_mut(COLOR_COMPONENTS) {
+            let res = porter_duff_pixel(cb, cs, fa, fb);
+            cb.copy_from_slice(&res);
+        }
+    }
+
+    pub(crate) fn porter_duff_buf(target: &mut [u8], src: &[u8], compose: peniko::Compose) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for (cb, cs) in target
+            .chunks_exact_mut(COLOR_COMPONENTS)
+            .zip(src.chunks_exact(COLOR_COMPONENTS))
+        {
+            let res = porter_duff_pixel(cb, cs, fa, fb);
+            cb.copy_from_slice(&res);
+        }
+    }
 }
 
 pub(crate) mod strip {
-    use crate::fine::{COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
+    use super::{lerp_pixel, porter_duff_pixel};
+    use crate::fine::{porter_duff_factors, COLOR_COMPONENTS, TOTAL_STRIP_HEIGHT};
     use crate::util::scalar::div_255;
     use crate::wide_tile::STRIP_HEIGHT;
 
@@ -65,4 +160,271 @@ pub(crate) mod strip {
             }
         }
     }
+
+    pub(crate) fn src_over_buf(target: &mut [u8], src: &[u8], alphas: &[u32]) {
+        for ((cb, cs), masks) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            for j in 0..STRIP_HEIGHT {
+                let am = ((*masks >> (j * 8)) & 0xff) as u16;
+                let inv_as_am = 255 - div_255(am * cs[j * 4 + 3] as u16);
+
+                for i in 0..COLOR_COMPONENTS {
+                    let im1 = cb[j * 4 + i] as u16 * inv_as_am;
+                    let im2 = cs[j * 4 + i] as u16 * am;
+                    let im3 = div_255(im1 + im2);
+                    cb[j * 4 + i] = im3 as u8;
+                }
+            }
+        }
+    }
+    pub(crate) fn porter_duff(
+        target: &mut [u8],
+        cs: &[u8; COLOR_COMPONENTS],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for (cb, masks) in target.chunks_exact_mut(TOTAL_STRIP_HEIGHT).zip(alphas) {
+            for (j, cb) in cb.chunks_exact_mut(COLOR_COMPONENTS).enumerate() {
+                let am = ((*masks >> (j * 8)) & 0xff) as u16;
+                let res = lerp_pixel(cb, &porter_duff_pixel(cb, cs, fa, fb), am);
+                cb.copy_from_slice(&res);
+            }
+        }
+    }
+
+    pub(crate) fn porter_duff_buf(
+        target: &mut [u8],
+        src: &[u8],
+        alphas: &[u32],
+        compose: peniko::Compose,
+    ) {
+        let (fa, fb) = porter_duff_factors(compose);
+
+        for ((cb, cs), masks) in target
+            .chunks_exact_mut(TOTAL_STRIP_HEIGHT)
+            .zip(src.chunks_exact(TOTAL_STRIP_HEIGHT))
+            .zip(alphas)
+        {
+            for (j, (cb, cs)) in cb
+                .chunks_exact_mut(COLOR_COMPONENTS)
+                .zip(cs.chunks_exact(COLOR_COMPONENTS))
+                .enumerate()
+            {
+                let am = ((*masks >> (j * 8)) & 0xff) as u16;
+                let res = lerp_pixel(cb, &porter_duff_pixel(cb, cs, fa, fb), am);
+                cb.copy_from_slice(&res);
+            }
+        }
+    }
+}
+
+pub(crate) mod mix {
+    // See https://www.w3.org/TR/compositing-1/#blending for the formulas. All kernels
+    // work with f32 and evaluate the exact same operations, so that the SIMD versions
+    // yield the same results.
+
+    use crate::fine::COLOR_COMPONENTS;
+    use peniko::Mix;
+
+    pub(crate) fn blend(src: &mut [u8], backdrop: &[u8], mix: Mix) {
+        for (cs, cb) in src
+            .chunks_exact_mut(COLOR_COMPONENTS)
+            .zip(backdrop.chunks_exact(COLOR_COMPONENTS))
+        {
+            let sa = cs[3] as f32;
+            let ba = cb[3] as f32;
+            let ab = ba / 255.0;
+
+            let us = [0, 1, 2].map(|i| unpremultiply(cs[i] as f32, sa));
+            let ub = [0, 1, 2].map(|i| unpremultiply(cb[i] as f32, ba));
+
+            let mixed = match mix {
+                Mix::Hue | Mix::Saturation | Mix::Color | Mix::Luminosity => {
+                    non_separable(ub, us, mix)
+                }
+                _ => [0, 1, 2].map(|i| separable(ub[i], us[i], mix)),
+            };
+
+            for i in 0..COLOR_COMPONENTS - 1 {
+                let s = cs[i] as f32;
+                // Where the backdrop is transparent, the source color stays unchanged.
+                let res = s * (1.0 - ab) + (sa * ab) * mixed[i];
+
+                cs[i] = (res.min(sa).max(0.0) + 0.5) as u8;
+            }
+        }
+    }
+
+    /// Unpremultiply a color component and normalize it to [0, 1].
+    #[inline(always)]
+    fn unpremultiply(c: f32, a: f32) -> f32 {
+        if a > 0.0 {
+            (c / a).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// The blend function `B(cb, cs)` of a separable mix mode.
+    #[inline(always)]
+    fn separable(cb: f32, cs: f32, mix: Mix) -> f32 {
+        match mix {
+            Mix::Multiply => cb * cs,
+            Mix::Screen => screen(cb, cs),
+            Mix::Overlay => hard_light(cs, cb),
+            Mix::Darken => cb.min(cs),
+            Mix::Lighten => cb.max(cs),
+            Mix::ColorDodge => {
+                if cb == 0.0 {
+                    0.0
+                } else if cs >= 1.0 {
+                    1.0
+                } else {
+                    (cb / (1.0 - cs)).min(1.0)
+                }
+            }
+            Mix::ColorBurn => {
+                if cb >= 1.0 {
+                    1.0
+                } else if cs <= 0.0 {
+                    0.0
+                } else {
+                    1.0 - ((1.0 - cb) / cs).min(1.0)
+                }
+            }
+            Mix::HardLight => hard_light(cb, cs),
+            Mix::SoftLight => {
+                if cs <= 0.5 {
+                    cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+                } else {
+                    let d = if cb <= 0.25 {
+                        ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                    } else {
+                        cb.sqrt()
+                    };
+
+                    cb + (2.0 * cs - 1.0) * (d - cb)
+                }
+            }
+            Mix::Difference => (cb - cs).abs(),
+            Mix::Exclusion => cb + cs - 2.0 * cb * cs,
+            _ => cs,
+        }
+    }
+
+    /// The blend function `B(cb, cs)` of a non-separable mix mode, which works on all
+    /// color components at once.
+    #[inline(always)]
+    fn non_separable(cb: [f32; 3], cs: [f32; 3], mix: Mix) -> [f32; 3] {
+        match mix {
+            Mix::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+            Mix::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+            Mix::Color => set_lum(cs, lum(cb)),
+            Mix::Luminosity => set_lum(cb, lum(cs)),
+            _ => cs,
+        }
+    }
+
+    #[inline(always)]
+    fn lum(c: [f32; 3]) -> f32 {
+        0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+    }
+
+    #[inline(always)]
+    fn sat(c: [f32; 3]) -> f32 {
+        c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+    }
+
+    #[inline(always)]
+    fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+        let d = l - lum(c);
+
+        clip_color(c.map(|c| c + d))
+    }
+
+    /// Bring the color components back into [0, 1] while preserving the luminosity.
+    #[inline(always)]
+    fn clip_color(c: [f32; 3]) -> [f32; 3] {
+        let l = lum(c);
+        let n = c[0].min(c[1]).min(c[2]);
+        let x = c[0].max(c[1]).max(c[2]);
+        let mut c = c;
+
+        if n < 0.0 {
+            c = c.map(|c| l + (c - l) * l / (l - n));
+        }
+
+        if x > 1.0 {
+            c = c.map(|c| l + (c - l) * (1.0 - l) / (x - l));
+        }
+
+        // Rounding errors could still push the components slightly out of range.
+        c.map(|c| c.clamp(0.0, 1.0))
+    }
+
+    /// Set the saturation of a color, i.e. stretch the components so that the minimum is
+    /// 0 and the maximum is `s`, keeping their relative order.
+    #[inline(always)]
+    fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+        let n = c[0].min(c[1]).min(c[2]);
+        let x = c[0].max(c[1]).max(c[2]);
+
+        if x > n {
+            c.map(|c| (c - n) * s / (x - n))
+        } else {
+            [0.0; 3]
+        }
+    }
+
+    #[inline(always)]
+    fn screen(cb: f32, cs: f32) -> f32 {
+        cb + cs - cb * cs
+    }
+
+    #[inline(always)]
+    fn hard_light(cb: f32, cs: f32) -> f32 {
+        if cs <= 0.5 {
+            cb * (2.0 * cs)
+        } else {
+            screen(cb, 2.0 * cs - 1.0)
+        }
+    }
+}
+
+/// Compose a single pixel using the Porter-Duff factors `fa` and `fb`.
+#[inline(always)]
+pub(crate) fn porter_duff_pixel(
+    cb: &[u8],
+    cs: &[u8],
+    fa: Factor,
+    fb: Factor,
+) -> [u8; COLOR_COMPONENTS] {
+    let fa = fa.eval(cs[3] as u16, cb[3] as u16);
+    let fb = fb.eval(cs[3] as u16, cb[3] as u16);
+    let mut res = [0; COLOR_COMPONENTS];
+
+    for i in 0..COLOR_COMPONENTS {
+        let im = div_255(cs[i] as u16 * fa) + div_255(cb[i] as u16 * fb);
+        res[i] = im.min(255) as u8;
+    }
+
+    res
+}
+
+/// Linearly interpolate between the backdrop and the composed color, using the
+/// coverage `am` of the pixel.
+#[inline(always)]
+pub(crate) fn lerp_pixel(cb: &[u8], co: &[u8], am: u16) -> [u8; COLOR_COMPONENTS] {
+    let mut res = [0; COLOR_COMPONENTS];
+
+    for i in 0..COLOR_COMPONENTS {
+        res[i] = div_255(cb[i] as u16 * (255 - am) + co[i] as u16 * am) as u8;
+    }
+
+    res
 }
@@ -4,22 +4,52 @@
 //! Utilities for flattening
 
 use flatten::stroke::LoweredPath;
-use peniko::kurbo::{self, Affine, BezPath, Line, Stroke};
+use peniko::kurbo::{
+    self, Affine, BezPath, Line, ParamCurve, ParamCurveArclen, ParamCurveDeriv, PathEl, PathSeg,
+    Rect, Stroke, Vec2,
+};
 
 use crate::tiling::{FlatLine, Point};
 
-/// The flattening tolerance
-const TOL: f64 = 0.25;
+/// The default flattening tolerance, in device pixels.
+pub const DEFAULT_TOLERANCE: f64 = 0.25;
 
-pub fn fill(path: &BezPath, affine: Affine, line_buf: &mut Vec<FlatLine>) {
+/// The width in device pixels below which strokes are drawn as hairlines.
+pub const HAIRLINE_THRESHOLD: f64 = 1.0;
+
+/// Flatten the transformed path into lines, with a maximum deviation of `tolerance` device
+/// pixels from the true curve.
+pub fn fill(path: &BezPath, affine: Affine, tolerance: f64, line_buf: &mut Vec<FlatLine>) {
+    fill_elements(path.iter().map(|el| affine * el), tolerance, line_buf);
+}
+
+/// Like [`fill`], but curves that lie completely above, below, left or right of a viewport
+/// with the given dimensions are replaced by lines between their end points instead of
+/// being flattened.
+///
+/// Such curves don't cover any pixels of the viewport, and their contribution to the
+/// winding numbers of the pixels is the same as the one of the replacement line, so the
+/// rendered result doesn't change.
+pub fn fill_in_viewport(
+    path: &BezPath,
+    affine: Affine,
+    tolerance: f64,
+    width: usize,
+    height: usize,
+    line_buf: &mut Vec<FlatLine>,
+) {
+    let iter = collapse_outside(path.iter().map(|el| affine * el), width, height);
+    fill_elements(iter, tolerance, line_buf);
+}
+
+fn fill_elements(iter: impl Iterator<Item = PathEl>, tolerance: f64, line_buf: &mut Vec<FlatLine>) {
     line_buf.clear();
     let mut start = kurbo::Point::default();
     let mut p0 = kurbo::Point::default();
-    let iter = path.iter().map(|el| affine * el);
 
     let mut closed = false;
 
-    kurbo::flatten(iter, TOL, |el| match el {
+    kurbo::flatten(iter, tolerance, |el| match el {
         kurbo::PathEl::MoveTo(p) => {
             if !closed && p0 != start {
                 close_path(start, p0, line_buf);
@@ -49,11 +79,88 @@ pub fn fill(path: &BezPath, affine: Affine, line_buf: &mut Vec<FlatLine>) {
     }
 }
 
-pub fn stroke(path: &BezPath, style: &Stroke, affine: Affine, line_buf: &mut Vec<FlatLine>) {
+/// Replace the curves whose control points lie completely above, below, left or right of
+/// the viewport by lines between their end points.
+fn collapse_outside(
+    iter: impl Iterator<Item = PathEl>,
+    width: usize,
+    height: usize,
+) -> impl Iterator<Item = PathEl> {
+    let (width, height) = (width as f64, height as f64);
+    let is_outside = move |points: &[kurbo::Point]| {
+        let bbox = points
+            .iter()
+            .fold(Rect::from_points(points[0], points[0]), |bbox, p| {
+                bbox.union_pt(*p)
+            });
+
+        bbox.y1 < 0.0 || bbox.y0 >= height || bbox.x1 < 0.0 || bbox.x0 > width
+    };
+
+    let mut start = kurbo::Point::default();
+    let mut p0 = kurbo::Point::default();
+
+    iter.map(move |el| {
+        let el = match el {
+            PathEl::QuadTo(p1, p2) if is_outside(&[p0, p1, p2]) => PathEl::LineTo(p2),
+            PathEl::CurveTo(p1, p2, p3) if is_outside(&[p0, p1, p2, p3]) => PathEl::LineTo(p3),
+            el => el,
+        };
+
+        match el {
+            PathEl::MoveTo(p) => {
+                start = p;
+                p0 = p;
+            }
+            PathEl::ClosePath => p0 = start,
+            PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => p0 = p,
+        }
+
+        el
+    })
+}
+
+/// Expand the stroke of the path and flatten its outline into transformed lines, with a
+/// maximum deviation of `tolerance` device pixels from the true outline.
+///
+/// Strokes for which [`hairline_alpha`] returns a value are drawn as hairlines instead,
+/// i.e. the center line is drawn one device pixel wide, and the paint needs to be
+/// modulated by the returned alpha value.
+pub fn stroke(
+    path: &BezPath,
+    style: &Stroke,
+    affine: Affine,
+    tolerance: f64,
+    line_buf: &mut Vec<FlatLine>,
+) {
     line_buf.clear();
 
-    // TODO: Temporary hack to ensure that strokes are scaled properly by the transform.
-    let tolerance = TOL / affine.as_coeffs()[0].abs().max(affine.as_coeffs()[3].abs());
+    // The stroke is expanded in user space and then transformed, which turns the circular
+    // pen into an elliptical one for non-uniform scales and skews. The tolerance needs to
+    // be scaled accordingly, so that the deviation stays within `tolerance` after transforming
+    // the outline in the direction that is stretched the most.
+    let scale = max_scale(affine);
+
+    if scale <= 0.0 || !scale.is_finite() {
+        // The transform collapses the stroke to a line or a point, or is invalid.
+        return;
+    }
+
+    if hairline_alpha(style, affine).is_some() {
+        hairline(path, style, affine, tolerance, scale, line_buf);
+        return;
+    }
+
+    let mut tolerance = tolerance / scale;
+
+    // Under strong downscaling, the tolerance can become much larger than the stroke
+    // itself, so the stroke outline would degenerate.
+    if style.width > 0.0 {
+        tolerance = tolerance.min(style.width * 0.25);
+    }
+
+    let dashed = dash(path, &style.dash_pattern, style.dash_offset, tolerance);
+    let path = dashed.as_ref().unwrap_or(path);
 
     let lines: LoweredPath<Line> = flatten::stroke::stroke_undashed(path.iter(), style, tolerance);
     for line in &lines.path {
@@ -65,6 +172,366 @@ pub fn stroke(path: &BezPath, style: &Stroke, affine: Affine, line_buf: &mut Vec
     }
 }
 
+/// Return the coverage of a stroke that should be drawn as a hairline, or `None` if the
+/// stroke is wide enough to be expanded normally.
+///
+/// Strokes with a width of zero are drawn as hairlines with full coverage. Strokes that are
+/// thinner than [`HAIRLINE_THRESHOLD`] device pixels in the direction that is stretched
+/// the most by the transform are drawn as hairlines with their width as coverage, since
+/// expanding them would yield polygons that are too thin to be rasterized evenly.
+pub fn hairline_alpha(style: &Stroke, affine: Affine) -> Option<f32> {
+    if style.width == 0.0 {
+        return Some(1.0);
+    }
+
+    let width = style.width.abs() * max_scale(affine);
+
+    (width < HAIRLINE_THRESHOLD).then_some(width as f32)
+}
+
+/// Flatten the center line of a stroke and draw each of its lines as a rectangle that is
+/// one device pixel wide and extends half a pixel beyond both ends, so that consecutive
+/// lines overlap instead of leaving gaps at the joins.
+///
+/// `scale` is the largest singular value of `affine`.
+fn hairline(
+    path: &BezPath,
+    style: &Stroke,
+    affine: Affine,
+    tolerance: f64,
+    scale: f64,
+    line_buf: &mut Vec<FlatLine>,
+) {
+    let dashed = dash(
+        path,
+        &style.dash_pattern,
+        style.dash_offset,
+        tolerance / scale,
+    );
+    let path = dashed.as_ref().unwrap_or(path);
+
+    let mut start = kurbo::Point::default();
+    let mut p0 = kurbo::Point::default();
+    let iter = path.iter().map(|el| affine * el);
+
+    kurbo::flatten(iter, tolerance, |el| match el {
+        PathEl::MoveTo(p) => {
+            start = p;
+            p0 = p;
+        }
+        PathEl::LineTo(p) => {
+            hairline_segment(p0, p, line_buf);
+            p0 = p;
+        }
+        PathEl::QuadTo(_, _) => unreachable!(),
+        PathEl::CurveTo(_, _, _) => unreachable!(),
+        PathEl::ClosePath => {
+            if p0 != start {
+                hairline_segment(p0, start, line_buf);
+            }
+
+            p0 = start;
+        }
+    });
+}
+
+/// Add the one pixel wide rectangle around the line from `p0` to `p1`.
+///
+/// All rectangles have the same orientation, so overlapping ones don't cancel out with the
+/// non-zero fill rule.
+fn hairline_segment(p0: kurbo::Point, p1: kurbo::Point, line_buf: &mut Vec<FlatLine>) {
+    let dir = (p1 - p0).normalize();
+    // Zero-length lines, like zero-length dashes, become a square pixel.
+    let dir = if dir.is_finite() {
+        dir
+    } else {
+        Vec2::new(1.0, 0.0)
+    };
+    let along = dir * 0.5;
+    let across = Vec2::new(-dir.y, dir.x) * 0.5;
+
+    let corners = [
+        p0 - along + across,
+        p1 + along + across,
+        p1 + along - across,
+        p0 - along - across,
+    ];
+
+    for i in 0..corners.len() {
+        let c0 = corners[i];
+        let c1 = corners[(i + 1) % corners.len()];
+        line_buf.push(FlatLine::new(
+            Point::new(c0.x as f32, c0.y as f32),
+            Point::new(c1.x as f32, c1.y as f32),
+        ));
+    }
+}
+
+/// The largest factor by which the transform scales a vector, i.e. its largest singular
+/// value.
+fn max_scale(affine: Affine) -> f64 {
+    let [a, b, c, d, _, _] = affine.as_coeffs();
+    let sum_sq = a * a + b * b + c * c + d * d;
+    let det = a * d - b * c;
+    let discriminant = (sum_sq * sum_sq - 4.0 * det * det).max(0.0);
+
+    ((sum_sq + discriminant.sqrt()) * 0.5).sqrt()
+}
+
+/// The length of the line that represents a dash of length zero.
+///
+/// Zero-length dashes still need a direction so that the stroker can orient their caps.
+const ZERO_DASH_LEN: f64 = 1e-6;
+
+/// The maximum number of dashes per path. Paths that would have more dashes (for example
+/// because the pattern is tiny compared to the path) are stroked without dashes instead,
+/// so that the time and memory spent on them stays bounded.
+const MAX_DASHES: usize = 100_000;
+
+/// Split a path into its dashes, each of which becomes a separate open subpath.
+///
+/// The dash pattern restarts at the beginning of each subpath, with the offset applied.
+/// On closed subpaths, a dash that runs through the start point is kept in one piece.
+/// Returns `None` if the path shouldn't be dashed, i.e. if the pattern is empty, contains
+/// negative lengths or only contains zeros, or if it would yield more than [`MAX_DASHES`]
+/// dashes.
+fn dash(path: &BezPath, pattern: &[f64], offset: f64, tolerance: f64) -> Option<BezPath> {
+    if pattern.is_empty() || pattern.iter().any(|d| !d.is_finite() || *d < 0.0) {
+        return None;
+    }
+
+    // An odd number of lengths is repeated to yield an even number of lengths.
+    let pattern = if pattern.len() % 2 == 1 {
+        [pattern, pattern].concat()
+    } else {
+        pattern.to_vec()
+    };
+    let total = pattern.iter().sum::<f64>();
+
+    if total <= 0.0 || !offset.is_finite() {
+        return None;
+    }
+
+    let mut dasher = Dasher {
+        pattern: &pattern,
+        start: DashState::new(&pattern, offset.rem_euclid(total)),
+        state: DashState::new(&pattern, 0.0),
+        dash_open: false,
+        subpath_start: 0,
+        dashes: 0,
+        out: BezPath::new(),
+        accuracy: tolerance * 0.1,
+    };
+
+    let mut start = kurbo::Point::default();
+    let mut p0 = kurbo::Point::default();
+
+    for el in path.elements() {
+        let seg = match *el {
+            PathEl::MoveTo(p) => {
+                dasher.start_subpath();
+                start = p;
+                p0 = p;
+                continue;
+            }
+            PathEl::LineTo(p) => PathSeg::Line(Line::new(p0, p)),
+            PathEl::QuadTo(p1, p2) => PathSeg::Quad(kurbo::QuadBez::new(p0, p1, p2)),
+            PathEl::CurveTo(p1, p2, p3) => PathSeg::Cubic(kurbo::CubicBez::new(p0, p1, p2, p3)),
+            PathEl::ClosePath => {
+                let seg = PathSeg::Line(Line::new(p0, start));
+                dasher.segment(seg)?;
+                dasher.close_subpath();
+                dasher.start_subpath();
+                p0 = start;
+                continue;
+            }
+        };
+
+        dasher.segment(seg)?;
+        p0 = seg.end();
+    }
+
+    Some(dasher.out)
+}
+
+/// The position within a dash pattern.
+#[derive(Debug, Clone, Copy)]
+struct DashState {
+    /// The index of the current dash or gap. Even indices are dashes.
+    ix: usize,
+    /// The remaining length of the current dash or gap.
+    remaining: f64,
+}
+
+impl DashState {
+    /// The state at distance `pos` from the start of the pattern.
+    fn new(pattern: &[f64], mut pos: f64) -> Self {
+        let mut ix = 0;
+
+        // A position on the boundary between two entries belongs to the second one, unless
+        // the first one is a zero-length dash, which would otherwise be skipped.
+        while pos > pattern[ix] || (pos == pattern[ix] && pattern[ix] > 0.0) {
+            pos -= pattern[ix];
+            ix = (ix + 1) % pattern.len();
+        }
+
+        Self {
+            ix,
+            remaining: pattern[ix] - pos,
+        }
+    }
+
+    // `usize::is_multiple_of` would require Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn is_dash(&self) -> bool {
+        self.ix % 2 == 0
+    }
+}
+
+struct Dasher<'a> {
+    pattern: &'a [f64],
+    /// The state at the start of each subpath.
+    start: DashState,
+    state: DashState,
+    /// Whether a dash has been started in `out` that hasn't ended yet.
+    dash_open: bool,
+    /// The number of elements in `out` before the current subpath.
+    subpath_start: usize,
+    /// The number of dashes that have been completed so far.
+    dashes: usize,
+    out: BezPath,
+    accuracy: f64,
+}
+
+impl Dasher<'_> {
+    fn start_subpath(&mut self) {
+        self.state = self.start;
+        self.dash_open = false;
+        self.subpath_start = self.out.elements().len();
+    }
+
+    /// Join the dash that is still open at the end of a closed subpath with the one at its
+    /// start, if the pattern is "on" at both ends, so that they are connected by a join
+    /// instead of having two caps at the start point.
+    fn close_subpath(&mut self) {
+        if !self.dash_open || !self.start.is_dash() || self.start.remaining <= 0.0 {
+            return;
+        }
+
+        let elements = &self.out.elements()[self.subpath_start..];
+        // The first dash ends where the next one starts.
+        let Some(first_len) = elements
+            .iter()
+            .skip(1)
+            .position(|el| matches!(el, PathEl::MoveTo(_)))
+            .map(|pos| pos + 1)
+        else {
+            // The whole subpath is covered by a single dash.
+            self.out.close_path();
+            return;
+        };
+
+        // The last dash ends at the start point of the subpath, where the first dash starts,
+        // so the first dash (without its move) can be appended to it.
+        let elements = elements.to_vec();
+        self.out.truncate(self.subpath_start);
+
+        for el in elements[first_len..].iter().chain(&elements[1..first_len]) {
+            self.out.push(*el);
+        }
+    }
+
+    /// Add the dashes covering a segment of the current subpath. Returns `None` if the
+    /// path has more than [`MAX_DASHES`] dashes.
+    fn segment(&mut self, seg: PathSeg) -> Option<()> {
+        let len = seg.arclen(self.accuracy);
+        let mut pos = 0.0;
+
+        loop {
+            let end = pos + self.state.remaining;
+
+            if end > len {
+                // The current dash or gap continues in the next segment.
+                if self.state.is_dash() && pos < len {
+                    self.emit(seg, pos, len);
+                }
+
+                self.state.remaining -= len - pos;
+                return Some(());
+            }
+
+            if self.state.is_dash() {
+                if self.state.remaining > 0.0 {
+                    self.emit(seg, pos, end);
+                } else {
+                    self.emit_zero_length(seg, pos, len);
+                }
+
+                self.dash_open = false;
+                self.dashes += 1;
+
+                if self.dashes > MAX_DASHES {
+                    return None;
+                }
+            }
+
+            pos = end;
+            self.state.ix = (self.state.ix + 1) % self.pattern.len();
+            self.state.remaining = self.pattern[self.state.ix];
+        }
+    }
+
+    /// Add the part of `seg` between the arc lengths `s0` and `s1` to the current dash.
+    fn emit(&mut self, seg: PathSeg, s0: f64, s1: f64) {
+        let t0 = seg.inv_arclen(s0, self.accuracy);
+        let t1 = seg.inv_arclen(s1, self.accuracy);
+        let sub = seg.subsegment(t0..t1);
+
+        if !self.dash_open {
+            self.out.move_to(sub.start());
+            self.dash_open = true;
+        }
+
+        match sub {
+            PathSeg::Line(l) => self.out.line_to(l.p1),
+            PathSeg::Quad(q) => self.out.quad_to(q.p1, q.p2),
+            PathSeg::Cubic(c) => self.out.curve_to(c.p1, c.p2, c.p3),
+        }
+    }
+
+    /// Add a dash of length zero at arc length `s`, oriented along the segment.
+    fn emit_zero_length(&mut self, seg: PathSeg, s: f64, len: f64) {
+        let t = if len > 0.0 {
+            seg.inv_arclen(s, self.accuracy)
+        } else {
+            0.0
+        };
+        let p = seg.eval(t);
+        let dir = tangent(seg, t).normalize();
+
+        // Segments without any direction can't have oriented caps.
+        if dir.is_finite() {
+            self.out.move_to(p);
+            self.out.line_to(p + dir * ZERO_DASH_LEN);
+        }
+    }
+}
+
+/// The direction of `seg` at `t`, falling back to the chord for degenerate derivatives.
+fn tangent(seg: PathSeg, t: f64) -> Vec2 {
+    let deriv = match seg {
+        PathSeg::Line(l) => l.p1 - l.p0,
+        PathSeg::Quad(q) => q.deriv().eval(t).to_vec2(),
+        PathSeg::Cubic(c) => c.deriv().eval(t).to_vec2(),
+    };
+
+    if deriv.hypot2() > 0.0 {
+        deriv
+    } else {
+        seg.end() - seg.start()
+    }
+}
+
 fn close_path(start: kurbo::Point, p0: kurbo::Point, line_buf: &mut Vec<FlatLine>) {
     let pt0 = Point::new(p0.x as f32, p0.y as f32);
     let pt1 = Point::new(start.x as f32, start.y as f32);
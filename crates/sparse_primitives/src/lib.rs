@@ -3,6 +3,7 @@
 
 #![cfg_attr(not(feature = "simd"), forbid(unsafe_code))]
 
+mod batch;
 pub mod execute;
 pub mod fine;
 pub mod flatten;
@@ -10,7 +11,10 @@ pub mod paint;
 pub mod pixmap;
 mod rect;
 pub mod render;
+pub mod scene;
+pub mod serialize;
 pub mod strip;
+pub mod target;
 pub mod tiling;
 mod util;
 pub mod wide_tile;
@@ -99,6 +103,55 @@ impl RenderContext {
         dispatch_mut!(func: stroke_path(path), self)
     }
 
+    /// Push a new layer. Everything that is drawn until the matching call to `pop_layer`
+    /// is rendered in isolation and then composited into the parent as a whole, using the
+    /// given blend mode and opacity.
+    ///
+    /// Layers that haven't been popped when rendering are popped implicitly.
+    pub fn push_layer(&mut self, blend_mode: BlendMode, alpha: f32) {
+        dispatch_mut!(func: push_layer(blend_mode, alpha), self)
+    }
+
+    /// Pop the last pushed layer. Does nothing if the last pushed layer, clip or mask isn't
+    /// a layer.
+    pub fn pop_layer(&mut self) {
+        dispatch_mut!(func: pop_layer(), self)
+    }
+
+    /// Start drawing a mask. Everything that is drawn until the next call to `apply_mask`
+    /// is rendered into the mask instead of the canvas.
+    pub fn push_mask(&mut self, mask_type: MaskType) {
+        dispatch_mut!(func: push_mask(mask_type), self)
+    }
+
+    /// Finish drawing the mask that was pushed last. Everything that is drawn until the
+    /// matching call to `pop_mask` is modulated by the alpha or luminance of the mask.
+    pub fn apply_mask(&mut self) {
+        dispatch_mut!(func: apply_mask(), self)
+    }
+
+    /// Pop the last pushed mask and composite the masked content. Does nothing if the last
+    /// pushed layer, clip or mask isn't a mask.
+    ///
+    /// Masks that haven't been popped when rendering are popped implicitly.
+    pub fn pop_mask(&mut self) {
+        dispatch_mut!(func: pop_mask(), self)
+    }
+
+    /// Push a clip path. Everything that is drawn until the matching call to `pop_clip`
+    /// is only visible inside of the path, which is filled using the given fill rule.
+    ///
+    /// Clips that haven't been popped when rendering are popped implicitly.
+    pub fn push_clip(&mut self, path: &BezPath, fill_rule: Fill) {
+        dispatch_mut!(func: push_clip(path, fill_rule), self)
+    }
+
+    /// Pop the last pushed clip path. Does nothing if the last pushed layer, clip or mask
+    /// isn't a clip.
+    pub fn pop_clip(&mut self) {
+        dispatch_mut!(func: pop_clip(), self)
+    }
+
     pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
         dispatch_mut!(func: set_blend_mode(blend_mode), self)
     }
@@ -109,7 +162,11 @@ impl RenderContext {
     }
 
     /// Set the paint for filling and stroking operations.
-    pub fn set_paint(&mut self, paint: Paint) {
+    ///
+    /// Besides the paints of this crate, this accepts colors, gradients, images and brushes
+    /// from `peniko`.
+    pub fn set_paint(&mut self, paint: impl Into<Paint>) {
+        let paint = paint.into();
         dispatch_mut!(func: set_paint(paint), self)
     }
 
@@ -118,6 +175,39 @@ impl RenderContext {
         dispatch_mut!(func: set_fill_rule(fill_rule), self)
     }
 
+    /// Set the flattening tolerance, i.e. the maximum distance in device pixels between the
+    /// lines that curves are approximated with and the true curves.
+    ///
+    /// It applies to filling and stroking paths, including clip paths, and to converting
+    /// shapes into paths. Larger values are faster to render, but less accurate. The
+    /// default is [`flatten::DEFAULT_TOLERANCE`].
+    ///
+    /// Panics if the tolerance isn't positive and finite.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        dispatch_mut!(func: set_tolerance(tolerance), self)
+    }
+
+    /// Get the current flattening tolerance.
+    pub fn tolerance(&self) -> f64 {
+        dispatch!(func: tolerance(), self)
+    }
+
+    /// Set whether paths are anti-aliased.
+    ///
+    /// If disabled, each pixel is either fully covered or not covered at all, depending on
+    /// whether its center lies inside of the path. This is useful for rendering hit-test
+    /// buffers or ID maps, where colors should never be blended at the edges of shapes.
+    /// It applies to filling and stroking paths, including clip paths. Anti-aliasing is
+    /// enabled by default.
+    pub fn set_anti_aliasing(&mut self, anti_alias: bool) {
+        dispatch_mut!(func: set_anti_aliasing(anti_alias), self)
+    }
+
+    /// Get whether paths are anti-aliased.
+    pub fn anti_aliasing(&self) -> bool {
+        dispatch!(func: anti_aliasing(), self)
+    }
+
     /// Pre-concatenate a transform to the current transformation matrix.
     pub fn pre_concat_transform(&mut self, transform: Affine) {
         dispatch_mut!(func: pre_concat_transform(transform), self)
@@ -148,11 +238,94 @@ impl RenderContext {
         dispatch_mut!(func: reset(), self)
     }
 
+    /// Draw a recorded scene, with `transform` applied on top of the transforms of the
+    /// scene.
+    ///
+    /// The scene is drawn with its own paint, stroke, fill rule and blend mode, starting
+    /// from the defaults of a new render context. The state of the render context itself
+    /// isn't changed: layers, clips and masks that the scene leaves open are popped
+    /// afterward, and the scene can't pop the ones of the render context.
+    pub fn render_scene(&mut self, scene: &Scene, transform: Affine) {
+        dispatch_mut!(func: render_scene(scene, transform), self)
+    }
+
+    /// Set the number of threads used for flattening, tiling and generating the strips
+    /// of paths.
+    ///
+    /// If `num_threads` is greater than 1, `fill_path` and `stroke_path` only record the
+    /// paths, which are then processed in parallel once the batch is flushed. Operations
+    /// that depend on the previously drawn paths, like pushing layers or filling
+    /// rectangles, flush the batch implicitly, but [`RenderContext::flush`] needs to be
+    /// called before rendering.
+    pub fn set_batch_threads(&mut self, num_threads: usize) {
+        dispatch_mut!(func: set_batch_threads(num_threads), self)
+    }
+
+    /// Process all paths that have been batched since the last flush.
+    pub fn flush(&mut self) {
+        dispatch_mut!(func: flush(), self)
+    }
+
     /// Render the current render context into a pixmap.
+    ///
+    /// The pixmap should have the same size as the render context. Otherwise, only the
+    /// part that is covered by both is rendered, and the other pixels of the pixmap are
+    /// left untouched.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet.
     pub fn render_to_pixmap(&self, pixmap: &mut Pixmap) {
         dispatch!(func: render_to_pixmap(pixmap), self)
     }
 
+    /// Render the current render context into a pixmap, using `num_threads` threads.
+    ///
+    /// The rows of wide tiles are distributed across the threads. The result is identical
+    /// to the one of `render_to_pixmap`.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet.
+    pub fn render_to_pixmap_parallel(&self, pixmap: &mut Pixmap, num_threads: usize) {
+        dispatch!(func: render_to_pixmap_parallel(pixmap, num_threads), self)
+    }
+
+    /// Render the part of the canvas covered by `region` into a pixmap, for example to
+    /// update only the damaged parts of a window.
+    ///
+    /// The top-left corner of the pixmap corresponds to the top-left corner of the region,
+    /// rounded outward to whole pixels, so a pixmap with the size of the region covers
+    /// all of it. Only the wide tiles intersecting the region are rasterized, and pixels
+    /// outside of the region or the canvas are left untouched.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet.
+    pub fn render_region(&self, region: &Rect, pixmap: &mut Pixmap) {
+        dispatch!(func: render_region(region, pixmap), self)
+    }
+
+    /// Render the part of the canvas covered by `region` into a caller-owned buffer.
+    ///
+    /// See [`RenderContext::render_region`] for details.
+    pub fn render_region_to_target(&self, region: &Rect, target: &mut RenderTarget) {
+        dispatch!(func: render_region_to_target(region, target), self)
+    }
+
+    /// Render the current render context into a caller-owned buffer, converting the pixels
+    /// to the pixel format and alpha mode of the render target.
+    ///
+    /// Like with [`RenderContext::render_to_pixmap`], only the part that is covered by both
+    /// the render context and the render target is rendered if their sizes differ.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet.
+    pub fn render_to_target(&self, target: &mut RenderTarget) {
+        dispatch!(func: render_to_target(target), self)
+    }
+
+    /// Render the current render context into a caller-owned buffer, using `num_threads`
+    /// threads.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet.
+    pub fn render_to_target_parallel(&self, target: &mut RenderTarget, num_threads: usize) {
+        dispatch!(func: render_to_target_parallel(target, num_threads), self)
+    }
+
     /// Get the width of the render context.
     pub fn width(&self) -> usize {
         dispatch!(func: width(), self)
@@ -164,6 +337,9 @@ impl RenderContext {
     }
 
     /// Get the wide tiles of the render context.
+    ///
+    /// Panics if there are batched paths that haven't been flushed yet, since they would
+    /// be missing from the wide tiles.
     pub fn wide_tiles(&self) -> &[WideTile] {
         dispatch!(func: wide_tiles(), self)
     }
@@ -254,5 +430,8 @@ use crate::paint::Paint;
 use crate::render::InnerContext;
 use crate::strip::Strip;
 use crate::tiling::{FlatLine, Tiles};
+pub use crate::wide_tile::MaskType;
 use crate::wide_tile::WideTile;
 pub use pixmap::Pixmap;
+pub use scene::Scene;
+pub use target::{AlphaMode, PixelFormat, RenderTarget};
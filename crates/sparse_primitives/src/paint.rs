@@ -1,8 +1,19 @@
+use crate::pixmap::Pixmap;
 use peniko::color::{AlphaColor, Srgb};
+use peniko::kurbo::{Affine, Point};
+use peniko::{
+    Brush, BrushRef, ColorStops, ColorStopsSource, Extend, Gradient, GradientKind, ImageFormat,
+    ImageQuality,
+};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum Paint {
     Solid(AlphaColor<Srgb>),
+    LinearGradient(Arc<LinearGradient>),
+    RadialGradient(Arc<RadialGradient>),
+    SweepGradient(Arc<SweepGradient>),
+    Image(Arc<Image>),
 }
 
 impl From<AlphaColor<Srgb>> for Paint {
@@ -11,10 +22,480 @@ impl From<AlphaColor<Srgb>> for Paint {
     }
 }
 
+impl From<LinearGradient> for Paint {
+    fn from(value: LinearGradient) -> Self {
+        Paint::LinearGradient(Arc::new(value))
+    }
+}
+
+impl From<RadialGradient> for Paint {
+    fn from(value: RadialGradient) -> Self {
+        Paint::RadialGradient(Arc::new(value))
+    }
+}
+
+impl From<SweepGradient> for Paint {
+    fn from(value: SweepGradient) -> Self {
+        Paint::SweepGradient(Arc::new(value))
+    }
+}
+
+impl From<Image> for Paint {
+    fn from(value: Image) -> Self {
+        Paint::Image(Arc::new(value))
+    }
+}
+
 impl Paint {
+    /// The alpha value of the paint. For paints that don't have a uniform alpha value,
+    /// this is the smallest alpha value the paint can produce. Paints that leave some
+    /// pixels undefined (and thus transparent) have an alpha value of 0.
     pub fn alpha(&self) -> f32 {
         match self {
             Paint::Solid(s) => s.components[3],
+            Paint::LinearGradient(g) if g.is_defined_everywhere() => min_alpha(&g.stops),
+            Paint::RadialGradient(g) if g.is_defined_everywhere() => min_alpha(&g.stops),
+            Paint::SweepGradient(g) if g.is_defined_everywhere() => min_alpha(&g.stops),
+            Paint::Image(i) if i.is_defined_everywhere() => i.min_alpha * i.alpha,
+            _ => 0.0,
+        }
+    }
+
+    /// Whether the paint is guaranteed to produce a fully opaque color for each pixel.
+    pub fn is_opaque(&self) -> bool {
+        self.alpha() == 1.0
+    }
+
+    /// Return the paint with its alpha value multiplied by `alpha`.
+    pub fn multiply_alpha(self, alpha: f32) -> Paint {
+        if alpha == 1.0 {
+            return self;
+        }
+
+        fn multiply_stops(stops: &mut ColorStops, alpha: f32) {
+            for stop in stops.iter_mut() {
+                *stop = stop.multiply_alpha(alpha);
+            }
+        }
+
+        match self {
+            Paint::Solid(s) => Paint::Solid(s.multiply_alpha(alpha)),
+            Paint::LinearGradient(mut g) => {
+                multiply_stops(&mut Arc::make_mut(&mut g).stops, alpha);
+                Paint::LinearGradient(g)
+            }
+            Paint::RadialGradient(mut g) => {
+                multiply_stops(&mut Arc::make_mut(&mut g).stops, alpha);
+                Paint::RadialGradient(g)
+            }
+            Paint::SweepGradient(mut g) => {
+                multiply_stops(&mut Arc::make_mut(&mut g).stops, alpha);
+                Paint::SweepGradient(g)
+            }
+            Paint::Image(mut i) => {
+                Arc::make_mut(&mut i).alpha *= alpha;
+                Paint::Image(i)
+            }
+        }
+    }
+
+    /// Return the paint with an additional transform applied to it, i.e. the transform
+    /// that maps from the coordinate system of the paint to the coordinate system of
+    /// the pixmap.
+    pub(crate) fn transformed(&self, transform: Affine) -> Paint {
+        match self {
+            Paint::Solid(_) => self.clone(),
+            Paint::LinearGradient(g) => Paint::LinearGradient(Arc::new(LinearGradient {
+                transform: transform * g.transform,
+                ..(**g).clone()
+            })),
+            Paint::RadialGradient(g) => Paint::RadialGradient(Arc::new(RadialGradient {
+                transform: transform * g.transform,
+                ..(**g).clone()
+            })),
+            Paint::SweepGradient(g) => Paint::SweepGradient(Arc::new(SweepGradient {
+                transform: transform * g.transform,
+                ..(**g).clone()
+            })),
+            Paint::Image(i) => Paint::Image(Arc::new(Image {
+                transform: transform * i.transform,
+                ..(**i).clone()
+            })),
+        }
+    }
+}
+
+/// A linear gradient.
+#[derive(Debug, Clone)]
+pub struct LinearGradient {
+    /// The start point of the gradient, which corresponds to offset 0.0.
+    pub start: Point,
+    /// The end point of the gradient, which corresponds to offset 1.0.
+    pub end: Point,
+    /// The color stops of the gradient, sorted by their offset.
+    pub stops: ColorStops,
+    /// How to extend the gradient beyond the start and end points.
+    pub extend: Extend,
+    /// An additional transform applied to the gradient, in addition to the
+    /// transform of the render context.
+    pub transform: Affine,
+}
+
+impl LinearGradient {
+    /// Create a new linear gradient with pad extend mode.
+    pub fn new(
+        start: impl Into<Point>,
+        end: impl Into<Point>,
+        stops: impl ColorStopsSource,
+    ) -> Self {
+        let mut color_stops = ColorStops::new();
+        stops.collect_stops(&mut color_stops);
+
+        Self {
+            start: start.into(),
+            end: end.into(),
+            stops: color_stops,
+            extend: Extend::Pad,
+            transform: Affine::IDENTITY,
+        }
+    }
+
+    /// Set the extend mode of the gradient.
+    pub fn with_extend(mut self, extend: Extend) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Set the transform of the gradient.
+    pub fn with_transform(mut self, transform: Affine) -> Self {
+        self.transform = transform;
+        self
+    }
+}
+
+impl LinearGradient {
+    fn is_defined_everywhere(&self) -> bool {
+        self.start != self.end && is_invertible(self.transform)
+    }
+}
+
+/// A two-point conical gradient, defined by a start and an end circle.
+///
+/// For each point, the color is determined by the largest `t` for which the point
+/// lies on the circle interpolated between the start circle (`t = 0`) and the end
+/// circle (`t = 1`) and that circle has a non-negative radius. Points that don't lie
+/// on any such circle are left transparent.
+#[derive(Debug, Clone)]
+pub struct RadialGradient {
+    /// The center of the start circle.
+    pub start_center: Point,
+    /// The radius of the start circle.
+    pub start_radius: f32,
+    /// The center of the end circle.
+    pub end_center: Point,
+    /// The radius of the end circle.
+    pub end_radius: f32,
+    /// The color stops of the gradient, sorted by their offset.
+    pub stops: ColorStops,
+    /// How to extend the gradient beyond the start and end circles.
+    pub extend: Extend,
+    /// An additional transform applied to the gradient, in addition to the
+    /// transform of the render context.
+    pub transform: Affine,
+}
+
+impl RadialGradient {
+    /// Create a new radial gradient with the given center and radius and pad extend mode.
+    pub fn new(center: impl Into<Point>, radius: f32, stops: impl ColorStopsSource) -> Self {
+        let center = center.into();
+
+        Self::new_two_point(center, 0.0, center, radius, stops)
+    }
+
+    /// Create a new two-point conical gradient with pad extend mode.
+    pub fn new_two_point(
+        start_center: impl Into<Point>,
+        start_radius: f32,
+        end_center: impl Into<Point>,
+        end_radius: f32,
+        stops: impl ColorStopsSource,
+    ) -> Self {
+        let mut color_stops = ColorStops::new();
+        stops.collect_stops(&mut color_stops);
+
+        Self {
+            start_center: start_center.into(),
+            start_radius,
+            end_center: end_center.into(),
+            end_radius,
+            stops: color_stops,
+            extend: Extend::Pad,
+            transform: Affine::IDENTITY,
+        }
+    }
+
+    /// Set the extend mode of the gradient.
+    pub fn with_extend(mut self, extend: Extend) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Set the transform of the gradient.
+    pub fn with_transform(mut self, transform: Affine) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    fn is_defined_everywhere(&self) -> bool {
+        // If one circle strictly contains the other, the interpolated circles sweep
+        // over the whole plane.
+        let dc = (self.end_center - self.start_center).hypot();
+        let dr = (self.end_radius - self.start_radius).abs() as f64;
+
+        self.start_radius >= 0.0
+            && self.end_radius >= 0.0
+            && dc < dr
+            && is_invertible(self.transform)
+    }
+}
+
+/// A sweep gradient, which transitions between colors around a center point.
+///
+/// Angles are in radians and measured from the x-axis towards the y-axis, i.e. clockwise
+/// in the usual coordinate system with the y-axis pointing down, like in vello. `peniko`
+/// describes the same convention as "counter-clockwise" relative to a y-axis pointing up,
+/// so its sweep gradients are converted without changing the angles. The gradient
+/// starts at `start_angle` (offset 0.0) and ends at `end_angle` (offset 1.0), and the
+/// seam where the gradient wraps around lies at `start_angle`. If `end_angle` is smaller
+/// than `start_angle`, the gradient sweeps in the opposite direction.
+#[derive(Debug, Clone)]
+pub struct SweepGradient {
+    /// The center of the gradient.
+    pub center: Point,
+    /// The angle at which the gradient starts.
+    pub start_angle: f32,
+    /// The angle at which the gradient ends.
+    pub end_angle: f32,
+    /// The color stops of the gradient, sorted by their offset.
+    pub stops: ColorStops,
+    /// How to extend the gradient beyond the end angle.
+    pub extend: Extend,
+    /// An additional transform applied to the gradient, in addition to the
+    /// transform of the render context.
+    pub transform: Affine,
+}
+
+impl SweepGradient {
+    /// Create a new sweep gradient with pad extend mode.
+    pub fn new(
+        center: impl Into<Point>,
+        start_angle: f32,
+        end_angle: f32,
+        stops: impl ColorStopsSource,
+    ) -> Self {
+        let mut color_stops = ColorStops::new();
+        stops.collect_stops(&mut color_stops);
+
+        Self {
+            center: center.into(),
+            start_angle,
+            end_angle,
+            stops: color_stops,
+            extend: Extend::Pad,
+            transform: Affine::IDENTITY,
+        }
+    }
+
+    /// Set the extend mode of the gradient.
+    pub fn with_extend(mut self, extend: Extend) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Set the transform of the gradient.
+    pub fn with_transform(mut self, transform: Affine) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    fn is_defined_everywhere(&self) -> bool {
+        self.start_angle != self.end_angle && is_invertible(self.transform)
+    }
+}
+
+/// An image paint.
+///
+/// The image is placed with its top-left corner at the origin, with each pixel
+/// covering a unit square.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pixmap: Arc<Pixmap>,
+    /// The smallest alpha value of all pixels in the pixmap.
+    min_alpha: f32,
+    /// How to extend the image in the horizontal direction.
+    pub x_extend: Extend,
+    /// How to extend the image in the vertical direction.
+    pub y_extend: Extend,
+    /// The sampling quality, i.e. nearest neighbor (low), bilinear (medium) or
+    /// bicubic (high) sampling.
+    pub quality: ImageQuality,
+    /// An additional alpha multiplier applied to the image.
+    pub alpha: f32,
+    /// An additional transform applied to the image, in addition to the
+    /// transform of the render context.
+    pub transform: Affine,
+}
+
+impl Image {
+    /// Create a new image paint from a pixmap with premultiplied alpha, with pad
+    /// extend mode and medium quality.
+    pub fn new(pixmap: impl Into<Arc<Pixmap>>) -> Self {
+        let pixmap = pixmap.into();
+        let min_alpha = pixmap.buf.chunks_exact(4).map(|p| p[3]).min().unwrap_or(0) as f32 / 255.0;
+
+        Self {
+            pixmap,
+            min_alpha,
+            x_extend: Extend::Pad,
+            y_extend: Extend::Pad,
+            quality: ImageQuality::Medium,
+            alpha: 1.0,
+            transform: Affine::IDENTITY,
+        }
+    }
+
+    /// The pixmap of the image.
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    /// Set the extend mode of the image in both directions.
+    pub fn with_extend(mut self, extend: Extend) -> Self {
+        self.x_extend = extend;
+        self.y_extend = extend;
+        self
+    }
+
+    /// Set the sampling quality of the image.
+    pub fn with_quality(mut self, quality: ImageQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Set the transform of the image.
+    pub fn with_transform(mut self, transform: Affine) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    fn is_defined_everywhere(&self) -> bool {
+        self.pixmap.width > 0 && self.pixmap.height > 0 && is_invertible(self.transform)
+    }
+}
+
+impl From<&peniko::Image> for Image {
+    /// Convert a peniko image with separate alpha into an image paint.
+    ///
+    /// Images in an unsupported format or with less data than required by their size
+    /// result in a transparent image.
+    fn from(value: &peniko::Image) -> Self {
+        let data = value.data.data();
+        let pixmap = match value.format.size_in_bytes(value.width, value.height) {
+            Some(len) if value.format == ImageFormat::Rgba8 && data.len() >= len => {
+                let mut pixmap = Pixmap::new(value.width as usize, value.height as usize);
+                pixmap.buf.copy_from_slice(&data[..len]);
+                pixmap.premultiply();
+
+                pixmap
+            }
+            _ => Pixmap::new(0, 0),
+        };
+
+        Self {
+            x_extend: value.x_extend,
+            y_extend: value.y_extend,
+            quality: value.quality,
+            alpha: value.alpha,
+            ..Self::new(pixmap)
+        }
+    }
+}
+
+impl From<&Gradient> for Paint {
+    /// Convert a peniko gradient into a gradient paint.
+    ///
+    /// Note that colors are always interpolated in sRGB, regardless of the interpolation
+    /// color space of the gradient.
+    fn from(value: &Gradient) -> Self {
+        let stops = value.stops.as_slice();
+
+        match value.kind {
+            GradientKind::Linear { start, end } => LinearGradient::new(start, end, stops)
+                .with_extend(value.extend)
+                .into(),
+            GradientKind::Radial {
+                start_center,
+                start_radius,
+                end_center,
+                end_radius,
+            } => RadialGradient::new_two_point(
+                start_center,
+                start_radius,
+                end_center,
+                end_radius,
+                stops,
+            )
+            .with_extend(value.extend)
+            .into(),
+            GradientKind::Sweep {
+                center,
+                start_angle,
+                end_angle,
+            } => SweepGradient::new(center, start_angle, end_angle, stops)
+                .with_extend(value.extend)
+                .into(),
+        }
+    }
+}
+
+impl From<&peniko::Image> for Paint {
+    fn from(value: &peniko::Image) -> Self {
+        Image::from(value).into()
+    }
+}
+
+impl From<BrushRef<'_>> for Paint {
+    fn from(value: BrushRef<'_>) -> Self {
+        match value {
+            BrushRef::Solid(c) => c.into(),
+            BrushRef::Gradient(g) => g.into(),
+            BrushRef::Image(i) => i.into(),
         }
     }
 }
+
+impl From<&Brush> for Paint {
+    fn from(value: &Brush) -> Self {
+        BrushRef::from(value).into()
+    }
+}
+
+impl From<Brush> for Paint {
+    fn from(value: Brush) -> Self {
+        (&value).into()
+    }
+}
+
+fn is_invertible(transform: Affine) -> bool {
+    let det = transform.determinant();
+
+    det != 0.0 && det.is_finite()
+}
+
+fn min_alpha(stops: &ColorStops) -> f32 {
+    stops
+        .iter()
+        .map(|s| s.color.components[3])
+        .reduce(f32::min)
+        .unwrap_or(0.0)
+}
@@ -28,13 +28,88 @@ impl Pixmap {
     ///
     /// Not fast, but useful for saving to PNG etc.
     pub fn unpremultiply(&mut self) {
+        unpremultiply(&mut self.buf);
+    }
+
+    /// Convert from separate alpha to premultiplied alpha.
+    pub(crate) fn premultiply(&mut self) {
         for rgba in self.buf.chunks_exact_mut(4) {
             let alpha = rgba[3] as f32 * (1.0 / 255.0);
-            if alpha != 0.0 {
-                rgba[0] = (rgba[0] as f32 / alpha).round().min(255.0) as u8;
-                rgba[1] = (rgba[1] as f32 / alpha).round().min(255.0) as u8;
-                rgba[2] = (rgba[2] as f32 / alpha).round().min(255.0) as u8;
+            rgba[0] = (rgba[0] as f32 * alpha + 0.5) as u8;
+            rgba[1] = (rgba[1] as f32 * alpha + 0.5) as u8;
+            rgba[2] = (rgba[2] as f32 * alpha + 0.5) as u8;
+        }
+    }
+
+    /// Decode a PNG image into a pixmap with premultiplied alpha.
+    ///
+    /// Images with a bit depth other than 8 or with a color palette are converted to
+    /// 8-bit RGB(A) first.
+    #[cfg(feature = "png")]
+    pub fn from_png(data: &[u8]) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        let data = &buf[..info.buffer_size()];
+
+        let mut pixmap = Self::new(info.width as usize, info.height as usize);
+        let pixels = pixmap.buf.chunks_exact_mut(4);
+
+        match info.color_type {
+            png::ColorType::Rgba => pixmap.buf.copy_from_slice(data),
+            png::ColorType::Rgb => {
+                for (rgba, rgb) in pixels.zip(data.chunks_exact(3)) {
+                    rgba.copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+                }
+            }
+            png::ColorType::GrayscaleAlpha => {
+                for (rgba, ga) in pixels.zip(data.chunks_exact(2)) {
+                    rgba.copy_from_slice(&[ga[0], ga[0], ga[0], ga[1]]);
+                }
+            }
+            png::ColorType::Grayscale => {
+                for (rgba, g) in pixels.zip(data) {
+                    rgba.copy_from_slice(&[*g, *g, *g, 255]);
+                }
             }
+            png::ColorType::Indexed => unreachable!("indexed images are expanded to RGB(A)"),
+        }
+
+        pixmap.premultiply();
+
+        Ok(pixmap)
+    }
+
+    /// Encode the pixmap as an 8-bit RGBA PNG image with separate alpha.
+    #[cfg(feature = "png")]
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut data = self.buf.clone();
+        unpremultiply(&mut data);
+
+        let mut out = vec![];
+        let mut encoder = png::Encoder::new(&mut out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+
+        Ok(out)
+    }
+}
+
+/// Convert the RGBA8 pixels in `buf` from premultiplied to separate alpha.
+pub(crate) fn unpremultiply(buf: &mut [u8]) {
+    for rgba in buf.chunks_exact_mut(4) {
+        let alpha = rgba[3] as f32 * (1.0 / 255.0);
+        if alpha != 0.0 {
+            rgba[0] = (rgba[0] as f32 / alpha).round().min(255.0) as u8;
+            rgba[1] = (rgba[1] as f32 / alpha).round().min(255.0) as u8;
+            rgba[2] = (rgba[2] as f32 / alpha).round().min(255.0) as u8;
         }
     }
 }
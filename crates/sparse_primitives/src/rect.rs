@@ -1,21 +1,195 @@
-//! Accelerators for filling and stroking rectangles more efficiently.
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Accelerators for filling rectangles.
 //!
-//! When filling/stroking simple rectangles, we can make quite a few optimizations that are
-//! not necessary for arbitrary paths. For example, alpha calculation becomes much easier,
-//! which means that we don't need to go through the steps "make tiles", "sort tiles" and "create
-//! strips", but can instead straight away generate appropriate strip and fill commands for the
-//! corresponding wide tiles, based on the coordinates of the rectangle.
+//! Axis-aligned rectangles are so common that it is worth special-casing them. Instead of
+//! going through the usual path pipeline, we skip "make tiles", "sort tiles" and
+//! "create strips" and directly generate the strip and fill commands for each wide tile,
+//! computing the coverage of the fractional edges and corners analytically.
 
 use crate::execute::KernelExecutor;
-use crate::render::{InnerContext, DEFAULT_TOLERANCE};
-use peniko::kurbo::{Rect, Shape};
+use crate::paint::Paint;
+use crate::render::{draw_span, InnerContext, Span};
+use crate::wide_tile::{STRIP_HEIGHT, WIDE_TILE_WIDTH};
+use peniko::kurbo::{Affine, Rect, Shape};
 
 impl<KE: KernelExecutor> InnerContext<KE> {
     pub(crate) fn fill_rect(&mut self, rect: &Rect) {
-        self.fill_path(&rect.to_path(DEFAULT_TOLERANCE).into());
+        match device_rect(rect, self.state.transform) {
+            Some(device_rect) => {
+                // The commands are generated immediately, so all batched paths need to
+                // be processed first.
+                self.flush();
+
+                let device_rect = if self.state.anti_alias {
+                    device_rect
+                } else {
+                    snap_to_pixel_centers(&device_rect)
+                };
+
+                self.render_rect(
+                    &device_rect,
+                    self.state.paint.transformed(self.state.transform),
+                )
+            }
+            // If the transform contains a rotation or skew, the rectangle isn't aligned
+            // with the pixel grid anymore, so we need to render it like any other path.
+            None => self.fill_path(&rect.to_path(self.state.tolerance)),
+        }
     }
 
     pub(crate) fn stroke_rect(&mut self, rect: &Rect) {
-        self.stroke_path(&rect.to_path(DEFAULT_TOLERANCE).into());
+        // Depending on the line join, the outline of the stroke isn't a rectangle, so we
+        // always use the path pipeline.
+        self.stroke_path(&rect.to_path(self.state.tolerance));
+    }
+
+    /// Generate the commands for a rectangle in device coordinates.
+    ///
+    /// The fill rule doesn't matter here, since the winding number is either 0 or 1.
+    fn render_rect(&mut self, rect: &Rect, paint: Paint) {
+        let width_tiles = self.width.div_ceil(WIDE_TILE_WIDTH);
+        let height_tiles = self.height.div_ceil(STRIP_HEIGHT);
+
+        // Clamping would turn NaN coordinates into the edges of the viewport, so they need
+        // to be rejected first. Like in the path pipeline, nothing is drawn in this case.
+        if !rect.is_finite() {
+            return;
+        }
+
+        // Clip the rectangle to the viewport.
+        let x0 = (rect.x0 as f32).max(0.0);
+        let y0 = (rect.y0 as f32).max(0.0);
+        let x1 = (rect.x1 as f32).min(self.width as f32);
+        let y1 = (rect.y1 as f32).min((height_tiles * STRIP_HEIGHT) as f32);
+
+        if !(x0 < x1 && y0 < y1) {
+            return;
+        }
+
+        self.resetted = false;
+
+        // All pixels that are touched by the rectangle.
+        let px0 = x0.floor() as u32;
+        let px1 = x1.ceil() as u32;
+        // The pixels that are completely covered in the horizontal direction.
+        let fx0 = x0.ceil() as u32;
+        let fx1 = x1.floor() as u32;
+
+        let row0 = y0 as usize / STRIP_HEIGHT;
+        let row1 = (y1.ceil() as usize).div_ceil(STRIP_HEIGHT);
+        let blend_mode = self.state.blend_mode;
+
+        for row in row0..row1 {
+            let strip_y = (row * STRIP_HEIGHT) as f32;
+            let y_coverage: [f32; STRIP_HEIGHT] =
+                std::array::from_fn(|i| coverage(strip_y + i as f32, y0, y1));
+
+            let mut emit = |x_start: u32, x_end: u32, alpha_ix: Option<usize>| {
+                for_each_tile_span(row, x_start, x_end, alpha_ix, |span| {
+                    draw_span(
+                        &mut self.wide_tiles[span.row * width_tiles + span.xtile],
+                        &span,
+                        &paint,
+                        blend_mode,
+                    )
+                });
+            };
+
+            if y_coverage == [1.0; STRIP_HEIGHT] && fx0 < fx1 {
+                // The strip row is fully covered vertically, so we only need strips for
+                // the anti-aliased left and right edge and can fill everything in between.
+                let left_ix = push_alphas(&mut self.alphas, px0, fx0, x0, x1, &y_coverage);
+                let right_ix = push_alphas(&mut self.alphas, fx1, px1, x0, x1, &y_coverage);
+
+                emit(px0, fx0, Some(left_ix));
+                emit(fx0, fx1, None);
+                emit(fx1, px1, Some(right_ix));
+            } else {
+                let alpha_ix = push_alphas(&mut self.alphas, px0, px1, x0, x1, &y_coverage);
+
+                emit(px0, px1, Some(alpha_ix));
+            }
+        }
+    }
+}
+
+/// Return the rectangle in device coordinates if the transform maps it to another
+/// axis-aligned rectangle, i.e. if it only consists of scaling, translation and rotations
+/// by multiples of 90 degrees.
+fn device_rect(rect: &Rect, transform: Affine) -> Option<Rect> {
+    let [a, b, c, d, _, _] = transform.as_coeffs();
+
+    ((b == 0.0 && c == 0.0) || (a == 0.0 && d == 0.0)).then(|| transform.transform_rect_bbox(*rect))
+}
+
+/// Round the edges of a rectangle in device coordinates to whole pixels, such that exactly
+/// the pixels whose centers are covered by it are filled, like in aliased path rendering.
+fn snap_to_pixel_centers(rect: &Rect) -> Rect {
+    Rect::new(
+        (rect.x0 - 0.5).floor() + 1.0,
+        (rect.y0 - 0.5).ceil(),
+        (rect.x1 - 0.5).floor() + 1.0,
+        (rect.y1 - 0.5).ceil(),
+    )
+}
+
+/// The length of the intersection between the pixel starting at `p` and the range
+/// `start..end`.
+#[inline(always)]
+fn coverage(p: f32, start: f32, end: f32) -> f32 {
+    ((p + 1.0).min(end) - p.max(start)).clamp(0.0, 1.0)
+}
+
+/// Push the alpha values for the columns `x_start..x_end` of a rectangle spanning
+/// `x0..x1` horizontally and return the index of the first column.
+fn push_alphas(
+    alphas: &mut Vec<u32>,
+    x_start: u32,
+    x_end: u32,
+    x0: f32,
+    x1: f32,
+    y_coverage: &[f32; STRIP_HEIGHT],
+) -> usize {
+    let alpha_ix = alphas.len();
+
+    alphas.extend((x_start..x_end).map(|x| {
+        let x_coverage = coverage(x as f32, x0, x1);
+
+        y_coverage
+            .iter()
+            .enumerate()
+            .map(|(y, y_coverage)| ((x_coverage * y_coverage * 255.0 + 0.5) as u32) << (y * 8))
+            .sum::<u32>()
+    }));
+
+    alpha_ix
+}
+
+/// Split the columns `x_start..x_end` of a strip row into spans that each lie
+/// within a single wide tile.
+fn for_each_tile_span(
+    row: usize,
+    x_start: u32,
+    x_end: u32,
+    alpha_ix: Option<usize>,
+    mut f: impl FnMut(Span),
+) {
+    let mut x = x_start;
+
+    while x < x_end {
+        let xtile = x as usize / WIDE_TILE_WIDTH;
+        let width = x_end.min(((xtile + 1) * WIDE_TILE_WIDTH) as u32) - x;
+
+        f(Span {
+            row,
+            xtile,
+            x: x % WIDE_TILE_WIDTH as u32,
+            width,
+            alpha_ix: alpha_ix.map(|ix| ix + (x - x_start) as usize),
+        });
+
+        x += width;
     }
 }
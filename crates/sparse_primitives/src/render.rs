@@ -1,25 +1,86 @@
 // Copyright 2024 the Piet Authors
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+use crate::batch::BatchedPath;
 use crate::color::palette::css::BLACK;
 use crate::execute::KernelExecutor;
+use crate::flatten::DEFAULT_TOLERANCE;
 use crate::kurbo::{Cap, Join, Stroke};
 use crate::paint::Paint;
 use crate::strip::render_strips;
-use crate::tiling::Tiles;
+use crate::target::{RenderTarget, TargetLayout};
+use crate::tiling::{cull_lines, Tiles};
 use crate::util::ColorExt;
 use crate::{
     fine::Fine,
     strip::Strip,
     tiling::FlatLine,
-    wide_tile::{Cmd, CmdStrip, WideTile, STRIP_HEIGHT, WIDE_TILE_WIDTH},
+    wide_tile::{
+        Cmd, CmdClipFill, CmdClipStrip, CmdStrip, MaskType, WideTile, STRIP_HEIGHT, WIDE_TILE_WIDTH,
+    },
     Pixmap,
 };
-use peniko::kurbo::BezPath;
+use peniko::kurbo::{BezPath, Rect};
 use peniko::{color::AlphaColor, kurbo::Affine, BlendMode, Compose, Fill, Mix};
 use std::marker::PhantomData;
 
-pub(crate) const DEFAULT_TOLERANCE: f64 = 0.1;
+/// A layer or clip that has been pushed, but not popped yet.
+pub(crate) enum Layer {
+    Blend(BlendMode, f32),
+    /// How the clip path affects each wide tile.
+    Clip(Vec<TileClip>),
+    Mask {
+        mask_type: MaskType,
+        /// Whether the mask has been applied, i.e. whether we are drawing the masked
+        /// content instead of the mask itself.
+        applied: bool,
+    },
+}
+
+/// How a clip path affects a single wide tile.
+pub(crate) enum TileClip {
+    /// The tile lies completely outside of the clip path, so nothing is drawn into it.
+    Outside,
+    /// The tile is completely inside of the clip path, so it doesn't need to be masked.
+    Inside,
+    /// The tile is partially covered by the clip path. The commands composite the parts
+    /// of the clip buffer that are inside of the clip path into the parent buffer.
+    Partial(Vec<Cmd>),
+}
+
+/// The properties of a render context that determine how paths and rectangles are drawn.
+#[derive(Debug, Clone)]
+pub(crate) struct State {
+    pub(crate) paint: Paint,
+    pub(crate) stroke: Stroke,
+    pub(crate) transform: Affine,
+    pub(crate) fill_rule: Fill,
+    pub(crate) blend_mode: BlendMode,
+    /// The maximum distance in device pixels between flattened lines and the true curves.
+    pub(crate) tolerance: f64,
+    /// Whether the coverage of pixels is computed analytically or sampled at their centers.
+    pub(crate) anti_alias: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            paint: BLACK.into(),
+            stroke: Stroke {
+                width: 1.0,
+                join: Join::Bevel,
+                start_cap: Cap::Butt,
+                end_cap: Cap::Butt,
+                ..Default::default()
+            },
+            transform: Affine::IDENTITY,
+            fill_rule: Fill::NonZero,
+            blend_mode: BlendMode::new(Mix::Normal, Compose::SrcOver),
+            tolerance: DEFAULT_TOLERANCE,
+            anti_alias: true,
+        }
+    }
+}
 
 pub(crate) struct InnerContext<KE: KernelExecutor> {
     pub(crate) width: usize,
@@ -29,13 +90,16 @@ pub(crate) struct InnerContext<KE: KernelExecutor> {
     pub(crate) line_buf: Vec<FlatLine>,
     pub(crate) tiles: Tiles,
     pub(crate) strip_buf: Vec<Strip>,
-    pub(crate) paint: Paint,
-    pub(crate) stroke: Stroke,
-    pub(crate) transform: Affine,
-    pub(crate) fill_rule: Fill,
-    pub(crate) blend_mode: BlendMode,
+    pub(crate) state: State,
+    /// The layers and clips that haven't been popped yet.
+    pub(crate) layers: Vec<Layer>,
+    /// The number of threads used for batched coarse rasterization. Batching is disabled
+    /// if this is at most 1.
+    pub(crate) batch_threads: usize,
+    /// The paths that have been submitted since the batch was last flushed.
+    pub(crate) batch: Vec<BatchedPath>,
     // Whether the current context is cleared.
-    resetted: bool,
+    pub(crate) resetted: bool,
     phantom_data: PhantomData<KE>,
 }
 
@@ -45,8 +109,8 @@ impl<KE: KernelExecutor> InnerContext<KE> {
         let height_tiles = height.div_ceil(STRIP_HEIGHT);
         let mut wide_tiles = Vec::with_capacity(width_tiles * height_tiles);
 
-        for w in 0..width_tiles {
-            for h in 0..height_tiles {
+        for h in 0..height_tiles {
+            for w in 0..width_tiles {
                 wide_tiles.push(WideTile::new(w * WIDE_TILE_WIDTH, h * STRIP_HEIGHT));
             }
         }
@@ -57,18 +121,6 @@ impl<KE: KernelExecutor> InnerContext<KE> {
         let strip_buf = vec![];
         let cleared = true;
 
-        let transform = Affine::IDENTITY;
-        let fill_rule = Fill::NonZero;
-        let paint = BLACK.into();
-        let stroke = Stroke {
-            width: 1.0,
-            join: Join::Bevel,
-            start_cap: Cap::Butt,
-            end_cap: Cap::Butt,
-            ..Default::default()
-        };
-        let blend_mode = BlendMode::new(Mix::Normal, Compose::SrcOver);
-
         Self {
             width,
             height,
@@ -77,86 +129,506 @@ impl<KE: KernelExecutor> InnerContext<KE> {
             line_buf,
             tiles,
             strip_buf,
-            transform,
-            paint,
-            fill_rule,
-            stroke,
-            blend_mode,
+            state: State::default(),
+            layers: vec![],
+            batch_threads: 1,
+            batch: vec![],
             resetted: cleared,
             phantom_data: Default::default(),
         }
     }
 
     pub(crate) fn fill_path(&mut self, path: &BezPath) {
-        crate::flatten::fill(&path, self.transform, &mut self.line_buf);
-        self.render_path(self.fill_rule, self.paint.clone());
+        if self.batch_path(path, false) {
+            return;
+        }
+
+        crate::flatten::fill_in_viewport(
+            path,
+            self.state.transform,
+            self.state.tolerance,
+            self.width,
+            self.height,
+            &mut self.line_buf,
+        );
+        self.render_path(
+            self.state.fill_rule,
+            self.state.paint.transformed(self.state.transform),
+        );
     }
 
     pub(crate) fn stroke_path(&mut self, path: &BezPath) {
-        crate::flatten::stroke(&path, &self.stroke, self.transform, &mut self.line_buf);
-        self.render_path(Fill::NonZero, self.paint.clone());
+        if self.batch_path(path, true) {
+            return;
+        }
+
+        crate::flatten::stroke(
+            path,
+            &self.state.stroke,
+            self.state.transform,
+            self.state.tolerance,
+            &mut self.line_buf,
+        );
+        self.render_path(Fill::NonZero, self.stroke_paint());
+    }
+
+    /// The transformed paint for stroking, modulated by the coverage of hairlines.
+    ///
+    /// Without anti-aliasing, hairlines are drawn with the full paint, so that no colors
+    /// are blended.
+    pub(crate) fn stroke_paint(&self) -> Paint {
+        let paint = self.state.paint.transformed(self.state.transform);
+
+        if !self.state.anti_alias {
+            return paint;
+        }
+
+        match crate::flatten::hairline_alpha(&self.state.stroke, self.state.transform) {
+            Some(alpha) => paint.multiply_alpha(alpha),
+            None => paint,
+        }
+    }
+
+    pub(crate) fn push_layer(&mut self, blend_mode: BlendMode, alpha: f32) {
+        self.flush();
+
+        for tile in &mut self.wide_tiles {
+            if tile.n_zero_clip == 0 {
+                tile.push_buf();
+            }
+        }
+
+        self.layers.push(Layer::Blend(blend_mode, alpha));
+        self.resetted = false;
+    }
+
+    pub(crate) fn pop_layer(&mut self) {
+        self.flush();
+
+        let Some(Layer::Blend(blend_mode, alpha)) = self.layers.last() else {
+            return;
+        };
+        let (blend_mode, alpha) = (*blend_mode, *alpha);
+        self.layers.pop();
+
+        for tile in &mut self.wide_tiles {
+            if tile.n_zero_clip == 0 {
+                tile.pop_buf(blend_mode, alpha);
+            }
+        }
+    }
+
+    pub(crate) fn push_mask(&mut self, mask_type: MaskType) {
+        self.flush();
+
+        for tile in &mut self.wide_tiles {
+            if tile.n_zero_clip == 0 {
+                tile.push_buf();
+            }
+        }
+
+        self.layers.push(Layer::Mask {
+            mask_type,
+            applied: false,
+        });
+        self.resetted = false;
+    }
+
+    pub(crate) fn apply_mask(&mut self) {
+        self.flush();
+
+        let Some(Layer::Mask { applied, .. }) = self.layers.last_mut() else {
+            return;
+        };
+
+        if *applied {
+            return;
+        }
+
+        *applied = true;
+
+        for tile in &mut self.wide_tiles {
+            if tile.n_zero_clip == 0 {
+                tile.push_buf();
+            }
+        }
+    }
+
+    pub(crate) fn pop_mask(&mut self) {
+        self.flush();
+
+        let Some(Layer::Mask { mask_type, .. }) = self.layers.last() else {
+            return;
+        };
+        let mask_type = *mask_type;
+
+        self.apply_mask();
+        self.layers.pop();
+
+        for tile in &mut self.wide_tiles {
+            if tile.n_zero_clip == 0 {
+                tile.pop_mask(mask_type);
+            }
+        }
+    }
+
+    pub(crate) fn push_clip(&mut self, path: &BezPath, fill_rule: Fill) {
+        self.flush();
+
+        crate::flatten::fill_in_viewport(
+            path,
+            self.state.transform,
+            self.state.tolerance,
+            self.width,
+            self.height,
+            &mut self.line_buf,
+        );
+        cull_lines(&mut self.line_buf, self.width, self.height);
+        self.tiles.make_tiles(&self.line_buf);
+        self.tiles.sort_tiles();
+
+        render_strips::<KE>(
+            &self.tiles,
+            &mut self.strip_buf,
+            &mut self.alphas,
+            fill_rule,
+            self.state.anti_alias,
+        );
+
+        let width_tiles = self.wide_tiles_per_row();
+        let mut spans = vec![vec![]; self.wide_tiles.len()];
+
+        for_each_span(
+            &self.strip_buf,
+            fill_rule,
+            self.width,
+            self.height,
+            |span| {
+                spans[span.row * width_tiles + span.xtile].push(span);
+            },
+        );
+
+        let mut clips = Vec::with_capacity(self.wide_tiles.len());
+
+        for (tile, spans) in self.wide_tiles.iter_mut().zip(spans) {
+            // The last column of wide tiles can extend beyond the viewport, so it is also
+            // fully covered if the clip path covers everything up to the right edge.
+            let tile_width = (self.width - tile.x).min(WIDE_TILE_WIDTH) as u32;
+
+            let is_inside = matches!(
+                spans[..],
+                [Span { x: 0, width, alpha_ix: None, .. }] if width >= tile_width
+            );
+
+            let clip = if tile.n_zero_clip > 0 || spans.is_empty() {
+                tile.n_zero_clip += 1;
+
+                TileClip::Outside
+            } else if is_inside {
+                TileClip::Inside
+            } else {
+                tile.push_clip_buf();
+
+                TileClip::Partial(clip_cmds(&spans))
+            };
+
+            clips.push(clip);
+        }
+
+        self.layers.push(Layer::Clip(clips));
+        self.resetted = false;
+    }
+
+    pub(crate) fn pop_clip(&mut self) {
+        self.flush();
+
+        if !matches!(self.layers.last(), Some(Layer::Clip(_))) {
+            return;
+        }
+
+        let Some(Layer::Clip(clips)) = self.layers.pop() else {
+            unreachable!()
+        };
+
+        for (tile, clip) in self.wide_tiles.iter_mut().zip(clips) {
+            match clip {
+                TileClip::Outside => tile.n_zero_clip -= 1,
+                TileClip::Inside => {}
+                TileClip::Partial(cmds) => tile.pop_clip(cmds),
+            }
+        }
     }
 
     pub(crate) fn set_blend_mode(&mut self, blend_mode: BlendMode) {
-        self.blend_mode = blend_mode;
+        self.state.blend_mode = blend_mode;
     }
 
     pub(crate) fn set_stroke(&mut self, stroke: Stroke) {
-        self.stroke = stroke;
+        self.state.stroke = stroke;
     }
 
     pub(crate) fn set_paint(&mut self, paint: Paint) {
-        self.paint = paint;
+        self.state.paint = paint;
     }
 
     pub(crate) fn set_fill_rule(&mut self, fill_rule: Fill) {
-        self.fill_rule = fill_rule;
+        self.state.fill_rule = fill_rule;
+    }
+
+    pub(crate) fn set_tolerance(&mut self, tolerance: f64) {
+        assert!(
+            tolerance > 0.0 && tolerance.is_finite(),
+            "the tolerance must be positive and finite"
+        );
+
+        self.state.tolerance = tolerance;
+    }
+
+    pub(crate) fn tolerance(&self) -> f64 {
+        self.state.tolerance
+    }
+
+    pub(crate) fn set_anti_aliasing(&mut self, anti_alias: bool) {
+        self.state.anti_alias = anti_alias;
+    }
+
+    pub(crate) fn anti_aliasing(&self) -> bool {
+        self.state.anti_alias
     }
 
     pub(crate) fn pre_concat_transform(&mut self, transform: Affine) {
-        self.transform *= transform;
+        self.state.transform *= transform;
     }
 
     pub(crate) fn post_concat_transform(&mut self, transform: Affine) {
-        self.transform = transform * self.transform;
+        self.state.transform = transform * self.state.transform;
     }
 
     pub(crate) fn set_transform(&mut self, transform: Affine) {
-        self.transform = transform;
+        self.state.transform = transform;
     }
 
     pub(crate) fn reset_transform(&mut self) {
-        self.transform = Affine::IDENTITY;
+        self.state.transform = Affine::IDENTITY;
     }
 
     pub(crate) fn current_transform(&self) -> Affine {
-        self.transform
+        self.state.transform
     }
 
     pub(crate) fn reset(&mut self) {
+        self.batch.clear();
+
         if !self.resetted {
             for tile in &mut self.wide_tiles {
                 tile.bg = AlphaColor::TRANSPARENT;
                 tile.cmds.clear();
+                tile.n_bufs = 0;
+                tile.n_zero_clip = 0;
             }
 
+            self.layers.clear();
+
             self.resetted = true;
         }
     }
 
     pub(crate) fn render_to_pixmap(&self, pixmap: &mut Pixmap) {
-        let mut fine = Fine::<KE>::new(pixmap.width, pixmap.height, &mut pixmap.buf);
-
-        let width_tiles = self.width.div_ceil(WIDE_TILE_WIDTH);
-        let height_tiles = self.height.div_ceil(STRIP_HEIGHT);
-        for y in 0..height_tiles {
-            for x in 0..width_tiles {
-                let tile = &self.wide_tiles[y * width_tiles + x];
-                fine.clear(tile.bg.premultiply().to_rgba8_fast());
-                for cmd in &tile.cmds {
-                    fine.run_cmd(cmd, &self.alphas, cmd.compose());
+        self.render_to_target(&mut pixmap.into());
+    }
+
+    pub(crate) fn render_to_pixmap_parallel(&self, pixmap: &mut Pixmap, num_threads: usize) {
+        self.render_to_target_parallel(&mut pixmap.into(), num_threads);
+    }
+
+    pub(crate) fn render_to_target(&self, target: &mut RenderTarget) {
+        assert!(
+            self.batch.is_empty(),
+            "the batch needs to be flushed before rendering"
+        );
+
+        let layout = target.layout;
+
+        for (y, row) in self.target_rows(target).enumerate() {
+            self.render_row(y, &layout, row);
+        }
+    }
+
+    /// Render into a render target, splitting the rows of wide tiles across `num_threads`
+    /// threads.
+    ///
+    /// The output is identical to the one of `render_to_target`.
+    pub(crate) fn render_to_target_parallel(&self, target: &mut RenderTarget, num_threads: usize) {
+        if num_threads <= 1 {
+            return self.render_to_target(target);
+        }
+
+        assert!(
+            self.batch.is_empty(),
+            "the batch needs to be flushed before rendering"
+        );
+
+        let layout = target.layout;
+        let rows = self.target_rows(target);
+        // There is no point in spawning threads that don't get any rows.
+        let num_threads = num_threads.min(rows.len());
+        let mut thread_rows = (0..num_threads).map(|_| vec![]).collect::<Vec<_>>();
+
+        // Distribute the rows in a round-robin fashion, since the content is often
+        // not evenly distributed across the height of the pixmap.
+        for (y, row) in rows.enumerate() {
+            thread_rows[y % num_threads].push((y, row));
+        }
+
+        std::thread::scope(|s| {
+            for rows in thread_rows {
+                s.spawn(move || {
+                    for (y, row) in rows {
+                        self.render_row(y, &layout, row);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Split a render target into the parts covered by each row of wide tiles. Rows below
+    /// the canvas are skipped, since there are no wide tiles for them.
+    fn target_rows<'a>(
+        &self,
+        target: &'a mut RenderTarget,
+    ) -> impl ExactSizeIterator<Item = &'a mut [u8]> {
+        let canvas_rows = self.height.div_ceil(STRIP_HEIGHT);
+
+        target.wide_tile_rows(STRIP_HEIGHT).take(canvas_rows)
+    }
+
+    pub(crate) fn render_region(&self, region: &Rect, pixmap: &mut Pixmap) {
+        self.render_region_to_target(region, &mut pixmap.into());
+    }
+
+    /// Render the part of the canvas covered by `region` into a render target, whose
+    /// top-left corner corresponds to the top-left corner of the region, rounded outward
+    /// to whole pixels.
+    ///
+    /// Only the wide tiles intersecting the region are rasterized. Pixels of the target
+    /// outside of the region or the canvas are left untouched.
+    pub(crate) fn render_region_to_target(&self, region: &Rect, target: &mut RenderTarget) {
+        assert!(
+            self.batch.is_empty(),
+            "the batch needs to be flushed before rendering"
+        );
+
+        if region.is_nan() {
+            return;
+        }
+
+        // Intersect the region with the target and the canvas before converting the
+        // coordinates to integers, so that huge or infinite coordinates can't overflow.
+        let x0 = region.x0.floor();
+        let y0 = region.y0.floor();
+        let x1 = region
+            .x1
+            .ceil()
+            .min(x0 + target.layout.width as f64)
+            .min(self.width as f64);
+        let y1 = region
+            .y1
+            .ceil()
+            .min(y0 + target.layout.height as f64)
+            .min(self.height as f64);
+
+        if x1 <= x0.max(0.0) || y1 <= y0.max(0.0) {
+            return;
+        }
+
+        // All coordinates are now within the size of the target or the canvas.
+        let (x0, y0, x1, y1) = (x0 as isize, y0 as isize, x1 as isize, y1 as isize);
+
+        // Restrict the output to the pixels of the region, so that the parts of the wide
+        // tiles outside of it don't overwrite anything.
+        let layout = TargetLayout {
+            width: (x1 - x0) as usize,
+            height: (y1 - y0) as usize,
+            ..target.layout
+        };
+        let mut fine = Fine::<KE>::new_with_layout(layout, &mut *target.data);
+
+        let width_tiles = self.wide_tiles_per_row();
+        let xtiles = x0.max(0) as usize / WIDE_TILE_WIDTH..(x1 as usize).div_ceil(WIDE_TILE_WIDTH);
+        let ytiles = y0.max(0) as usize / STRIP_HEIGHT..(y1 as usize).div_ceil(STRIP_HEIGHT);
+
+        for y in ytiles {
+            for x in xtiles.clone() {
+                self.render_tile(&mut fine, x, y, width_tiles);
+                fine.pack(
+                    (x * WIDE_TILE_WIDTH) as isize - x0,
+                    (y * STRIP_HEIGHT) as isize - y0,
+                );
+            }
+        }
+    }
+
+    /// Render the wide tiles of row `y` into `out_buf`, which holds the pixels of that row
+    /// in a render target with the given layout.
+    ///
+    /// Only the pixels covered by both the canvas and the render target are written.
+    fn render_row(&self, y: usize, layout: &TargetLayout, out_buf: &mut [u8]) {
+        let width = layout.width.min(self.width);
+        let row_layout = TargetLayout {
+            width,
+            height: (layout.height.min(self.height) - y * STRIP_HEIGHT).min(STRIP_HEIGHT),
+            ..*layout
+        };
+        let mut fine = Fine::<KE>::new_with_layout(row_layout, out_buf);
+
+        let width_tiles = self.wide_tiles_per_row();
+        for x in 0..width.div_ceil(WIDE_TILE_WIDTH) {
+            self.render_tile(&mut fine, x, y, width_tiles);
+            // The output buffer only contains the current row.
+            fine.pack((x * WIDE_TILE_WIDTH) as isize, 0);
+        }
+    }
+
+    /// Rasterize the wide tile at `(x, y)` into the scratch buffer of `fine`.
+    fn render_tile(&self, fine: &mut Fine<KE>, x: usize, y: usize, width_tiles: usize) {
+        let tile = &self.wide_tiles[y * width_tiles + x];
+        fine.set_coords(x, y);
+        fine.clear(tile.bg.premultiply().to_rgba8_fast());
+        for cmd in &tile.cmds {
+            fine.run_cmd(cmd, &self.alphas);
+        }
+
+        self.pop_open_layers(fine, y * width_tiles + x);
+    }
+
+    /// Implicitly pop the layers and clips that are still open when rendering a wide tile.
+    fn pop_open_layers(&self, fine: &mut Fine<KE>, tile_ix: usize) {
+        // Layers and clips pushed after a clip that doesn't cover the tile at all didn't
+        // push anything.
+        let open = self
+            .layers
+            .iter()
+            .position(|l| matches!(l, Layer::Clip(c) if matches!(c[tile_ix], TileClip::Outside)))
+            .unwrap_or(self.layers.len());
+
+        for layer in self.layers[..open].iter().rev() {
+            match layer {
+                Layer::Blend(blend_mode, alpha) => fine.pop_buf(*blend_mode, *alpha),
+                Layer::Clip(clips) => {
+                    if let TileClip::Partial(cmds) = &clips[tile_ix] {
+                        for cmd in cmds {
+                            fine.run_cmd(cmd, &self.alphas);
+                        }
+                    }
+                }
+                Layer::Mask { mask_type, applied } => {
+                    if *applied {
+                        fine.pop_mask(*mask_type);
+                    } else {
+                        // Without content, there is nothing to mask.
+                        fine.drop_buf();
+                    }
                 }
-                fine.pack(x, y);
             }
         }
     }
@@ -170,6 +642,11 @@ impl<KE: KernelExecutor> InnerContext<KE> {
     }
 
     pub(crate) fn wide_tiles(&self) -> &[WideTile] {
+        assert!(
+            self.batch.is_empty(),
+            "the batch needs to be flushed before accessing the wide tiles"
+        );
+
         &self.wide_tiles
     }
 
@@ -190,6 +667,7 @@ impl<KE: KernelExecutor> InnerContext<KE> {
     }
 
     fn render_path(&mut self, fill_rule: Fill, paint: Paint) {
+        cull_lines(&mut self.line_buf, self.width, self.height);
         self.tiles.make_tiles(&self.line_buf);
         self.tiles.sort_tiles();
 
@@ -198,9 +676,10 @@ impl<KE: KernelExecutor> InnerContext<KE> {
             &mut self.strip_buf,
             &mut self.alphas,
             fill_rule,
+            self.state.anti_alias,
         );
 
-        self.generate_commands(fill_rule, paint);
+        self.generate_commands(fill_rule, paint, self.state.blend_mode);
     }
 
     fn wide_tiles_per_row(&self) -> usize {
@@ -208,7 +687,12 @@ impl<KE: KernelExecutor> InnerContext<KE> {
     }
 
     /// Generate the strip and fill commands for each wide tile using the current `strip_buf`.
-    pub(crate) fn generate_commands(&mut self, fill_rule: Fill, paint: Paint) {
+    pub(crate) fn generate_commands(
+        &mut self,
+        fill_rule: Fill,
+        paint: Paint,
+        blend_mode: BlendMode,
+    ) {
         let width_tiles = self.wide_tiles_per_row();
 
         if self.strip_buf.is_empty() {
@@ -219,80 +703,167 @@ impl<KE: KernelExecutor> InnerContext<KE> {
         // safe than sorry.
         self.resetted = false;
 
-        for i in 0..self.strip_buf.len() - 1 {
-            let strip = &self.strip_buf[i];
+        let wide_tiles = &mut self.wide_tiles;
 
-            if strip.x() >= self.width as i32 {
-                // Don't render strips that are outside the viewport.
-                continue;
-            }
+        for_each_span(
+            &self.strip_buf,
+            fill_rule,
+            self.width,
+            self.height,
+            |span| {
+                draw_span(
+                    &mut wide_tiles[span.row * width_tiles + span.xtile],
+                    &span,
+                    &paint,
+                    blend_mode,
+                )
+            },
+        );
+    }
+}
 
-            if strip.y() >= self.height as u16 {
-                // Since strips are sorted by location, any subsequent strips will also be
-                // outside the viewport, so we can abort entirely.
-                break;
-            }
+/// Generate the strip or fill command for a single span.
+pub(crate) fn draw_span(tile: &mut WideTile, span: &Span, paint: &Paint, blend_mode: BlendMode) {
+    // The tile lies outside of the current clip path.
+    if tile.n_zero_clip > 0 {
+        return;
+    }
+
+    match span.alpha_ix {
+        Some(alpha_ix) => tile.push(Cmd::Strip(CmdStrip {
+            x: span.x,
+            width: span.width,
+            alpha_ix,
+            paint: paint.clone(),
+            blend_mode,
+        })),
+        None => tile.fill(span.x, span.width, paint.clone(), blend_mode),
+    }
+}
+
+/// Generate the commands that composite the parts of a clip buffer covered by `spans`.
+fn clip_cmds(spans: &[Span]) -> Vec<Cmd> {
+    let mut cmds = spans
+        .iter()
+        .map(|span| match span.alpha_ix {
+            Some(alpha_ix) => Cmd::ClipStrip(CmdClipStrip {
+                x: span.x,
+                width: span.width,
+                alpha_ix,
+            }),
+            None => Cmd::ClipFill(CmdClipFill {
+                x: span.x,
+                width: span.width,
+            }),
+        })
+        .collect::<Vec<_>>();
+    cmds.push(Cmd::DropBuf);
+
+    cmds
+}
+
+/// A horizontal span of a strip row that lies within a single wide tile.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span {
+    /// The row of the wide tile.
+    pub(crate) row: usize,
+    /// The column of the wide tile.
+    pub(crate) xtile: usize,
+    /// The x coordinate of the span, relative to the wide tile.
+    pub(crate) x: u32,
+    pub(crate) width: u32,
+    /// The index of the alpha values for spans of a strip, or `None` for spans that
+    /// are completely inside of the path.
+    pub(crate) alpha_ix: Option<usize>,
+}
+
+/// Call `f` for each span covered by the strips in `strip_buf`.
+fn for_each_span(
+    strip_buf: &[Strip],
+    fill_rule: Fill,
+    width: usize,
+    height: usize,
+    mut f: impl FnMut(Span),
+) {
+    let width_tiles = width.div_ceil(WIDE_TILE_WIDTH);
+
+    if strip_buf.is_empty() {
+        return;
+    }
+
+    for i in 0..strip_buf.len() - 1 {
+        let strip = &strip_buf[i];
+
+        if strip.x() >= width as i32 {
+            // Don't render strips that are outside the viewport.
+            continue;
+        }
 
-            let next_strip = &self.strip_buf[i + 1];
-            // Currently, strips can also start at a negative x position, since we don't
-            // support viewport culling yet. However, when generating the commands
-            // we only want to emit strips >= 0, so we calculate the adjustment
-            // and then only include the alpha indices for columns where x >= 0.
-            let x0_adjustment = (strip.x()).min(0).unsigned_abs();
-            let x0 = (strip.x() + x0_adjustment as i32) as u32;
-            let y = strip.strip_y();
-            let row_start = y as usize * width_tiles;
-            let mut col = strip.col + x0_adjustment;
-            // Can potentially be 0, if the next strip's x values is also < 0.
-            let strip_width = next_strip.col.saturating_sub(col);
-            let x1 = x0 + strip_width;
-            let xtile0 = x0 as usize / WIDE_TILE_WIDTH;
-            // It's possible that a strip extends into a new wide tile, but we don't actually
-            // have as many wide tiles (e.g. because the pixmap width is only 512, but
-            // strip ends at 513), so take the minimum between the rounded values and `width_tiles`.
-            let xtile1 = (x1 as usize).div_ceil(WIDE_TILE_WIDTH).min(width_tiles);
-            let mut x = x0;
-
-            for xtile in xtile0..xtile1 {
+        if strip.y() >= height as u16 {
+            // Since strips are sorted by location, any subsequent strips will also be
+            // outside the viewport, so we can abort entirely.
+            break;
+        }
+
+        let next_strip = &strip_buf[i + 1];
+        // Strips can also start at a negative x position, since lines to the left of
+        // the viewport are kept to preserve the winding numbers. However, when generating
+        // the commands we only want to emit strips >= 0, so we calculate the adjustment
+        // and then only include the alpha indices for columns where x >= 0.
+        let x0_adjustment = (strip.x()).min(0).unsigned_abs();
+        let x0 = (strip.x() + x0_adjustment as i32) as u32;
+        let y = strip.strip_y();
+        let row = y as usize;
+        let mut col = strip.col + x0_adjustment;
+        // Can potentially be 0, if the next strip's x values is also < 0.
+        let strip_width = next_strip.col.saturating_sub(col);
+        let x1 = x0 + strip_width;
+        let xtile0 = x0 as usize / WIDE_TILE_WIDTH;
+        // It's possible that a strip extends into a new wide tile, but we don't actually
+        // have as many wide tiles (e.g. because the pixmap width is only 512, but
+        // strip ends at 513), so take the minimum between the rounded values and `width_tiles`.
+        let xtile1 = (x1 as usize).div_ceil(WIDE_TILE_WIDTH).min(width_tiles);
+        let mut x = x0;
+
+        for xtile in xtile0..xtile1 {
+            let x_tile_rel = x % WIDE_TILE_WIDTH as u32;
+            let width = x1.min(((xtile + 1) * WIDE_TILE_WIDTH) as u32) - x;
+            f(Span {
+                row,
+                xtile,
+                x: x_tile_rel,
+                width,
+                alpha_ix: Some(col as usize),
+            });
+            x += width;
+            col += width;
+        }
+
+        let active_fill = match fill_rule {
+            Fill::NonZero => next_strip.winding != 0,
+            Fill::EvenOdd => next_strip.winding % 2 != 0,
+        };
+
+        if active_fill
+            && y == next_strip.strip_y()
+            // Only fill if we are actually inside the viewport.
+            && next_strip.x() >= 0
+        {
+            x = x1;
+            let x2 = next_strip.x() as u32;
+            let fxt0 = x1 as usize / WIDE_TILE_WIDTH;
+            let fxt1 = (x2 as usize).div_ceil(WIDE_TILE_WIDTH).min(width_tiles);
+            for xtile in fxt0..fxt1 {
                 let x_tile_rel = x % WIDE_TILE_WIDTH as u32;
-                let width = x1.min(((xtile + 1) * WIDE_TILE_WIDTH) as u32) - x;
-                let cmd = CmdStrip {
+                let width = x2.min(((xtile + 1) * WIDE_TILE_WIDTH) as u32) - x;
+                x += width;
+                f(Span {
+                    row,
+                    xtile,
                     x: x_tile_rel,
                     width,
-                    alpha_ix: col as usize,
-                    paint: paint.clone(),
-                    compose: self.blend_mode.compose,
-                };
-                x += width;
-                col += width;
-                self.wide_tiles[row_start + xtile].push(Cmd::Strip(cmd));
-            }
-
-            let active_fill = match fill_rule {
-                Fill::NonZero => next_strip.winding != 0,
-                Fill::EvenOdd => next_strip.winding % 2 != 0,
-            };
-
-            if active_fill
-                && y == next_strip.strip_y()
-                // Only fill if we are actually inside the viewport.
-                && next_strip.x() >= 0
-            {
-                x = x1;
-                let x2 = next_strip.x() as u32;
-                let fxt0 = x1 as usize / WIDE_TILE_WIDTH;
-                let fxt1 = (x2 as usize).div_ceil(WIDE_TILE_WIDTH);
-                for xtile in fxt0..fxt1 {
-                    let x_tile_rel = x % WIDE_TILE_WIDTH as u32;
-                    let width = x2.min(((xtile + 1) * WIDE_TILE_WIDTH) as u32) - x;
-                    x += width;
-                    self.wide_tiles[row_start + xtile].fill(
-                        x_tile_rel,
-                        width,
-                        paint.clone(),
-                        self.blend_mode.compose,
-                    );
-                }
+                    alpha_ix: None,
+                });
             }
         }
     }
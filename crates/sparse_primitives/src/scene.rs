@@ -0,0 +1,301 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Recording of drawing operations.
+//!
+//! A [`Scene`] records the same operations that can be performed on a render context, so
+//! that they can be replayed into different render contexts later on, for example at a
+//! different size or with a different transform.
+
+use crate::execute::KernelExecutor;
+use crate::paint::Paint;
+use crate::render::{InnerContext, Layer, State};
+use crate::wide_tile::MaskType;
+use peniko::kurbo::{Affine, BezPath, Rect, Stroke};
+use peniko::{BlendMode, Fill};
+
+/// A single recorded operation.
+#[derive(Debug, Clone)]
+pub(crate) enum Command {
+    FillPath(BezPath),
+    StrokePath(BezPath),
+    FillRect(Rect),
+    StrokeRect(Rect),
+    PushLayer(BlendMode, f32),
+    PopLayer,
+    PushMask(MaskType),
+    ApplyMask,
+    PopMask,
+    PushClip(BezPath, Fill),
+    PopClip,
+    SetBlendMode(BlendMode),
+    SetStroke(Stroke),
+    SetPaint(Paint),
+    SetFillRule(Fill),
+    SetTolerance(f64),
+    SetAntiAliasing(bool),
+    /// The transform relative to the scene. Changes of the transform are always
+    /// recorded as the resulting absolute transform, so that an additional outer
+    /// transform can be applied when replaying.
+    SetTransform(Affine),
+}
+
+/// A recording of drawing operations, which can be replayed into a render context.
+///
+/// A new scene starts out with the same state as a new render context, i.e. a black
+/// paint, an identity transform and so on.
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub(crate) commands: Vec<Command>,
+    /// The state at the end of the recorded commands.
+    state: State,
+}
+
+impl Scene {
+    /// Create a new, empty scene.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record filling a rectangle.
+    pub fn fill_rect(&mut self, rect: &Rect) {
+        self.commands.push(Command::FillRect(*rect));
+    }
+
+    /// Record stroking a rectangle.
+    pub fn stroke_rect(&mut self, rect: &Rect) {
+        self.commands.push(Command::StrokeRect(*rect));
+    }
+
+    /// Record filling a path.
+    pub fn fill_path(&mut self, path: &BezPath) {
+        self.commands.push(Command::FillPath(path.clone()));
+    }
+
+    /// Record stroking a path.
+    pub fn stroke_path(&mut self, path: &BezPath) {
+        self.commands.push(Command::StrokePath(path.clone()));
+    }
+
+    /// Record pushing a new layer. See [`RenderContext::push_layer`].
+    ///
+    /// [`RenderContext::push_layer`]: crate::RenderContext::push_layer
+    pub fn push_layer(&mut self, blend_mode: BlendMode, alpha: f32) {
+        self.commands.push(Command::PushLayer(blend_mode, alpha));
+    }
+
+    /// Record popping the last pushed layer.
+    pub fn pop_layer(&mut self) {
+        self.commands.push(Command::PopLayer);
+    }
+
+    /// Record starting to draw a mask. See [`RenderContext::push_mask`].
+    ///
+    /// [`RenderContext::push_mask`]: crate::RenderContext::push_mask
+    pub fn push_mask(&mut self, mask_type: MaskType) {
+        self.commands.push(Command::PushMask(mask_type));
+    }
+
+    /// Record finishing the mask that was pushed last.
+    pub fn apply_mask(&mut self) {
+        self.commands.push(Command::ApplyMask);
+    }
+
+    /// Record popping the last pushed mask.
+    pub fn pop_mask(&mut self) {
+        self.commands.push(Command::PopMask);
+    }
+
+    /// Record pushing a clip path. See [`RenderContext::push_clip`].
+    ///
+    /// [`RenderContext::push_clip`]: crate::RenderContext::push_clip
+    pub fn push_clip(&mut self, path: &BezPath, fill_rule: Fill) {
+        self.commands
+            .push(Command::PushClip(path.clone(), fill_rule));
+    }
+
+    /// Record popping the last pushed clip path.
+    pub fn pop_clip(&mut self) {
+        self.commands.push(Command::PopClip);
+    }
+
+    /// Set the blend mode for filling and stroking operations.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.state.blend_mode = blend_mode;
+        self.commands.push(Command::SetBlendMode(blend_mode));
+    }
+
+    /// Set the stroking properties for stroking operations.
+    pub fn set_stroke(&mut self, stroke: Stroke) {
+        self.state.stroke = stroke.clone();
+        self.commands.push(Command::SetStroke(stroke));
+    }
+
+    /// Set the paint for filling and stroking operations.
+    ///
+    /// See [`RenderContext::set_paint`](crate::RenderContext::set_paint) for the supported
+    /// paints.
+    pub fn set_paint(&mut self, paint: impl Into<Paint>) {
+        let paint = paint.into();
+        self.state.paint = paint.clone();
+        self.commands.push(Command::SetPaint(paint));
+    }
+
+    /// Set the fill rule for filling operations.
+    pub fn set_fill_rule(&mut self, fill_rule: Fill) {
+        self.state.fill_rule = fill_rule;
+        self.commands.push(Command::SetFillRule(fill_rule));
+    }
+
+    /// Set the flattening tolerance. See [`RenderContext::set_tolerance`].
+    ///
+    /// Panics if the tolerance isn't positive and finite.
+    ///
+    /// [`RenderContext::set_tolerance`]: crate::RenderContext::set_tolerance
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        assert!(
+            tolerance > 0.0 && tolerance.is_finite(),
+            "the tolerance must be positive and finite"
+        );
+
+        self.state.tolerance = tolerance;
+        self.commands.push(Command::SetTolerance(tolerance));
+    }
+
+    /// Set whether paths are anti-aliased. See [`RenderContext::set_anti_aliasing`].
+    ///
+    /// [`RenderContext::set_anti_aliasing`]: crate::RenderContext::set_anti_aliasing
+    pub fn set_anti_aliasing(&mut self, anti_alias: bool) {
+        self.state.anti_alias = anti_alias;
+        self.commands.push(Command::SetAntiAliasing(anti_alias));
+    }
+
+    /// Pre-concatenate a transform to the current transformation matrix.
+    pub fn pre_concat_transform(&mut self, transform: Affine) {
+        self.set_transform(self.state.transform * transform);
+    }
+
+    /// Post-concatenate a new transform to the current transformation matrix.
+    pub fn post_concat_transform(&mut self, transform: Affine) {
+        self.set_transform(transform * self.state.transform);
+    }
+
+    /// Set the current transformation matrix.
+    pub fn set_transform(&mut self, transform: Affine) {
+        self.state.transform = transform;
+        self.commands.push(Command::SetTransform(transform));
+    }
+
+    /// Reset the current transformation matrix to the identity matrix.
+    pub fn reset_transform(&mut self) {
+        self.set_transform(Affine::IDENTITY);
+    }
+
+    /// Get the current transformation matrix.
+    pub fn current_transform(&self) -> Affine {
+        self.state.transform
+    }
+
+    /// Append the operations of another scene, with `transform` applied on top of the
+    /// transforms of `other`.
+    ///
+    /// `other` is drawn with its own state, i.e. the paint, stroke and so on of this
+    /// scene don't affect it, and the state of this scene is the same afterward.
+    pub fn append(&mut self, other: &Scene, transform: Affine) {
+        self.push_state(&State {
+            transform,
+            ..State::default()
+        });
+
+        self.commands
+            .extend(other.commands.iter().map(|command| match command {
+                Command::SetTransform(t) => Command::SetTransform(transform * *t),
+                command => command.clone(),
+            }));
+
+        let state = self.state.clone();
+        self.push_state(&state);
+    }
+
+    /// Remove all recorded operations and reset the state.
+    pub fn reset(&mut self) {
+        self.commands.clear();
+        self.state = State::default();
+    }
+
+    /// Return whether no operations have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    fn push_state(&mut self, state: &State) {
+        self.commands.extend([
+            Command::SetPaint(state.paint.clone()),
+            Command::SetStroke(state.stroke.clone()),
+            Command::SetTransform(state.transform),
+            Command::SetFillRule(state.fill_rule),
+            Command::SetBlendMode(state.blend_mode),
+            Command::SetTolerance(state.tolerance),
+            Command::SetAntiAliasing(state.anti_alias),
+        ]);
+    }
+}
+
+impl<KE: KernelExecutor> InnerContext<KE> {
+    pub(crate) fn render_scene(&mut self, scene: &Scene, transform: Affine) {
+        // The scene is drawn with its own state, so the state of the render context
+        // needs to be restored afterward.
+        let saved = self.replace_state(State {
+            transform,
+            ..State::default()
+        });
+        // Likewise, the scene must neither pop nor leave behind any layers, clips or masks
+        // of the render context.
+        let depth = self.layers.len();
+
+        for command in &scene.commands {
+            let owns_layer = self.layers.len() > depth;
+
+            match command {
+                Command::FillPath(path) => self.fill_path(path),
+                Command::StrokePath(path) => self.stroke_path(path),
+                Command::FillRect(rect) => self.fill_rect(rect),
+                Command::StrokeRect(rect) => self.stroke_rect(rect),
+                Command::PushLayer(blend_mode, alpha) => self.push_layer(*blend_mode, *alpha),
+                Command::PopLayer if owns_layer => self.pop_layer(),
+                Command::PushMask(mask_type) => self.push_mask(*mask_type),
+                Command::ApplyMask if owns_layer => self.apply_mask(),
+                Command::PopMask if owns_layer => self.pop_mask(),
+                Command::PushClip(path, fill_rule) => self.push_clip(path, *fill_rule),
+                Command::PopClip if owns_layer => self.pop_clip(),
+                Command::PopLayer | Command::ApplyMask | Command::PopMask | Command::PopClip => {}
+                Command::SetBlendMode(blend_mode) => self.set_blend_mode(*blend_mode),
+                Command::SetStroke(stroke) => self.set_stroke(stroke.clone()),
+                Command::SetPaint(paint) => self.set_paint(paint.clone()),
+                Command::SetFillRule(fill_rule) => self.set_fill_rule(*fill_rule),
+                Command::SetTolerance(tolerance) => self.set_tolerance(*tolerance),
+                Command::SetAntiAliasing(anti_alias) => self.set_anti_aliasing(*anti_alias),
+                Command::SetTransform(t) => self.set_transform(transform * *t),
+            }
+        }
+
+        self.pop_layers_to(depth);
+        self.replace_state(saved);
+    }
+
+    /// Pop the layers, clips and masks pushed after the first `depth` ones.
+    fn pop_layers_to(&mut self, depth: usize) {
+        while self.layers.len() > depth {
+            match self.layers.last() {
+                Some(Layer::Blend(..)) => self.pop_layer(),
+                Some(Layer::Clip(_)) => self.pop_clip(),
+                Some(Layer::Mask { .. }) => self.pop_mask(),
+                None => unreachable!(),
+            }
+        }
+    }
+
+    fn replace_state(&mut self, state: State) -> State {
+        std::mem::replace(&mut self.state, state)
+    }
+}
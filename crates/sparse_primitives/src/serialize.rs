@@ -0,0 +1,756 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Serialization of recorded scenes.
+//!
+//! Scenes are stored in a line-based text format, so that they can easily be attached
+//! to bug reports, inspected and edited by hand. The first line is a header with the
+//! version of the format, e.g. `sparse-scene 1`. Each following line contains a single
+//! command, starting with the name of the corresponding method of [`Scene`], followed
+//! by its arguments. Arguments that aren't self-explanatory are preceded by their name:
+//!
+//! ```text
+//! sparse-scene 1
+//! # A red triangle with a dashed outline.
+//! set_paint solid 1 0 0 1
+//! fill_path M 10 10 L 90 10 L 50 90 Z
+//! set_stroke width 2 join miter miter_limit 4 start_cap round end_cap round dash_offset 0 dashes 4 2
+//! set_transform 1 0 0 1 0 0.5
+//! stroke_path M 10 10 L 90 10 L 50 90 Z
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored. Numbers are written with the
+//! shortest representation that parses back to the same value, so a scene that is read
+//! back renders exactly like the original one. The only exception are the colors of
+//! gradient stops, which are always stored in sRGB, since that's what they are converted
+//! to for rendering anyway.
+//!
+//! Paths are written as a sequence of `M`, `L`, `Q`, `C` and `Z` elements, followed by
+//! their points. Image paints are stored together with their premultiplied pixels as
+//! hexadecimal RGBA values.
+
+use crate::color::{AlphaColor, DynamicColor, Srgb};
+use crate::kurbo::{Affine, BezPath, Cap, Join, PathEl, Point, Rect, Stroke};
+use crate::paint::{Image, LinearGradient, Paint, RadialGradient, SweepGradient};
+use crate::scene::{Command, Scene};
+use crate::wide_tile::MaskType;
+use crate::{BlendMode, ColorStop, ColorStops, Compose, Extend, Fill, ImageQuality, Mix, Pixmap};
+use std::fmt::{self, Write as _};
+use std::io::{self, BufRead, Write};
+use std::str::SplitWhitespace;
+
+/// The current version of the format.
+pub const VERSION: u32 = 1;
+
+const HEADER: &str = "sparse-scene";
+
+/// An error that occurred while reading a scene.
+#[derive(Debug)]
+pub enum ReadError {
+    /// An error of the underlying reader.
+    Io(io::Error),
+    /// The data doesn't start with a valid header.
+    InvalidHeader,
+    /// The scene was written with a newer version of the format.
+    UnsupportedVersion(u32),
+    /// A command couldn't be parsed.
+    InvalidCommand {
+        /// The line of the command, starting at 1.
+        line: usize,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "failed to read scene: {err}"),
+            ReadError::InvalidHeader => write!(f, "missing or invalid scene header"),
+            ReadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported scene version {version}")
+            }
+            ReadError::InvalidCommand { line, message } => {
+                write!(f, "invalid command in line {line}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl Scene {
+    /// Write the scene in the format described in the [module documentation](crate::serialize).
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{HEADER} {VERSION}")?;
+
+        let mut line = String::new();
+
+        for command in &self.commands {
+            line.clear();
+            // Writing into a string can't fail.
+            write_command(&mut line, command).unwrap();
+            writeln!(writer, "{line}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a scene in the format described in the [module documentation](crate::serialize).
+    pub fn read(reader: impl BufRead) -> Result<Scene, ReadError> {
+        let mut lines = reader.lines();
+
+        let header = lines.next().transpose()?.ok_or(ReadError::InvalidHeader)?;
+        let version = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [HEADER, version] => version.parse().map_err(|_| ReadError::InvalidHeader)?,
+            _ => return Err(ReadError::InvalidHeader),
+        };
+
+        if version > VERSION {
+            return Err(ReadError::UnsupportedVersion(version));
+        }
+
+        let mut scene = Scene::new();
+
+        for (ix, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            read_command(&mut scene, &mut Tokens(line.split_whitespace())).map_err(|message| {
+                ReadError::InvalidCommand {
+                    // The header is the first line.
+                    line: ix + 2,
+                    message,
+                }
+            })?;
+        }
+
+        Ok(scene)
+    }
+}
+
+fn write_command(out: &mut String, command: &Command) -> fmt::Result {
+    match command {
+        Command::FillPath(path) => {
+            write!(out, "fill_path")?;
+            write_path(out, path)
+        }
+        Command::StrokePath(path) => {
+            write!(out, "stroke_path")?;
+            write_path(out, path)
+        }
+        Command::FillRect(rect) => write_rect(out, "fill_rect", rect),
+        Command::StrokeRect(rect) => write_rect(out, "stroke_rect", rect),
+        Command::PushLayer(blend_mode, alpha) => {
+            write!(out, "push_layer")?;
+            write_blend_mode(out, blend_mode)?;
+            write!(out, " alpha {alpha}")
+        }
+        Command::PopLayer => write!(out, "pop_layer"),
+        Command::PushMask(mask_type) => {
+            write!(out, "push_mask {}", name_of(MASK_TYPES, *mask_type))
+        }
+        Command::ApplyMask => write!(out, "apply_mask"),
+        Command::PopMask => write!(out, "pop_mask"),
+        Command::PushClip(path, fill_rule) => {
+            write!(out, "push_clip {}", name_of(FILL_RULES, *fill_rule))?;
+            write_path(out, path)
+        }
+        Command::PopClip => write!(out, "pop_clip"),
+        Command::SetBlendMode(blend_mode) => {
+            write!(out, "set_blend_mode")?;
+            write_blend_mode(out, blend_mode)
+        }
+        Command::SetStroke(stroke) => {
+            write!(
+                out,
+                "set_stroke width {} join {} miter_limit {} start_cap {} end_cap {} \
+                 dash_offset {} dashes",
+                stroke.width,
+                name_of(JOINS, stroke.join),
+                stroke.miter_limit,
+                name_of(CAPS, stroke.start_cap),
+                name_of(CAPS, stroke.end_cap),
+                stroke.dash_offset
+            )?;
+
+            for dash in &stroke.dash_pattern {
+                write!(out, " {dash}")?;
+            }
+
+            Ok(())
+        }
+        Command::SetPaint(paint) => {
+            write!(out, "set_paint ")?;
+            write_paint(out, paint)
+        }
+        Command::SetFillRule(fill_rule) => {
+            write!(out, "set_fill_rule {}", name_of(FILL_RULES, *fill_rule))
+        }
+        Command::SetTolerance(tolerance) => write!(out, "set_tolerance {tolerance}"),
+        Command::SetAntiAliasing(anti_alias) => {
+            write!(out, "set_anti_aliasing {}", name_of(BOOLS, *anti_alias))
+        }
+        Command::SetTransform(transform) => {
+            write!(out, "set_transform")?;
+            write_affine(out, transform)
+        }
+    }
+}
+
+fn write_path(out: &mut String, path: &BezPath) -> fmt::Result {
+    for el in path.elements() {
+        let (name, points) = match el {
+            PathEl::MoveTo(p0) => ("M", &[*p0][..]),
+            PathEl::LineTo(p0) => ("L", &[*p0][..]),
+            PathEl::QuadTo(p0, p1) => ("Q", &[*p0, *p1][..]),
+            PathEl::CurveTo(p0, p1, p2) => ("C", &[*p0, *p1, *p2][..]),
+            PathEl::ClosePath => ("Z", &[][..]),
+        };
+
+        write!(out, " {name}")?;
+
+        for p in points {
+            write!(out, " {} {}", p.x, p.y)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_rect(out: &mut String, name: &str, rect: &Rect) -> fmt::Result {
+    write!(
+        out,
+        "{name} {} {} {} {}",
+        rect.x0, rect.y0, rect.x1, rect.y1
+    )
+}
+
+fn write_affine(out: &mut String, transform: &Affine) -> fmt::Result {
+    for c in transform.as_coeffs() {
+        write!(out, " {c}")?;
+    }
+
+    Ok(())
+}
+
+fn write_blend_mode(out: &mut String, blend_mode: &BlendMode) -> fmt::Result {
+    write!(
+        out,
+        " mix {} compose {}",
+        name_of(MIXES, blend_mode.mix),
+        name_of(COMPOSES, blend_mode.compose)
+    )
+}
+
+fn write_point(out: &mut String, name: &str, point: Point) -> fmt::Result {
+    write!(out, " {name} {} {}", point.x, point.y)
+}
+
+fn write_gradient_end(
+    out: &mut String,
+    extend: Extend,
+    transform: &Affine,
+    stops: &ColorStops,
+) -> fmt::Result {
+    write!(out, " extend {} transform", name_of(EXTENDS, extend))?;
+    write_affine(out, transform)?;
+    write!(out, " stops")?;
+
+    for stop in stops.iter() {
+        let [r, g, b, a] = stop.color.to_alpha_color::<Srgb>().components;
+        write!(out, " {} {r} {g} {b} {a}", stop.offset)?;
+    }
+
+    Ok(())
+}
+
+fn write_paint(out: &mut String, paint: &Paint) -> fmt::Result {
+    match paint {
+        Paint::Solid(color) => {
+            let [r, g, b, a] = color.components;
+            write!(out, "solid {r} {g} {b} {a}")
+        }
+        Paint::LinearGradient(g) => {
+            write!(out, "linear")?;
+            write_point(out, "start", g.start)?;
+            write_point(out, "end", g.end)?;
+            write_gradient_end(out, g.extend, &g.transform, &g.stops)
+        }
+        Paint::RadialGradient(g) => {
+            write!(out, "radial")?;
+            write_point(out, "start_center", g.start_center)?;
+            write!(out, " start_radius {}", g.start_radius)?;
+            write_point(out, "end_center", g.end_center)?;
+            write!(out, " end_radius {}", g.end_radius)?;
+            write_gradient_end(out, g.extend, &g.transform, &g.stops)
+        }
+        Paint::SweepGradient(g) => {
+            write!(out, "sweep")?;
+            write_point(out, "center", g.center)?;
+            write!(
+                out,
+                " start_angle {} end_angle {}",
+                g.start_angle, g.end_angle
+            )?;
+            write_gradient_end(out, g.extend, &g.transform, &g.stops)
+        }
+        Paint::Image(i) => {
+            write!(
+                out,
+                "image x_extend {} y_extend {} quality {} alpha {} transform",
+                name_of(EXTENDS, i.x_extend),
+                name_of(EXTENDS, i.y_extend),
+                name_of(QUALITIES, i.quality),
+                i.alpha
+            )?;
+            write_affine(out, &i.transform)?;
+
+            let pixmap = i.pixmap();
+            write!(out, " size {} {} data ", pixmap.width, pixmap.height)?;
+
+            for byte in &pixmap.buf {
+                write!(out, "{byte:02x}")?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+fn read_command(scene: &mut Scene, tokens: &mut Tokens<'_>) -> Result<(), String> {
+    match tokens.next()? {
+        "fill_path" => scene.fill_path(&tokens.path()?),
+        "stroke_path" => scene.stroke_path(&tokens.path()?),
+        "fill_rect" => scene.fill_rect(&tokens.rect()?),
+        "stroke_rect" => scene.stroke_rect(&tokens.rect()?),
+        "push_layer" => {
+            let blend_mode = tokens.blend_mode()?;
+            tokens.keyword("alpha")?;
+            scene.push_layer(blend_mode, tokens.f32()?);
+        }
+        "pop_layer" => scene.pop_layer(),
+        "push_mask" => scene.push_mask(tokens.name(MASK_TYPES)?),
+        "apply_mask" => scene.apply_mask(),
+        "pop_mask" => scene.pop_mask(),
+        "push_clip" => {
+            let fill_rule = tokens.name(FILL_RULES)?;
+            scene.push_clip(&tokens.path()?, fill_rule);
+        }
+        "pop_clip" => scene.pop_clip(),
+        "set_blend_mode" => scene.set_blend_mode(tokens.blend_mode()?),
+        "set_stroke" => scene.set_stroke(tokens.stroke()?),
+        "set_paint" => scene.set_paint(tokens.paint()?),
+        "set_fill_rule" => scene.set_fill_rule(tokens.name(FILL_RULES)?),
+        "set_tolerance" => {
+            let tolerance = tokens.f64()?;
+
+            // Setting an invalid tolerance would panic.
+            if !(tolerance > 0.0 && tolerance.is_finite()) {
+                return Err(format!("invalid tolerance `{tolerance}`"));
+            }
+
+            scene.set_tolerance(tolerance);
+        }
+        "set_anti_aliasing" => scene.set_anti_aliasing(tokens.name(BOOLS)?),
+        "set_transform" => scene.set_transform(tokens.affine()?),
+        name => return Err(format!("unknown command `{name}`")),
+    }
+
+    tokens.finish()
+}
+
+/// The remaining arguments of a command.
+struct Tokens<'a>(SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        self.0
+            .next()
+            .ok_or_else(|| "unexpected end of line".to_string())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        match self.0.next() {
+            Some(token) => Err(format!("unexpected argument `{token}`")),
+            None => Ok(()),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == keyword => Ok(()),
+            token => Err(format!("expected `{keyword}`, found `{token}`")),
+        }
+    }
+
+    /// Read a finite number. Infinities and NaN aren't accepted anywhere, since they
+    /// can't be rendered sensibly.
+    fn f64(&mut self) -> Result<f64, String> {
+        let token = self.next()?;
+        token
+            .parse()
+            .ok()
+            .filter(|v: &f64| v.is_finite())
+            .ok_or_else(|| format!("invalid number `{token}`"))
+    }
+
+    /// Read a finite number, like [`Tokens::f64`].
+    fn f32(&mut self) -> Result<f32, String> {
+        let token = self.next()?;
+        token
+            .parse()
+            .ok()
+            .filter(|v: &f32| v.is_finite())
+            .ok_or_else(|| format!("invalid number `{token}`"))
+    }
+
+    fn usize(&mut self) -> Result<usize, String> {
+        let token = self.next()?;
+        token.parse().map_err(|_| format!("invalid size `{token}`"))
+    }
+
+    fn name<T: Copy>(&mut self, table: &[(T, &str)]) -> Result<T, String> {
+        let token = self.next()?;
+        table
+            .iter()
+            .find(|(_, name)| *name == token)
+            .map(|(value, _)| *value)
+            .ok_or_else(|| format!("unknown value `{token}`"))
+    }
+
+    fn point(&mut self) -> Result<Point, String> {
+        Ok(Point::new(self.f64()?, self.f64()?))
+    }
+
+    fn named_point(&mut self, keyword: &str) -> Result<Point, String> {
+        self.keyword(keyword)?;
+        self.point()
+    }
+
+    fn named_f32(&mut self, keyword: &str) -> Result<f32, String> {
+        self.keyword(keyword)?;
+        self.f32()
+    }
+
+    fn named_f64(&mut self, keyword: &str) -> Result<f64, String> {
+        self.keyword(keyword)?;
+        self.f64()
+    }
+
+    fn named<T: Copy>(&mut self, keyword: &str, table: &[(T, &str)]) -> Result<T, String> {
+        self.keyword(keyword)?;
+        self.name(table)
+    }
+
+    fn rect(&mut self) -> Result<Rect, String> {
+        Ok(Rect::new(
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+        ))
+    }
+
+    fn affine(&mut self) -> Result<Affine, String> {
+        let mut coeffs = [0.0; 6];
+
+        for c in &mut coeffs {
+            *c = self.f64()?;
+        }
+
+        Ok(Affine::new(coeffs))
+    }
+
+    fn path(&mut self) -> Result<BezPath, String> {
+        let mut path = BezPath::new();
+
+        while let Some(name) = self.0.next() {
+            if path.elements().is_empty() && name != "M" {
+                return Err("path doesn't start with `M`".to_string());
+            }
+
+            match name {
+                "M" => path.move_to(self.point()?),
+                "L" => path.line_to(self.point()?),
+                "Q" => path.quad_to(self.point()?, self.point()?),
+                "C" => path.curve_to(self.point()?, self.point()?, self.point()?),
+                "Z" => path.close_path(),
+                _ => return Err(format!("unknown path element `{name}`")),
+            }
+        }
+
+        Ok(path)
+    }
+
+    fn blend_mode(&mut self) -> Result<BlendMode, String> {
+        let mix = self.named("mix", MIXES)?;
+        let compose = self.named("compose", COMPOSES)?;
+
+        Ok(BlendMode::new(mix, compose))
+    }
+
+    fn stroke(&mut self) -> Result<Stroke, String> {
+        let width = self.named_f64("width")?;
+        let join = self.named("join", JOINS)?;
+        let miter_limit = self.named_f64("miter_limit")?;
+        let start_cap = self.named("start_cap", CAPS)?;
+        let end_cap = self.named("end_cap", CAPS)?;
+        let dash_offset = self.named_f64("dash_offset")?;
+        self.keyword("dashes")?;
+
+        let mut dash_pattern = vec![];
+
+        while self.0.clone().next().is_some() {
+            dash_pattern.push(self.f64()?);
+        }
+
+        Ok(Stroke {
+            width,
+            join,
+            miter_limit,
+            start_cap,
+            end_cap,
+            dash_pattern: dash_pattern.into_iter().collect(),
+            dash_offset,
+        })
+    }
+
+    /// Read the components of an sRGB color.
+    ///
+    /// The components are clamped to [0, 1], since the compositing kernels rely on the
+    /// color channels never exceeding the alpha channel after premultiplication.
+    fn color(&mut self) -> Result<AlphaColor<Srgb>, String> {
+        let mut components = [0.0; 4];
+
+        for c in &mut components {
+            *c = self.f32()?.clamp(0.0, 1.0);
+        }
+
+        Ok(AlphaColor::new(components))
+    }
+
+    /// Read the extend mode, transform and color stops of a gradient.
+    fn gradient_end(&mut self) -> Result<(Extend, Affine, ColorStops), String> {
+        let extend = self.named("extend", EXTENDS)?;
+        self.keyword("transform")?;
+        let transform = self.affine()?;
+        self.keyword("stops")?;
+
+        let mut stops = ColorStops::new();
+
+        while self.0.clone().next().is_some() {
+            let offset = self.f32()?;
+            let color = self.color()?;
+
+            stops.push(ColorStop {
+                offset,
+                color: DynamicColor::from_alpha_color(color),
+            });
+        }
+
+        Ok((extend, transform, stops))
+    }
+
+    fn paint(&mut self) -> Result<Paint, String> {
+        Ok(match self.next()? {
+            "solid" => self.color()?.into(),
+            "linear" => {
+                let start = self.named_point("start")?;
+                let end = self.named_point("end")?;
+                let (extend, transform, stops) = self.gradient_end()?;
+
+                LinearGradient {
+                    start,
+                    end,
+                    stops,
+                    extend,
+                    transform,
+                }
+                .into()
+            }
+            "radial" => {
+                let start_center = self.named_point("start_center")?;
+                let start_radius = self.named_f32("start_radius")?;
+                let end_center = self.named_point("end_center")?;
+                let end_radius = self.named_f32("end_radius")?;
+                let (extend, transform, stops) = self.gradient_end()?;
+
+                RadialGradient {
+                    start_center,
+                    start_radius,
+                    end_center,
+                    end_radius,
+                    stops,
+                    extend,
+                    transform,
+                }
+                .into()
+            }
+            "sweep" => {
+                let center = self.named_point("center")?;
+                let start_angle = self.named_f32("start_angle")?;
+                let end_angle = self.named_f32("end_angle")?;
+                let (extend, transform, stops) = self.gradient_end()?;
+
+                SweepGradient {
+                    center,
+                    start_angle,
+                    end_angle,
+                    stops,
+                    extend,
+                    transform,
+                }
+                .into()
+            }
+            "image" => {
+                let x_extend = self.named("x_extend", EXTENDS)?;
+                let y_extend = self.named("y_extend", EXTENDS)?;
+                let quality = self.named("quality", QUALITIES)?;
+                let alpha = self.named_f32("alpha")?;
+                self.keyword("transform")?;
+                let transform = self.affine()?;
+                self.keyword("size")?;
+                let (width, height) = (self.usize()?, self.usize()?);
+                self.keyword("data")?;
+                // An empty image doesn't have any data.
+                let data = self.0.next().unwrap_or_default();
+
+                // Check the size before allocating the pixmap, since it can be arbitrarily
+                // large. Each byte is encoded as two hex digits.
+                let hex_len = width
+                    .checked_mul(height)
+                    .and_then(|pixels| pixels.checked_mul(4 * 2));
+
+                if hex_len != Some(data.len()) {
+                    return Err("image data doesn't match the size of the image".to_string());
+                }
+
+                let mut pixmap = Pixmap::new(width, height);
+
+                for (byte, hex) in pixmap.buf.iter_mut().zip(data.as_bytes().chunks_exact(2)) {
+                    *byte = std::str::from_utf8(hex)
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        .ok_or_else(|| "invalid image data".to_string())?;
+                }
+
+                // The compositing kernels rely on the pixels being premultiplied, i.e. on
+                // no color channel exceeding the alpha channel.
+                if pixmap
+                    .buf
+                    .chunks_exact(4)
+                    .any(|pixel| pixel[..3].iter().any(|c| *c > pixel[3]))
+                {
+                    return Err("image data isn't premultiplied".to_string());
+                }
+
+                let mut image = Image::new(pixmap)
+                    .with_quality(quality)
+                    .with_transform(transform);
+                image.x_extend = x_extend;
+                image.y_extend = y_extend;
+                image.alpha = alpha;
+
+                image.into()
+            }
+            name => return Err(format!("unknown paint `{name}`")),
+        })
+    }
+}
+
+fn name_of<T: Copy + PartialEq>(table: &[(T, &'static str)], value: T) -> &'static str {
+    table
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, name)| *name)
+        // All tables are exhaustive.
+        .unwrap()
+}
+
+const BOOLS: &[(bool, &str)] = &[(true, "true"), (false, "false")];
+
+const FILL_RULES: &[(Fill, &str)] = &[(Fill::NonZero, "nonzero"), (Fill::EvenOdd, "evenodd")];
+
+const MASK_TYPES: &[(MaskType, &str)] = &[
+    (MaskType::Alpha, "alpha"),
+    (MaskType::Luminance, "luminance"),
+];
+
+const JOINS: &[(Join, &str)] = &[
+    (Join::Bevel, "bevel"),
+    (Join::Miter, "miter"),
+    (Join::Round, "round"),
+];
+
+const CAPS: &[(Cap, &str)] = &[
+    (Cap::Butt, "butt"),
+    (Cap::Square, "square"),
+    (Cap::Round, "round"),
+];
+
+const EXTENDS: &[(Extend, &str)] = &[
+    (Extend::Pad, "pad"),
+    (Extend::Repeat, "repeat"),
+    (Extend::Reflect, "reflect"),
+];
+
+const QUALITIES: &[(ImageQuality, &str)] = &[
+    (ImageQuality::Low, "low"),
+    (ImageQuality::Medium, "medium"),
+    (ImageQuality::High, "high"),
+];
+
+const MIXES: &[(Mix, &str)] = &[
+    (Mix::Normal, "normal"),
+    (Mix::Multiply, "multiply"),
+    (Mix::Screen, "screen"),
+    (Mix::Overlay, "overlay"),
+    (Mix::Darken, "darken"),
+    (Mix::Lighten, "lighten"),
+    (Mix::ColorDodge, "color_dodge"),
+    (Mix::ColorBurn, "color_burn"),
+    (Mix::HardLight, "hard_light"),
+    (Mix::SoftLight, "soft_light"),
+    (Mix::Difference, "difference"),
+    (Mix::Exclusion, "exclusion"),
+    (Mix::Hue, "hue"),
+    (Mix::Saturation, "saturation"),
+    (Mix::Color, "color"),
+    (Mix::Luminosity, "luminosity"),
+    (Mix::Clip, "clip"),
+];
+
+const COMPOSES: &[(Compose, &str)] = &[
+    (Compose::Clear, "clear"),
+    (Compose::Copy, "copy"),
+    (Compose::Dest, "dest"),
+    (Compose::SrcOver, "src_over"),
+    (Compose::DestOver, "dest_over"),
+    (Compose::SrcIn, "src_in"),
+    (Compose::DestIn, "dest_in"),
+    (Compose::SrcOut, "src_out"),
+    (Compose::DestOut, "dest_out"),
+    (Compose::SrcAtop, "src_atop"),
+    (Compose::DestAtop, "dest_atop"),
+    (Compose::Xor, "xor"),
+    (Compose::Plus, "plus"),
+    (Compose::PlusLighter, "plus_lighter"),
+];
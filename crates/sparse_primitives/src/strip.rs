@@ -30,6 +30,7 @@ pub trait Render {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     );
 }
 
@@ -39,8 +40,9 @@ impl Render for Scalar {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
-        scalar::render_strips(tiles, strip_buf, alpha_buf, fill_rule);
+        scalar::render_strips(tiles, strip_buf, alpha_buf, fill_rule, anti_alias);
     }
 }
 
@@ -51,9 +53,10 @@ impl Render for crate::execute::Avx2 {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
         unsafe {
-            avx2::render_strips(tiles, strip_buf, alpha_buf, fill_rule);
+            avx2::render_strips(tiles, strip_buf, alpha_buf, fill_rule, anti_alias);
         }
     }
 }
@@ -65,23 +68,29 @@ impl Render for crate::execute::Neon {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
         unsafe {
-            neon::render_strips(tiles, strip_buf, alpha_buf, fill_rule);
+            neon::render_strips(tiles, strip_buf, alpha_buf, fill_rule, anti_alias);
         }
     }
 }
 
+/// Render the sorted tiles into strips and their alpha values.
+///
+/// If `anti_alias` is `false`, the coverage of each pixel is sampled at its center instead of
+/// being computed from the covered area, so that all alpha values are either 0 or 255.
 #[inline(never)]
 pub fn render_strips<KE: KernelExecutor>(
     tiles: &Tiles,
     strip_buf: &mut Vec<Strip>,
     alpha_buf: &mut Vec<u32>,
     fill_rule: Fill,
+    anti_alias: bool,
 ) {
     strip_buf.clear();
 
-    KE::render_strips(tiles, strip_buf, alpha_buf, fill_rule);
+    KE::render_strips(tiles, strip_buf, alpha_buf, fill_rule, anti_alias);
 }
 
 impl Strip {
@@ -109,6 +118,7 @@ pub(crate) mod scalar {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
         let mut strip_start = true;
         let mut cols = alpha_buf.len() as u32;
@@ -155,6 +165,20 @@ pub(crate) mod scalar {
                             // Relative y offset of the start
                             // point from the current row.
                             let rel_y = p0.y - y as f32;
+
+                            if !anti_alias {
+                                areas[x as usize][y] += sample_center(
+                                    rel_x,
+                                    rel_y,
+                                    p1.y - y as f32,
+                                    inv_slope,
+                                    p0.x == 0.0,
+                                    p1.x == 0.0,
+                                );
+
+                                continue;
+                            }
+
                             // y values will be 1 if the point is below the current row,
                             // 0 if the point is above the current row, and between 0-1
                             // if it is on the same row.
@@ -270,6 +294,50 @@ pub(crate) mod scalar {
             prev_tile = tile;
         }
     }
+
+    /// The contribution of a line to the winding number at the center of a pixel.
+    ///
+    /// This mirrors the area computation, but replaces the vertical coverage of the row
+    /// with a step at the center of the row and the horizontal coverage of the pixel with
+    /// a step at the center of the pixel, so that the sum of the contributions is exactly
+    /// the winding number at the center.
+    #[inline(always)]
+    fn sample_center(
+        rel_x: f32,
+        rel_y: f32,
+        rel_y1: f32,
+        inv_slope: f32,
+        p0_left: bool,
+        p1_left: bool,
+    ) -> f32 {
+        let y0 = step(rel_y);
+        let y1 = step(rel_y1);
+        let dy = y0 - y1;
+        // x intersection point with the center of the row. This can be NaN for horizontal
+        // lines, in which case the comparison below fails and `dy` is 0 anyway.
+        let xx = rel_x + (0.5 - rel_y) * inv_slope;
+        let a = if xx < 0.5 { 1.0 } else { 0.0 };
+
+        let mut winding = a * dy;
+
+        if p0_left {
+            winding += 1.0 - y0;
+        } else if p1_left {
+            winding -= 1.0 - y1;
+        }
+
+        winding
+    }
+
+    /// 1 if the point is below the center of the current row, 0 otherwise.
+    #[inline(always)]
+    fn step(rel_y: f32) -> f32 {
+        if rel_y > 0.5 {
+            1.0
+        } else {
+            0.0
+        }
+    }
 }
 
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
@@ -285,6 +353,7 @@ pub(crate) mod neon {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
         let mut strip_start = true;
         let mut cols = alpha_buf.len() as u32;
@@ -332,6 +401,32 @@ pub(crate) mod neon {
 
                         let y = vld1q_f32([0.0, 1.0, 2.0, 3.0].as_ptr());
                         let rel_y = vsubq_f32(p0_y, y);
+
+                        if !anti_alias {
+                            let idx = (4 * x) as usize;
+                            let half = vdupq_n_f32(0.5);
+                            let y0 = step(rel_y);
+                            let y1 = step(vsubq_f32(p1_y, y));
+                            let dy = vsubq_f32(y0, y1);
+                            let xx = vfmaq_f32(rel_x, inv_slope, vsubq_f32(half, rel_y));
+                            let a = vreinterpretq_f32_u32(vandq_u32(
+                                vcltq_f32(xx, half),
+                                vreinterpretq_u32_f32(ones),
+                            ));
+
+                            let mut area = vld1q_f32(areas.as_ptr().add(idx));
+                            area = vfmaq_f32(area, dy, a);
+
+                            if p0.x == 0.0 {
+                                area = vaddq_f32(area, vsubq_f32(ones, y0));
+                            } else if p1.x == 0.0 {
+                                area = vsubq_f32(area, vsubq_f32(ones, y1));
+                            }
+
+                            vst1q_f32(areas.as_mut_ptr().add(idx), area);
+                            continue;
+                        }
+
                         let y0 = clamp(rel_y, 0.0, 1.0);
                         let y1 = clamp(vsubq_f32(p1_y, y), 0.0, 1.0);
                         let dy = vsubq_f32(y0, y1);
@@ -459,6 +554,15 @@ pub(crate) mod neon {
     unsafe fn clamp(val: float32x4_t, min: f32, max: f32) -> float32x4_t {
         vmaxq_f32(vminq_f32(val, vdupq_n_f32(max)), vdupq_n_f32(min))
     }
+
+    /// 1 in the lanes where the value is greater than 0.5, 0 otherwise.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `neon`.
+    unsafe fn step(val: float32x4_t) -> float32x4_t {
+        let mask = vcgtq_f32(val, vdupq_n_f32(0.5));
+
+        vreinterpretq_f32_u32(vandq_u32(mask, vreinterpretq_u32_f32(vdupq_n_f32(1.0))))
+    }
 }
 
 #[cfg(all(target_arch = "x86_64", feature = "simd"))]
@@ -484,6 +588,15 @@ pub(crate) mod avx2 {
         res
     }
 
+    /// 1 in the lanes where the value is greater than 0.5, 0 otherwise.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    unsafe fn step(val: __m256) -> __m256 {
+        let mask = _mm256_cmp_ps::<_CMP_GT_OQ>(val, _mm256_set1_ps(0.5));
+        _mm256_and_ps(mask, _mm256_set1_ps(1.0))
+    }
+
     /// SAFETY: The CPU needs to support the target feature `avx2`.
     #[target_feature(enable = "avx2")]
     unsafe fn abs_128(val: __m128) -> __m128 {
@@ -498,6 +611,7 @@ pub(crate) mod avx2 {
         strip_buf: &mut Vec<Strip>,
         alpha_buf: &mut Vec<u32>,
         fill_rule: Fill,
+        anti_alias: bool,
     ) {
         let mut strip_start = true;
         let mut cols = alpha_buf.len() as u32;
@@ -548,6 +662,29 @@ pub(crate) mod avx2 {
 
                         let y = _mm256_set_ps(3.0, 2.0, 1.0, 0.0, 3.0, 2.0, 1.0, 0.0);
                         let rel_y = _mm256_sub_ps(p0_y, y);
+
+                        if !anti_alias {
+                            let idx = (4 * x__) as usize;
+                            let half = _mm256_set1_ps(0.5);
+                            let y0 = step(rel_y);
+                            let y1 = step(_mm256_sub_ps(p1_y, y));
+                            let dy = _mm256_sub_ps(y0, y1);
+                            let xx = _mm256_fmadd_ps(_mm256_sub_ps(half, rel_y), inv_slope, rel_x);
+                            let a = _mm256_and_ps(_mm256_cmp_ps::<_CMP_LT_OQ>(xx, half), ones);
+
+                            let mut area = _mm256_loadu_ps(areas.as_ptr().add(idx));
+                            area = _mm256_fmadd_ps(a, dy, area);
+
+                            if p0.x == 0.0 {
+                                area = _mm256_add_ps(area, _mm256_sub_ps(ones, y0));
+                            } else if p1.x == 0.0 {
+                                area = _mm256_sub_ps(area, _mm256_sub_ps(ones, y1));
+                            }
+
+                            _mm256_storeu_ps(areas.as_mut_ptr().add(idx), area);
+                            continue;
+                        }
+
                         let y0 = clamp(rel_y, 0.0, 1.0);
                         let y1 = clamp(_mm256_sub_ps(p1_y, y), 0.0, 1.0);
                         let dy = _mm256_sub_ps(y0, y1);
@@ -0,0 +1,130 @@
+// Copyright 2024 the Piet Authors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Borrowed render targets.
+
+use crate::fine::COLOR_COMPONENTS;
+use crate::pixmap::Pixmap;
+
+/// The order of the color channels of a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+    /// 8 bits per channel, in the order red, green, blue, alpha.
+    #[default]
+    Rgba8,
+    /// 8 bits per channel, in the order blue, green, red, alpha.
+    Bgra8,
+}
+
+/// How the color channels of a pixel relate to its alpha channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    /// The color channels are multiplied by the alpha channel.
+    #[default]
+    Premultiplied,
+    /// The color channels are independent of the alpha channel.
+    Unpremultiplied,
+}
+
+/// A caller-owned buffer that can be rendered into, for example the framebuffer of a
+/// window.
+///
+/// Rows can be padded, i.e. the distance in bytes between the starts of two rows (the
+/// stride) can be larger than the width of a row. The padding is left untouched when
+/// rendering.
+#[derive(Debug)]
+pub struct RenderTarget<'a> {
+    pub(crate) data: &'a mut [u8],
+    pub(crate) layout: TargetLayout,
+}
+
+/// The size and pixel layout of a render target.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TargetLayout {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) stride: usize,
+    pub(crate) format: PixelFormat,
+    pub(crate) alpha_mode: AlphaMode,
+}
+
+impl<'a> RenderTarget<'a> {
+    /// Create a new render target with premultiplied RGBA8 pixels.
+    ///
+    /// `stride` is the number of bytes between the starts of two rows. The last row
+    /// doesn't need to be padded.
+    ///
+    /// Panics if the stride is smaller than a row or if the buffer is too small for the
+    /// given size.
+    pub fn new(data: &'a mut [u8], width: usize, height: usize, stride: usize) -> Self {
+        let row_len = width * COLOR_COMPONENTS;
+
+        assert!(
+            stride >= row_len,
+            "the stride must be at least {row_len} bytes"
+        );
+
+        let required_len = match height {
+            0 => 0,
+            _ => (height - 1) * stride + row_len,
+        };
+
+        assert!(
+            data.len() >= required_len,
+            "the buffer must be at least {required_len} bytes long"
+        );
+
+        Self {
+            data,
+            layout: TargetLayout {
+                width,
+                height,
+                stride,
+                format: PixelFormat::default(),
+                alpha_mode: AlphaMode::default(),
+            },
+        }
+    }
+
+    /// Set the pixel format of the render target.
+    pub fn with_format(mut self, format: PixelFormat) -> Self {
+        self.layout.format = format;
+        self
+    }
+
+    /// Set the alpha mode of the render target.
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.layout.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// The width of the render target.
+    pub fn width(&self) -> usize {
+        self.layout.width
+    }
+
+    /// The height of the render target.
+    pub fn height(&self) -> usize {
+        self.layout.height
+    }
+
+    /// Split the buffer into the parts that are covered by each row of wide tiles.
+    pub(crate) fn wide_tile_rows(
+        &mut self,
+        strip_height: usize,
+    ) -> impl ExactSizeIterator<Item = &mut [u8]> {
+        let num_rows = self.layout.height.div_ceil(strip_height);
+
+        self.data
+            .chunks_mut((strip_height * self.layout.stride).max(1))
+            .take(num_rows)
+    }
+}
+
+impl<'a> From<&'a mut Pixmap> for RenderTarget<'a> {
+    fn from(pixmap: &'a mut Pixmap) -> Self {
+        let (width, height) = (pixmap.width, pixmap.height);
+
+        Self::new(&mut pixmap.buf, width, height, width * COLOR_COMPONENTS)
+    }
+}
@@ -484,6 +484,95 @@ impl FlatLine {
     }
 }
 
+/// The x coordinate onto which the parts of lines left of the viewport are projected.
+///
+/// This needs to be negative, so that the crossings of lines with the left edge of the
+/// viewport are preserved.
+const CULL_LEFT_X: f32 = -1.0;
+
+/// Cull and clip lines against a viewport with the given dimensions, so that the cost of
+/// tiling only depends on the visible part of a path.
+///
+/// Parts of lines that lie above or below the viewport don't affect any visible pixels,
+/// so they are removed. Parts to the left of the viewport still contribute to the winding
+/// numbers of visible pixels, and parts to the right of the viewport are needed to end
+/// the filled regions of a strip row, so instead they are projected onto vertical lines
+/// at the sides of the viewport.
+pub fn cull_lines(lines: &mut Vec<FlatLine>, width: usize, height: usize) {
+    let right_x = width as f32;
+    let height = height as f32;
+    let len = lines.len();
+
+    for i in 0..len {
+        let FlatLine { mut p0, mut p1 } = lines[i];
+
+        if p0.y.max(p1.y) < 0.0 || p0.y.min(p1.y) >= height {
+            continue;
+        }
+
+        // Clip vertically. Since we culled lines that are completely outside, at most one
+        // of the points can lie above and at most one below the viewport.
+        if p0.y < 0.0 {
+            p0 = intersect_y(p0, p1, 0.0);
+        } else if p1.y < 0.0 {
+            p1 = intersect_y(p0, p1, 0.0);
+        }
+
+        if p0.y > height {
+            p0 = intersect_y(p0, p1, height);
+        } else if p1.y > height {
+            p1 = intersect_y(p0, p1, height);
+        }
+
+        // Split the line where it crosses the sides of the viewport, so that each
+        // part can be projected separately.
+        let mut points = [p0; 4];
+        let mut n = 1;
+
+        let mut split_at = |x: f32| {
+            if (p0.x - x) * (p1.x - x) < 0.0 {
+                points[n] = intersect_x(p0, p1, x);
+                n += 1;
+            }
+        };
+
+        if p0.x < p1.x {
+            split_at(CULL_LEFT_X);
+            split_at(right_x);
+        } else {
+            split_at(right_x);
+            split_at(CULL_LEFT_X);
+        }
+
+        points[n] = p1;
+
+        for j in 0..n {
+            let project = |p: Point| Point::new(p.x.clamp(CULL_LEFT_X, right_x), p.y);
+            let (q0, q1) = (project(points[j]), project(points[j + 1]));
+
+            if q0 != q1 {
+                lines.push(FlatLine::new(q0, q1));
+            }
+        }
+    }
+
+    lines.drain(..len);
+}
+
+/// The point on the line through `p0` and `p1` with the given y coordinate.
+fn intersect_y(p0: Point, p1: Point, y: f32) -> Point {
+    let t = (y - p0.y) / (p1.y - p0.y);
+
+    Point::new(p0.x + t * (p1.x - p0.x), y)
+}
+
+/// The point on the line through `p0` and `p1` with the given x coordinate.
+fn intersect_x(p0: Point, p1: Point, x: f32) -> Point {
+    let t = (x - p0.x) / (p1.x - p0.x);
+
+    Point::new(x, p0.y + t * (p1.y - p0.y))
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Point {
     pub x: f32,
@@ -530,7 +619,7 @@ fn scale_down(z: Point) -> Point {
 
 #[cfg(test)]
 mod tests {
-    use crate::tiling::{scale_up, FlatLine, Footprint, Point, Tile, Tiles};
+    use crate::tiling::{cull_lines, scale_up, FlatLine, Footprint, Point, Tile, Tiles};
 
     impl Footprint {
         pub(crate) fn is_empty(&self) -> bool {
@@ -683,4 +772,84 @@ mod tests {
         let mut tiles = Tiles::new();
         tiles.make_tiles(&[line]);
     }
+
+    fn line(x0: f32, y0: f32, x1: f32, y1: f32) -> FlatLine {
+        FlatLine::new(Point::new(x0, y0), Point::new(x1, y1))
+    }
+
+    fn assert_lines(lines: &[FlatLine], expected: &[FlatLine]) {
+        assert_eq!(lines.len(), expected.len());
+
+        for (line, expected) in lines.iter().zip(expected) {
+            assert_eq!(line.p0, expected.p0);
+            assert_eq!(line.p1, expected.p1);
+        }
+    }
+
+    #[test]
+    fn cull_visible_lines() {
+        let mut lines = vec![line(1.0, 2.0, 8.5, 7.0), line(8.5, 7.0, 1.0, 2.0)];
+        cull_lines(&mut lines, 10, 10);
+
+        assert_lines(
+            &lines,
+            &[line(1.0, 2.0, 8.5, 7.0), line(8.5, 7.0, 1.0, 2.0)],
+        );
+    }
+
+    #[test]
+    fn cull_lines_above_and_below() {
+        let mut lines = vec![
+            line(1.0, -5.0, 8.0, -1.0),
+            line(1.0, 10.0, 8.0, 15.0),
+            line(1.0, 12.0, 8.0, 11.0),
+        ];
+        cull_lines(&mut lines, 10, 10);
+
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn cull_clip_vertically() {
+        let mut lines = vec![line(2.0, -4.0, 2.0, 14.0), line(4.0, 16.0, 8.0, -4.0)];
+        cull_lines(&mut lines, 10, 10);
+
+        assert_lines(
+            &lines,
+            &[line(2.0, 0.0, 2.0, 10.0), line(5.2, 10.0, 7.2, 0.0)],
+        );
+    }
+
+    #[test]
+    fn cull_project_left_and_right() {
+        let mut lines = vec![
+            line(-50.0, 1.0, -20.0, 5.0),
+            line(30.0, 5.0, 50.0, 1.0),
+            line(-30.0, 2.0, -40.0, 2.0),
+        ];
+        cull_lines(&mut lines, 10, 10);
+
+        assert_lines(
+            &lines,
+            &[line(-1.0, 1.0, -1.0, 5.0), line(10.0, 5.0, 10.0, 1.0)],
+        );
+    }
+
+    #[test]
+    fn cull_split_at_sides() {
+        let mut lines = vec![line(-5.0, 0.0, 15.0, 20.0), line(15.0, 20.0, -5.0, 0.0)];
+        cull_lines(&mut lines, 10, 20);
+
+        assert_lines(
+            &lines,
+            &[
+                line(-1.0, 0.0, -1.0, 4.0),
+                line(-1.0, 4.0, 10.0, 15.0),
+                line(10.0, 15.0, 10.0, 20.0),
+                line(10.0, 20.0, 10.0, 15.0),
+                line(10.0, 15.0, -1.0, 4.0),
+                line(-1.0, 4.0, -1.0, 0.0),
+            ],
+        );
+    }
 }
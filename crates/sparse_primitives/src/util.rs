@@ -45,7 +45,7 @@ pub(crate) mod avx2 {
     use crate::util::scalar::splat_x4;
     use std::arch::x86_64::{
         __m128i, __m256i, _mm256_add_epi16, _mm256_cvtepu8_epi16, _mm256_set1_epi16,
-        _mm256_srli_epi16, _mm_loadu_si128,
+        _mm256_shufflehi_epi16, _mm256_shufflelo_epi16, _mm256_srli_epi16, _mm_loadu_si128,
     };
 
     /// SAFETY: The CPU needs to support the target feature `avx2`.
@@ -67,6 +67,14 @@ pub(crate) mod avx2 {
 
         _mm256_cvtepu8_epi16(_mm_loadu_si128(cs.as_ptr() as *const __m128i))
     }
+
+    /// Broadcast the alpha channel of each pixel in 16x u16 to all of its components.
+    ///
+    /// SAFETY: The CPU needs to support the target feature `avx2`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn splat_alpha(val: __m256i) -> __m256i {
+        _mm256_shufflehi_epi16::<0b11111111>(_mm256_shufflelo_epi16::<0b11111111>(val))
+    }
 }
 
 #[cfg(all(target_arch = "aarch64", feature = "simd"))]
@@ -3,7 +3,7 @@
 
 use crate::paint::Paint;
 use peniko::color::{AlphaColor, Srgb};
-use peniko::Compose;
+use peniko::{BlendMode, Compose, Mix};
 
 pub const WIDE_TILE_WIDTH: usize = 256;
 pub const STRIP_HEIGHT: usize = 4;
@@ -14,6 +14,11 @@ pub struct WideTile {
     pub y: usize,
     pub bg: AlphaColor<Srgb>,
     pub cmds: Vec<Cmd>,
+    /// The number of buffers that have been pushed, but not popped yet.
+    pub(crate) n_bufs: usize,
+    /// The number of active clips that don't cover this tile at all. As long as this is
+    /// non-zero, nothing needs to be drawn into the tile.
+    pub(crate) n_zero_clip: usize,
 }
 
 impl WideTile {
@@ -23,6 +28,8 @@ impl WideTile {
             y,
             bg: AlphaColor::TRANSPARENT,
             cmds: vec![],
+            n_bufs: 0,
+            n_zero_clip: 0,
         }
     }
 }
@@ -31,15 +38,35 @@ impl WideTile {
 pub enum Cmd {
     Fill(CmdFill),
     Strip(CmdStrip),
+    /// Push a new, transparent buffer that subsequent commands draw into.
+    PushBuf,
+    /// Pop the current buffer and composite it into the previous one.
+    PopBuf(CmdPopBuf),
+    /// Push a copy of the current buffer that subsequent commands draw into. This is used
+    /// for clip paths, so that drawing inside of the clip path behaves exactly like drawing
+    /// into the current buffer, regardless of the blend mode.
+    PushClipBuf,
+    /// Copy some columns of the current buffer into the previous one.
+    ClipFill(CmdClipFill),
+    /// Interpolate some columns of the previous buffer towards the current one, using the
+    /// coverage of a strip of the clip path.
+    ClipStrip(CmdClipStrip),
+    /// Pop the current buffer without compositing it. This is used after the parts of
+    /// the buffer that are inside of the clip path have been composited using
+    /// `ClipFill` and `ClipStrip`.
+    DropBuf,
+    /// Pop the current buffer and the mask buffer below it, and composite the current
+    /// buffer into the previous one, modulated by the mask.
+    PopMask(MaskType),
 }
 
-impl Cmd {
-    pub fn compose(&self) -> Compose {
-        match self {
-            Cmd::Fill(f) => f.compose,
-            Cmd::Strip(s) => s.compose,
-        }
-    }
+/// Which value of the mask is used to modulate the masked content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskType {
+    /// Use the alpha channel of the mask.
+    Alpha,
+    /// Use the luminance of the mask, multiplied by its alpha channel.
+    Luminance,
 }
 
 #[derive(Debug)]
@@ -47,7 +74,7 @@ pub struct CmdFill {
     pub x: u32,
     pub width: u32,
     pub paint: Paint,
-    pub compose: Compose,
+    pub blend_mode: BlendMode,
 }
 
 #[derive(Debug)]
@@ -56,28 +83,139 @@ pub struct CmdStrip {
     pub width: u32,
     pub alpha_ix: usize,
     pub paint: Paint,
-    pub compose: Compose,
+    pub blend_mode: BlendMode,
+}
+
+#[derive(Debug)]
+pub struct CmdPopBuf {
+    pub blend_mode: BlendMode,
+    /// The opacity of the buffer, in the range [0, 1].
+    pub alpha: f32,
+}
+
+#[derive(Debug)]
+pub struct CmdClipFill {
+    pub x: u32,
+    pub width: u32,
+}
+
+#[derive(Debug)]
+pub struct CmdClipStrip {
+    pub x: u32,
+    pub width: u32,
+    pub alpha_ix: usize,
 }
 
 impl WideTile {
-    pub(crate) fn fill(&mut self, x: u32, width: u32, paint: Paint, compose: Compose) {
-        let Paint::Solid(s) = &paint;
-        let can_override = x == 0 && width == WIDE_TILE_WIDTH as u32 && s.components[3] == 1.0;
+    pub(crate) fn fill(&mut self, x: u32, width: u32, paint: Paint, blend_mode: BlendMode) {
+        let compose = blend_mode.compose;
+        // With any other mix mode, the result depends on the backdrop, even for opaque paints.
+        let is_normal = matches!(blend_mode.mix, Mix::Normal | Mix::Clip);
+
+        // If the fill completely replaces the contents of the tile, we can discard all
+        // previous commands.
+        let can_override = x == 0
+            && width == WIDE_TILE_WIDTH as u32
+            // The background only applies to the base buffer.
+            && self.n_bufs == 0
+            && match compose {
+                Compose::Clear => true,
+                Compose::Copy => is_normal,
+                Compose::SrcOver => is_normal && paint.is_opaque(),
+                _ => false,
+            };
 
         if can_override {
             self.cmds.clear();
-            self.bg = *s;
-        } else {
-            self.cmds.push(Cmd::Fill(CmdFill {
-                x,
-                width,
-                paint,
-                compose,
-            }));
+
+            match &paint {
+                _ if compose == Compose::Clear => {
+                    self.bg = AlphaColor::TRANSPARENT;
+                    return;
+                }
+                Paint::Solid(s) => {
+                    self.bg = *s;
+                    return;
+                }
+                _ => self.bg = AlphaColor::TRANSPARENT,
+            }
         }
+
+        self.cmds.push(Cmd::Fill(CmdFill {
+            x,
+            width,
+            paint,
+            blend_mode,
+        }));
     }
 
     pub(crate) fn push(&mut self, cmd: Cmd) {
         self.cmds.push(cmd)
     }
+
+    pub(crate) fn push_buf(&mut self) {
+        self.cmds.push(Cmd::PushBuf);
+        self.n_bufs += 1;
+    }
+
+    pub(crate) fn push_clip_buf(&mut self) {
+        self.cmds.push(Cmd::PushClipBuf);
+        self.n_bufs += 1;
+    }
+
+    pub(crate) fn pop_buf(&mut self, blend_mode: BlendMode, alpha: f32) {
+        self.n_bufs -= 1;
+
+        // If nothing was drawn into the buffer, compositing it is a no-op unless the
+        // compose operator also affects the backdrop where the source is transparent.
+        if matches!(self.cmds.last(), Some(Cmd::PushBuf))
+            && matches!(
+                blend_mode.compose,
+                Compose::Dest
+                    | Compose::SrcOver
+                    | Compose::DestOver
+                    | Compose::DestOut
+                    | Compose::SrcAtop
+                    | Compose::Xor
+                    | Compose::Plus
+                    | Compose::PlusLighter
+            )
+        {
+            self.cmds.pop();
+
+            return;
+        }
+
+        self.cmds.push(Cmd::PopBuf(CmdPopBuf { blend_mode, alpha }));
+    }
+
+    /// Pop a buffer that was pushed for a clip path, using the commands that composite
+    /// the parts inside of the clip path.
+    pub(crate) fn pop_clip(&mut self, cmds: Vec<Cmd>) {
+        self.n_bufs -= 1;
+
+        // Nothing was drawn inside of the clip path.
+        if matches!(self.cmds.last(), Some(Cmd::PushClipBuf)) {
+            self.cmds.pop();
+
+            return;
+        }
+
+        self.cmds.extend(cmds);
+    }
+
+    /// Pop the content buffer and the mask buffer that were pushed for a mask.
+    pub(crate) fn pop_mask(&mut self, mask_type: MaskType) {
+        self.n_bufs -= 2;
+
+        // Nothing was drawn into the content buffer, so we can discard both.
+        if matches!(self.cmds.last(), Some(Cmd::PushBuf)) {
+            self.cmds.pop();
+            self.cmds.push(Cmd::DropBuf);
+
+            return;
+        }
+
+        self.cmds.push(Cmd::PopMask(mask_type));
+    }
 }
@@ -1,11 +1,16 @@
 use crate::util::{check_ref, get_ctx, render_pixmap};
 use peniko::color::palette::css::{DARK_GREEN, YELLOW};
-use peniko::kurbo::{Affine, BezPath, Circle, Join, Point, Rect, Shape, Stroke};
-use peniko::{BlendMode, Compose, Mix};
+use peniko::kurbo::{Affine, BezPath, Cap, Circle, Join, Line, Point, Rect, Shape, Stroke};
+use peniko::{
+    BlendMode, Blob, Brush, BrushRef, Compose, Extend, Gradient, ImageFormat, ImageQuality, Mix,
+};
 use sparse_primitives::color::palette::css::{
-    BEIGE, BLUE, GREEN, LIME, MAROON, REBECCA_PURPLE, RED,
+    BEIGE, BLACK, BLUE, GREEN, LIME, MAROON, REBECCA_PURPLE, RED, WHITE,
+};
+use sparse_primitives::paint::{Image, LinearGradient, Paint, RadialGradient, SweepGradient};
+use sparse_primitives::{
+    AlphaMode, Fill, MaskType, PixelFormat, Pixmap, RenderContext, RenderTarget, Scene,
 };
-use sparse_primitives::{Fill, RenderContext};
 use std::f64::consts::PI;
 
 mod util;
@@ -62,7 +67,7 @@ fn empty_1134x1376() {
 fn full_cover_1() {
     let mut ctx = get_ctx(8, 8, true);
 
-    ctx.set_paint(BEIGE.into());
+    ctx.set_paint(BEIGE);
     ctx.fill_path(&Rect::new(0.0, 0.0, 8.0, 8.0).to_path(0.1).into());
 
     check_ref(&ctx, "full_cover_1")
@@ -82,7 +87,7 @@ fn filled_triangle() {
         path
     };
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "filled_triangle");
@@ -102,7 +107,7 @@ fn stroked_triangle() {
     };
 
     ctx.set_stroke(Stroke::new(3.0));
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.stroke_path(&path.into());
 
     check_ref(&ctx, "stroked_triangle");
@@ -112,7 +117,7 @@ fn stroked_triangle() {
 fn filled_circle() {
     let mut ctx = get_ctx(100, 100, false);
     let circle = Circle::new((50.0, 50.0), 45.0);
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&circle.to_path(0.1).into());
 
     check_ref(&ctx, "filled_circle");
@@ -123,7 +128,7 @@ fn filled_circle_with_opacity() {
     let mut ctx = get_ctx(100, 100, false);
     let circle = Circle::new((50.0, 50.0), 45.0);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_path(&circle.to_path(0.1).into());
 
     check_ref(&ctx, "filled_circle_with_opacity");
@@ -135,20 +140,33 @@ fn filled_overlapping_circles() {
 
     for e in [(35.0, 35.0, RED), (65.0, 35.0, GREEN), (50.0, 65.0, BLUE)] {
         let circle = Circle::new((e.0, e.1), 30.0);
-        ctx.set_paint(e.2.with_alpha(0.5).into());
+        ctx.set_paint(e.2.with_alpha(0.5));
         ctx.fill_path(&circle.to_path(0.1).into());
     }
 
     check_ref(&ctx, "filled_overlapping_circles");
 }
 
+#[test]
+fn filled_zoomed_in_circle() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((50.0, 50.0), 40.0);
+
+    // Zoom in so much that only a small part of the left side of the circle is visible.
+    ctx.set_transform(Affine::translate((-950.0, -4950.0)) * Affine::scale(100.0));
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "filled_zoomed_in_circle");
+}
+
 #[test]
 fn stroked_circle() {
     let mut ctx = get_ctx(100, 100, false);
     let circle = Circle::new((50.0, 50.0), 45.0);
     let stroke = Stroke::new(3.0);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.set_stroke(stroke);
     ctx.stroke_path(&circle.to_path(0.1).into());
 
@@ -172,7 +190,7 @@ fn filling_nonzero_rule() {
     let mut ctx = get_ctx(100, 100, false);
     let star = star_path();
 
-    ctx.set_paint(MAROON.into());
+    ctx.set_paint(MAROON);
     ctx.fill_path(&star.into());
 
     check_ref(&ctx, "filling_nonzero_rule");
@@ -183,7 +201,7 @@ fn filling_evenodd_rule() {
     let mut ctx = get_ctx(100, 100, false);
     let star = star_path();
 
-    ctx.set_paint(MAROON.into());
+    ctx.set_paint(MAROON);
     ctx.set_fill_rule(Fill::EvenOdd);
     ctx.fill_path(&star.into());
 
@@ -195,7 +213,7 @@ fn filled_aligned_rect() {
     let mut ctx = get_ctx(30, 20, false);
     let rect = Rect::new(1.0, 1.0, 29.0, 19.0);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_aligned_rect");
@@ -211,7 +229,7 @@ fn stroked_unaligned_rect() {
         ..Default::default()
     };
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -228,7 +246,7 @@ fn stroked_unaligned_rect_as_path() {
         ..Default::default()
     };
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_path(&rect.into());
 
@@ -241,7 +259,7 @@ fn stroked_aligned_rect() {
     let rect = Rect::new(5.0, 5.0, 25.0, 25.0);
     let stroke = miter_stroke_2();
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -258,7 +276,7 @@ fn overflowing_stroked_rect() {
         ..Default::default()
     };
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -271,7 +289,7 @@ fn round_stroked_rect() {
     let rect = Rect::new(5.0, 5.0, 25.0, 25.0);
     let stroke = Stroke::new(3.0);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -288,19 +306,349 @@ fn bevel_stroked_rect() {
         ..Default::default()
     };
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
     check_ref(&ctx, "bevel_stroked_rect");
 }
 
+#[test]
+fn dashed_line() {
+    let mut ctx = get_ctx(100, 20, false);
+    let line = Line::new((10.0, 10.0), (90.0, 10.0));
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(4.0).with_dashes(0.0, [10.0, 5.0]));
+    ctx.stroke_path(&line.to_path(0.1));
+
+    check_ref(&ctx, "dashed_line");
+}
+
+#[test]
+fn dashed_line_with_offset_and_caps() {
+    let mut ctx = get_ctx(100, 40, false);
+    let line = Line::new((10.0, 10.0), (90.0, 10.0));
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(
+        Stroke::new(4.0)
+            .with_caps(Cap::Round)
+            .with_dashes(7.0, [10.0, 8.0]),
+    );
+    ctx.stroke_path(&line.to_path(0.1));
+    ctx.set_stroke(
+        Stroke::new(4.0)
+            .with_caps(Cap::Square)
+            .with_dashes(-3.0, [10.0, 8.0]),
+    );
+    ctx.stroke_path(&(Affine::translate((0.0, 20.0)) * line.to_path(0.1)));
+
+    check_ref(&ctx, "dashed_line_with_offset_and_caps");
+}
+
+#[test]
+fn dashed_curve() {
+    let mut ctx = get_ctx(100, 100, false);
+    let mut path = BezPath::new();
+    path.move_to((10.0, 90.0));
+    path.curve_to((10.0, 10.0), (90.0, 90.0), (90.0, 10.0));
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(3.0).with_dashes(0.0, [12.0, 4.0, 2.0, 4.0]));
+    ctx.stroke_path(&path);
+
+    check_ref(&ctx, "dashed_curve");
+}
+
+#[test]
+fn dashed_circle() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((50.0, 50.0), 40.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(3.0).with_dashes(0.0, [5.0]));
+    ctx.stroke_path(&circle.to_path(0.1));
+
+    check_ref(&ctx, "dashed_circle");
+}
+
+#[test]
+fn dashed_rect() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(
+        Stroke::new(4.0)
+            .with_join(Join::Miter)
+            .with_dashes(5.0, [30.0, 10.0]),
+    );
+    ctx.stroke_rect(&rect);
+
+    check_ref(&ctx, "dashed_rect");
+}
+
+#[test]
+fn dashed_zero_length_round_caps() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(
+        Stroke::new(6.0)
+            .with_caps(Cap::Round)
+            .with_dashes(0.0, [0.0, 10.0]),
+    );
+    ctx.stroke_rect(&rect);
+
+    check_ref(&ctx, "dashed_zero_length_round_caps");
+}
+
+#[test]
+fn dashed_invalid_pattern() {
+    let line = Line::new((10.0, 10.0), (90.0, 10.0));
+    let draw = |stroke: Stroke| {
+        let mut ctx = get_ctx(100, 20, false);
+        ctx.set_paint(BLACK);
+        ctx.set_stroke(stroke);
+        ctx.stroke_path(&line.to_path(0.1));
+
+        render_pixmap(&ctx)
+    };
+
+    let expected = draw(Stroke::new(4.0));
+
+    // Patterns that only contain zeros or negative lengths are ignored.
+    for pattern in [[0.0, 0.0], [10.0, -5.0]] {
+        let actual = draw(Stroke::new(4.0).with_dashes(0.0, pattern));
+        assert_eq!(actual.data(), expected.data(), "{pattern:?}");
+    }
+
+    // Patterns that would yield an excessive number of dashes are ignored as well.
+    let actual = draw(Stroke::new(4.0).with_dashes(0.0, [1e-4, 1e-4]));
+    assert_eq!(actual.data(), expected.data());
+}
+
+fn draw_dashed_closed_path(path: &BezPath, stroke: Stroke) -> Pixmap {
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(stroke.with_join(Join::Miter));
+    ctx.stroke_path(path);
+
+    render_pixmap(&ctx)
+}
+
+#[test]
+fn dashed_closed_path_joins_dash_at_start() {
+    // The dash pattern is "on" at the start and at the end of the rectangle, so the dash
+    // through the top-left corner needs to be drawn with a join, like all other corners.
+    let stroke = Stroke::new(4.0).with_dashes(5.0, [30.0, 10.0]);
+    let mut path = BezPath::new();
+    path.move_to((10.0, 10.0));
+    path.line_to((90.0, 10.0));
+    path.line_to((90.0, 90.0));
+    path.line_to((10.0, 90.0));
+    path.close_path();
+
+    // The same rectangle starting at the top-right corner. The length of the first side
+    // is a multiple of the pattern length, so the dashes end up at the same positions.
+    let mut rotated = BezPath::new();
+    rotated.move_to((90.0, 10.0));
+    rotated.line_to((90.0, 90.0));
+    rotated.line_to((10.0, 90.0));
+    rotated.line_to((10.0, 10.0));
+    rotated.close_path();
+
+    assert_eq!(
+        draw_dashed_closed_path(&path, stroke.clone()).data(),
+        draw_dashed_closed_path(&rotated, stroke).data()
+    );
+}
+
+#[test]
+fn dashed_closed_path_single_dash() {
+    let path = Rect::new(10.0, 10.0, 90.0, 90.0).to_path(0.1);
+
+    // A dash that is longer than the path covers it completely, so the path stays closed.
+    assert_eq!(
+        draw_dashed_closed_path(&path, Stroke::new(4.0).with_dashes(0.0, [400.0, 10.0])).data(),
+        draw_dashed_closed_path(&path, Stroke::new(4.0)).data()
+    );
+}
+
+#[test]
+fn stroked_rotated_circle() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 40.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(4.0));
+    ctx.set_transform(Affine::translate((50.0, 50.0)) * Affine::rotate(PI / 2.0));
+    ctx.stroke_path(&circle.to_path(0.1));
+
+    check_ref(&ctx, "stroked_rotated_circle");
+}
+
+#[test]
+fn stroked_rotated_circle_matches_unrotated() {
+    let draw = |transform: Affine| {
+        let mut ctx = get_ctx(100, 100, false);
+        ctx.set_paint(BLACK);
+        ctx.set_stroke(Stroke::new(4.0));
+        ctx.set_transform(Affine::translate((50.0, 50.0)) * transform);
+        ctx.stroke_path(&Circle::new((0.0, 0.0), 40.0).to_path(0.1));
+
+        render_pixmap(&ctx)
+    };
+
+    let expected = draw(Affine::IDENTITY);
+
+    for angle in [PI / 2.0, PI / 3.0, 3.0 * PI / 4.0] {
+        let actual = draw(Affine::rotate(angle));
+        let max_diff = actual
+            .data()
+            .iter()
+            .zip(expected.data())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap();
+
+        // The outlines are flattened differently, but stay within the tolerance.
+        assert!(max_diff <= 80, "{angle}: {max_diff}");
+    }
+}
+
+#[test]
+fn stroked_skewed_curve() {
+    let mut ctx = get_ctx(100, 100, false);
+    let mut path = BezPath::new();
+    path.move_to((10.0, 60.0));
+    path.curve_to((10.0, 10.0), (60.0, 60.0), (60.0, 10.0));
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(6.0).with_caps(Cap::Round));
+    ctx.set_transform(Affine::translate((0.0, 20.0)) * Affine::skew(0.6, 0.2));
+    ctx.stroke_path(&path);
+
+    check_ref(&ctx, "stroked_skewed_curve");
+}
+
+#[test]
+fn stroked_anisotropic_scale() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 9.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(2.0));
+    ctx.set_transform(
+        Affine::translate((50.0, 50.0))
+            * Affine::rotate(PI / 6.0)
+            * Affine::scale_non_uniform(4.0, 1.5),
+    );
+    ctx.stroke_path(&circle.to_path(0.1));
+
+    check_ref(&ctx, "stroked_anisotropic_scale");
+}
+
+#[test]
+fn stroked_strong_downscale() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 4000.0);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.set_stroke(Stroke::new(200.0));
+    ctx.set_transform(Affine::translate((50.0, 50.0)) * Affine::scale(0.01));
+    ctx.stroke_path(&circle.to_path(0.1));
+
+    check_ref(&ctx, "stroked_strong_downscale");
+}
+
+/// Draw a horizontal line through the centers of the pixels in row 10 and return the alpha
+/// values of rows 9 to 11 at column 50.
+fn horizontal_hairline(stroke: Stroke, transform: Affine) -> [u8; 3] {
+    let mut ctx = get_ctx(100, 20, true);
+    let line = Line::new((10.0, 10.5), (90.0, 10.5));
+
+    ctx.set_paint(BLACK);
+    ctx.set_stroke(stroke);
+    ctx.set_transform(transform);
+    ctx.stroke_path(&(transform.inverse() * line.to_path(0.1)));
+
+    let pixmap = render_pixmap(&ctx);
+    let alpha = |y: usize| pixmap.data()[(y * 100 + 50) * 4 + 3];
+
+    [alpha(9), alpha(10), alpha(11)]
+}
+
+#[test]
+fn hairline_zero_width() {
+    assert_eq!(
+        horizontal_hairline(Stroke::new(0.0), Affine::IDENTITY),
+        [0, 255, 0]
+    );
+}
+
+#[test]
+fn hairline_thin_width() {
+    assert_eq!(
+        horizontal_hairline(Stroke::new(0.5), Affine::IDENTITY),
+        [0, 128, 0]
+    );
+}
+
+#[test]
+fn hairline_downscaled() {
+    assert_eq!(
+        horizontal_hairline(Stroke::new(2.5), Affine::scale(0.1)),
+        [0, 64, 0]
+    );
+}
+
+#[test]
+fn hairline_zoom_independent() {
+    let expected = horizontal_hairline(Stroke::new(0.0), Affine::IDENTITY);
+
+    for scale in [0.01, 0.5, 3.0, 1000.0] {
+        let actual = horizontal_hairline(Stroke::new(0.0), Affine::scale(scale));
+        assert_eq!(actual, expected, "{scale}");
+    }
+}
+
+#[test]
+fn hairline_shapes() {
+    let mut ctx = get_ctx(100, 100, false);
+
+    ctx.set_paint(BLACK);
+    ctx.set_stroke(Stroke::new(0.0));
+    ctx.stroke_path(&star_path());
+    ctx.stroke_path(&Circle::new((50.0, 50.0), 45.0).to_path(0.1));
+    ctx.set_transform(Affine::scale(0.01));
+    ctx.set_paint(BLUE);
+    ctx.set_stroke(Stroke::new(30.0));
+    ctx.stroke_rect(&Rect::new(1000.0, 1000.0, 9000.0, 9000.0));
+
+    check_ref(&ctx, "hairline_shapes");
+}
+
+#[test]
+fn hairline_dashed() {
+    let mut ctx = get_ctx(100, 100, false);
+
+    ctx.set_paint(BLACK);
+    ctx.set_stroke(Stroke::new(0.0).with_dashes(0.0, [6.0, 3.0]));
+    ctx.stroke_path(&Circle::new((50.0, 50.0), 40.0).to_path(0.1));
+    ctx.stroke_rect(&Rect::new(20.0, 20.0, 80.0, 80.0));
+
+    check_ref(&ctx, "hairline_dashed");
+}
+
 #[test]
 fn filled_unaligned_rect() {
     let mut ctx = get_ctx(30, 20, false);
     let rect = Rect::new(1.5, 1.5, 28.5, 18.5);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_unaligned_rect");
@@ -312,7 +660,7 @@ fn filled_transformed_rect_1() {
     let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
 
     ctx.pre_concat_transform(Affine::translate((10.0, 10.0)));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_transformed_rect_1");
@@ -324,7 +672,7 @@ fn filled_transformed_rect_2() {
     let rect = Rect::new(5.0, 5.0, 10.0, 10.0);
 
     ctx.pre_concat_transform(Affine::scale(2.0));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_transformed_rect_2");
@@ -336,7 +684,7 @@ fn filled_transformed_rect_3() {
     let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
 
     ctx.pre_concat_transform(Affine::new([2.0, 0.0, 0.0, 2.0, 5.0, 5.0]));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_transformed_rect_3");
@@ -351,7 +699,7 @@ fn filled_transformed_rect_4() {
         45.0 * PI / 180.0,
         Point::new(15.0, 15.0),
     ));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_transformed_rect_4");
@@ -364,7 +712,7 @@ fn stroked_transformed_rect_1() {
     let stroke = miter_stroke_2();
 
     ctx.pre_concat_transform(Affine::translate((10.0, 10.0)));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -378,7 +726,7 @@ fn stroked_transformed_rect_2() {
     let stroke = miter_stroke_2();
 
     ctx.pre_concat_transform(Affine::scale(2.0));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -392,7 +740,7 @@ fn stroked_transformed_rect_3() {
     let stroke = miter_stroke_2();
 
     ctx.pre_concat_transform(Affine::new([2.0, 0.0, 0.0, 2.0, 5.0, 5.0]));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -409,7 +757,7 @@ fn stroked_transformed_rect_4() {
         45.0 * PI / 180.0,
         Point::new(15.0, 15.0),
     ));
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.set_stroke(stroke);
     ctx.stroke_rect(&rect);
 
@@ -421,7 +769,7 @@ fn strip_inscribed_rect() {
     let mut ctx = get_ctx(30, 20, false);
     let rect = Rect::new(1.5, 9.5, 28.5, 11.5);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "strip_inscribed_rect");
@@ -432,7 +780,7 @@ fn filled_vertical_hairline_rect() {
     let mut ctx = get_ctx(5, 8, false);
     let rect = Rect::new(2.25, 0.0, 2.75, 8.0);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_vertical_hairline_rect");
@@ -443,55 +791,2062 @@ fn filled_vertical_hairline_rect_2() {
     let mut ctx = get_ctx(10, 10, false);
     let rect = Rect::new(4.5, 0.5, 5.5, 9.5);
 
-    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5).into());
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
     check_ref(&ctx, "filled_vertical_hairline_rect_2");
 }
 
-fn miter_stroke_2() -> Stroke {
-    Stroke {
-        width: 2.0,
-        join: Join::Miter,
-        ..Default::default()
-    }
+#[test]
+fn filled_unaligned_rect_corners() {
+    let mut ctx = get_ctx(15, 12, false);
+    let rect = Rect::new(1.25, 2.75, 13.6, 9.3);
+
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "filled_unaligned_rect_corners");
 }
 
-fn bevel_stroke_2() -> Stroke {
-    Stroke {
-        width: 2.0,
-        join: Join::Bevel,
-        ..Default::default()
+#[test]
+fn filled_tiny_rect() {
+    let mut ctx = get_ctx(5, 5, false);
+    let rect = Rect::new(2.2, 2.3, 2.7, 2.6);
+
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "filled_tiny_rect");
+}
+
+#[test]
+fn filled_rect_multiple_wide_tiles() {
+    let mut ctx = get_ctx(600, 12, false);
+    let rect = Rect::new(10.5, 2.5, 590.5, 9.5);
+
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "filled_rect_multiple_wide_tiles");
+}
+
+#[test]
+fn filled_rect_outside_viewport() {
+    let mut ctx = get_ctx(30, 30, false);
+    let rect = Rect::new(-10.5, -5.5, 20.25, 15.75);
+
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_rect(&rect);
+    ctx.fill_rect(&Rect::new(25.5, 25.5, 40.0, 40.0));
+
+    check_ref(&ctx, "filled_rect_outside_viewport");
+}
+
+#[test]
+fn filled_non_finite_rect() {
+    for rect in [
+        Rect::new(f64::NAN, 2.5, 20.0, 20.0),
+        Rect::new(2.5, 2.5, f64::NAN, f64::NAN),
+        Rect::new(f64::NEG_INFINITY, 2.5, 20.0, 20.0),
+    ] {
+        let mut ctx = get_ctx(30, 30, true);
+
+        ctx.set_paint(REBECCA_PURPLE);
+        ctx.fill_rect(&rect);
+
+        assert!(
+            render_pixmap(&ctx).data().iter().all(|c| *c == 0),
+            "{rect:?}"
+        );
     }
 }
 
-fn compose_destination() -> RenderContext {
-    let mut ctx = get_ctx(50, 50, true);
-    let rect = Rect::new(4.5, 4.5, 35.5, 35.5);
-    ctx.set_paint(YELLOW.with_alpha(0.35).into());
-    ctx.set_stroke(bevel_stroke_2());
+#[test]
+fn filled_rotated_90_rect() {
+    let mut ctx = get_ctx(30, 30, false);
+    let rect = Rect::new(8.5, 12.25, 24.5, 17.75);
+
+    // A rotation by 90 degrees around the center, without any rounding errors.
+    ctx.pre_concat_transform(Affine::new([0.0, 1.0, -1.0, 0.0, 30.0, 0.0]));
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
     ctx.fill_rect(&rect);
 
-    ctx
+    check_ref(&ctx, "filled_rotated_90_rect");
 }
 
-fn compose_source(ctx: &mut RenderContext) {
-    let rect = Rect::new(14.5, 14.5, 45.5, 45.5);
-    ctx.set_paint(DARK_GREEN.with_alpha(0.8).into());
+fn linear_gradient_impl(extend: Extend, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let gradient =
+        LinearGradient::new((35.0, 35.0), (65.0, 50.0), [RED, YELLOW, BLUE]).with_extend(extend);
+
+    ctx.set_paint(gradient);
     ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
 }
 
-macro_rules! compose_impl {
-    ($mode:path, $name:expr) => {
-        let mut ctx = compose_destination();
-        ctx.set_blend_mode(BlendMode::new(Mix::Normal, $mode));
-        compose_source(&mut ctx);
+#[test]
+fn linear_gradient_pad() {
+    linear_gradient_impl(Extend::Pad, "linear_gradient_pad");
+}
 
-        check_ref(&ctx, $name);
-    };
+#[test]
+fn linear_gradient_repeat() {
+    linear_gradient_impl(Extend::Repeat, "linear_gradient_repeat");
 }
 
 #[test]
-fn compose_solid_src_over() {
-    compose_impl!(Compose::SrcOver, "compose_solid_src_over");
+fn linear_gradient_reflect() {
+    linear_gradient_impl(Extend::Reflect, "linear_gradient_reflect");
+}
+
+#[test]
+fn linear_gradient_hard_stops() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let gradient = LinearGradient::new(
+        (10.0, 10.0),
+        (90.0, 90.0),
+        [(0.0, RED), (0.5, RED), (0.5, BLUE), (1.0, BLUE)],
+    );
+
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "linear_gradient_hard_stops");
+}
+
+#[test]
+fn linear_gradient_with_opacity() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((50.0, 50.0), 45.0);
+    let gradient = LinearGradient::new(
+        (5.0, 0.0),
+        (95.0, 0.0),
+        [REBECCA_PURPLE.with_alpha(0.2), LIME.with_alpha(0.8)],
+    );
+
+    ctx.set_paint(gradient);
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "linear_gradient_with_opacity");
+}
+
+#[test]
+fn linear_gradient_with_transform() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(-20.0, -20.0, 20.0, 20.0);
+    let gradient = LinearGradient::new((-20.0, 0.0), (20.0, 0.0), [RED, YELLOW, BLUE])
+        .with_extend(Extend::Repeat)
+        .with_transform(Affine::scale(0.5));
+
+    ctx.set_transform(
+        Affine::translate((50.0, 50.0)) * Affine::rotate(PI / 4.0) * Affine::scale(1.5),
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "linear_gradient_with_transform");
+}
+
+fn radial_gradient_impl(extend: Extend, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let gradient = RadialGradient::new((50.0, 50.0), 20.0, [RED, YELLOW, BLUE]).with_extend(extend);
+
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
+}
+
+#[test]
+fn radial_gradient_pad() {
+    radial_gradient_impl(Extend::Pad, "radial_gradient_pad");
+}
+
+#[test]
+fn radial_gradient_repeat() {
+    radial_gradient_impl(Extend::Repeat, "radial_gradient_repeat");
+}
+
+#[test]
+fn radial_gradient_reflect() {
+    radial_gradient_impl(Extend::Reflect, "radial_gradient_reflect");
+}
+
+fn two_point_gradient_impl(gradient: RadialGradient, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
+}
+
+#[test]
+fn two_point_gradient_focal_inside() {
+    let gradient =
+        RadialGradient::new_two_point((35.0, 40.0), 5.0, (50.0, 50.0), 35.0, [RED, YELLOW, BLUE]);
+
+    two_point_gradient_impl(gradient, "two_point_gradient_focal_inside");
+}
+
+#[test]
+fn two_point_gradient_focal_outside() {
+    let gradient =
+        RadialGradient::new_two_point((25.0, 50.0), 5.0, (60.0, 50.0), 20.0, [RED, YELLOW, BLUE]);
+
+    two_point_gradient_impl(gradient, "two_point_gradient_focal_outside");
+}
+
+#[test]
+fn two_point_gradient_focal_outside_repeat() {
+    let gradient =
+        RadialGradient::new_two_point((25.0, 50.0), 5.0, (45.0, 50.0), 10.0, [RED, YELLOW, BLUE])
+            .with_extend(Extend::Repeat);
+
+    two_point_gradient_impl(gradient, "two_point_gradient_focal_outside_repeat");
+}
+
+#[test]
+fn two_point_gradient_focal_on_circle() {
+    let gradient =
+        RadialGradient::new_two_point((20.0, 50.0), 0.0, (50.0, 50.0), 30.0, [RED, YELLOW, BLUE]);
+
+    two_point_gradient_impl(gradient, "two_point_gradient_focal_on_circle");
+}
+
+#[test]
+fn two_point_gradient_shrinking() {
+    let gradient = RadialGradient::new_two_point(
+        (50.0, 50.0),
+        40.0,
+        (60.0, 45.0),
+        10.0,
+        [BLUE.with_alpha(0.5), LIME, REBECCA_PURPLE],
+    )
+    .with_extend(Extend::Reflect);
+
+    two_point_gradient_impl(gradient, "two_point_gradient_shrinking");
+}
+
+#[test]
+fn radial_gradient_with_transform() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 30.0);
+    let gradient = RadialGradient::new((0.0, 0.0), 15.0, [RED, YELLOW, BLUE])
+        .with_extend(Extend::Reflect)
+        .with_transform(Affine::scale_non_uniform(1.0, 0.5));
+
+    ctx.set_transform(
+        Affine::translate((50.0, 50.0)) * Affine::rotate(PI / 6.0) * Affine::scale(1.4),
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "radial_gradient_with_transform");
+}
+
+#[test]
+fn radial_gradient_covering_viewport() {
+    let mut ctx = get_ctx(300, 20, false);
+    let rect = Rect::new(0.0, 0.0, 300.0, 20.0);
+
+    ctx.set_paint(RED);
+    ctx.fill_rect(&Rect::new(10.0, 2.0, 290.0, 18.0));
+
+    // Completely covers all wide tiles, so the previous commands can be discarded.
+    let gradient =
+        RadialGradient::new_two_point((150.0, 10.0), 0.0, (140.0, 10.0), 100.0, [BLUE, LIME]);
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "radial_gradient_covering_viewport");
+}
+
+fn sweep_gradient_impl(start_angle: f32, end_angle: f32, extend: Extend, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let gradient = SweepGradient::new((50.0, 50.0), start_angle, end_angle, [RED, YELLOW, BLUE])
+        .with_extend(extend);
+
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
+}
+
+#[test]
+fn sweep_gradient_full() {
+    sweep_gradient_impl(0.0, 2.0 * PI as f32, Extend::Pad, "sweep_gradient_full");
+}
+
+#[test]
+fn sweep_gradient_pad() {
+    sweep_gradient_impl(
+        PI as f32 / 4.0,
+        PI as f32,
+        Extend::Pad,
+        "sweep_gradient_pad",
+    );
+}
+
+#[test]
+fn sweep_gradient_repeat() {
+    sweep_gradient_impl(
+        PI as f32 / 4.0,
+        PI as f32,
+        Extend::Repeat,
+        "sweep_gradient_repeat",
+    );
+}
+
+#[test]
+fn sweep_gradient_reflect() {
+    sweep_gradient_impl(
+        PI as f32 / 4.0,
+        PI as f32,
+        Extend::Reflect,
+        "sweep_gradient_reflect",
+    );
+}
+
+#[test]
+fn sweep_gradient_across_seam() {
+    // The same gradient as `sweep_gradient_pad`, but with the angles shifted by a full turn.
+    sweep_gradient_impl(
+        -7.0 * PI as f32 / 4.0,
+        -PI as f32,
+        Extend::Pad,
+        "sweep_gradient_pad",
+    );
+}
+
+#[test]
+fn sweep_gradient_reversed() {
+    sweep_gradient_impl(
+        PI as f32 / 2.0,
+        -PI as f32 / 2.0,
+        Extend::Pad,
+        "sweep_gradient_reversed",
+    );
+}
+
+#[test]
+fn sweep_gradient_orientation() {
+    // Angles are measured from the x-axis towards the y-axis, i.e. clockwise on screen,
+    // so the sweep covers the part below the center.
+    sweep_gradient_impl(
+        PI as f32 / 8.0,
+        PI as f32 / 2.0,
+        Extend::Pad,
+        "sweep_gradient_orientation",
+    );
+
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_paint(SweepGradient::new(
+        (50.0, 50.0),
+        PI as f32 / 8.0,
+        PI as f32 / 2.0,
+        [RED, YELLOW, BLUE],
+    ));
+    ctx.fill_rect(&Rect::new(10.0, 10.0, 90.0, 90.0));
+    let pixmap = render_pixmap(&ctx);
+    let pixel = |x: usize, y: usize| &pixmap.data()[(y * 100 + x) * 4..][..4];
+
+    // Above the center, the angle lies after the end of the sweep.
+    assert_eq!(pixel(60, 20), BLUE.to_rgba8().to_u8_array());
+    // Below the center, the angle lies within the sweep.
+    assert_ne!(pixel(60, 80), BLUE.to_rgba8().to_u8_array());
+    assert_ne!(pixel(60, 80), RED.to_rgba8().to_u8_array());
+}
+
+#[test]
+fn sweep_gradient_with_transform() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 30.0);
+    let gradient = SweepGradient::new((0.0, 0.0), 0.0, PI as f32 / 3.0, [RED, YELLOW, BLUE])
+        .with_extend(Extend::Reflect)
+        .with_transform(Affine::skew(0.5, 0.0));
+
+    ctx.set_transform(
+        Affine::translate((50.0, 50.0)) * Affine::rotate(PI / 5.0) * Affine::scale(1.4),
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "sweep_gradient_with_transform");
+}
+
+fn gradient_paints() -> Vec<Paint> {
+    let stops = [RED, YELLOW.with_alpha(0.3), BLUE];
+    let hard_stops = [
+        (0.0, RED),
+        (0.5, RED),
+        (0.5, LIME.with_alpha(0.5)),
+        (1.0, BLUE),
+    ];
+    let mut paints = vec![];
+
+    for extend in [Extend::Pad, Extend::Repeat, Extend::Reflect] {
+        paints.push(
+            LinearGradient::new((-20.0, 5.0), (25.0, 20.0), stops)
+                .with_extend(extend)
+                .into(),
+        );
+        paints.push(
+            LinearGradient::new((10.0, 0.0), (30.0, 0.0), hard_stops.as_slice())
+                .with_extend(extend)
+                .with_transform(Affine::rotate(0.4))
+                .into(),
+        );
+        paints.push(
+            RadialGradient::new_two_point((40.0, 20.0), 5.0, (50.0, 25.0), 15.0, stops)
+                .with_extend(extend)
+                .into(),
+        );
+        // The quadratic coefficient is 0 if the focal point lies on the end circle.
+        paints.push(
+            RadialGradient::new_two_point((20.0, 20.0), 0.0, (50.0, 20.0), 30.0, stops)
+                .with_extend(extend)
+                .into(),
+        );
+        paints.push(
+            RadialGradient::new_two_point((50.0, 20.0), 20.0, (55.0, 18.0), 5.0, stops)
+                .with_extend(extend)
+                .into(),
+        );
+        paints.push(
+            SweepGradient::new((50.0, 20.0), 0.5, 2.0, hard_stops.as_slice())
+                .with_extend(extend)
+                .into(),
+        );
+        // Maps most pixels to huge positions.
+        paints.push(
+            LinearGradient::new((0.0, 0.0), (1e-30, 0.0), stops)
+                .with_extend(extend)
+                .into(),
+        );
+    }
+
+    paints
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn gradients_match_scalar() {
+    use sparse_primitives::execute::ExecutionMode;
+
+    // An odd width, so that the last wide tile ends with a single column.
+    let render = |mode: ExecutionMode, paint: &Paint| {
+        let mut ctx = RenderContext::new_with_execution_mode(101, 40, mode);
+        ctx.set_paint(paint.clone());
+        ctx.fill_rect(&Rect::new(0.0, 0.0, 101.0, 40.0));
+
+        render_pixmap(&ctx)
+    };
+
+    for paint in gradient_paints() {
+        assert_eq!(
+            render(ExecutionMode::Auto, &paint).data(),
+            render(ExecutionMode::Scalar, &paint).data(),
+            "{paint:?}"
+        );
+    }
+}
+
+#[test]
+fn gradients_sharing_color_ramp() {
+    let rect = |i: usize| Rect::new(0.0, 4.0 * i as f64, 101.0, 4.0 * (i + 1) as f64);
+    let paints = gradient_paints();
+
+    // Alternate between the same paints, so that the color ramp is reused in some
+    // commands and needs to be rebuilt in others.
+    let mut ctx = get_ctx(101, 40, true);
+
+    for i in 0..10 {
+        ctx.set_paint(paints[i % 3].clone());
+        ctx.fill_rect(&rect(i));
+    }
+
+    let pixmap = render_pixmap(&ctx);
+    let row_len = 101 * 4 * 4;
+
+    for i in 0..10 {
+        // Use a fresh copy of the paint, which can't share the ramp with anything.
+        let mut expected = get_ctx(101, 40, true);
+        expected.set_paint(gradient_paints().swap_remove(i % 3));
+        expected.fill_rect(&rect(i));
+        let expected = render_pixmap(&expected);
+
+        assert_eq!(
+            pixmap.data()[i * row_len..][..row_len],
+            expected.data()[i * row_len..][..row_len]
+        );
+    }
+}
+
+/// A 4x4 image with distinct colors, some of them semi-transparent.
+fn test_image() -> Pixmap {
+    let colors = [
+        RED,
+        LIME,
+        BLUE,
+        YELLOW,
+        REBECCA_PURPLE.with_alpha(0.5),
+        BEIGE,
+        MAROON.with_alpha(0.7),
+        DARK_GREEN,
+    ];
+    let mut pixmap = Pixmap::new(4, 4);
+
+    for (i, pixel) in pixmap.data_mut().chunks_exact_mut(4).enumerate() {
+        let c = colors[(i + i / 4) % colors.len()].premultiply();
+        pixel.copy_from_slice(&c.to_rgba8().to_u8_array());
+    }
+
+    pixmap
+}
+
+fn image_impl(image: Image, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+
+    ctx.set_paint(image);
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
+}
+
+fn scaled_image(quality: ImageQuality) -> Image {
+    Image::new(test_image())
+        .with_quality(quality)
+        .with_transform(Affine::translate((10.0, 10.0)) * Affine::scale(20.0))
+}
+
+#[test]
+fn image_nearest() {
+    image_impl(scaled_image(ImageQuality::Low), "image_nearest");
+}
+
+#[test]
+fn image_bilinear() {
+    image_impl(scaled_image(ImageQuality::Medium), "image_bilinear");
+}
+
+#[test]
+fn image_bicubic() {
+    image_impl(scaled_image(ImageQuality::High), "image_bicubic");
+}
+
+#[test]
+fn image_pad() {
+    let image = Image::new(test_image())
+        .with_quality(ImageQuality::Low)
+        .with_transform(Affine::translate((35.0, 35.0)) * Affine::scale(8.0));
+
+    image_impl(image, "image_pad");
+}
+
+#[test]
+fn image_repeat() {
+    let image = Image::new(test_image())
+        .with_extend(Extend::Repeat)
+        .with_quality(ImageQuality::Low)
+        .with_transform(Affine::translate((35.0, 35.0)) * Affine::scale(8.0));
+
+    image_impl(image, "image_repeat");
+}
+
+#[test]
+fn image_reflect() {
+    let image = Image::new(test_image())
+        .with_extend(Extend::Reflect)
+        .with_quality(ImageQuality::Low)
+        .with_transform(Affine::translate((35.0, 35.0)) * Affine::scale(8.0));
+
+    image_impl(image, "image_reflect");
+}
+
+#[test]
+fn image_mixed_extend() {
+    let mut image = Image::new(test_image())
+        .with_quality(ImageQuality::Medium)
+        .with_transform(Affine::translate((35.0, 35.0)) * Affine::scale(8.0));
+    image.x_extend = Extend::Repeat;
+    image.y_extend = Extend::Reflect;
+
+    image_impl(image, "image_mixed_extend");
+}
+
+#[test]
+fn image_with_transform() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((0.0, 0.0), 30.0);
+    let image = Image::new(test_image())
+        .with_extend(Extend::Repeat)
+        .with_quality(ImageQuality::Medium)
+        .with_transform(Affine::scale(5.0));
+
+    ctx.set_transform(
+        Affine::translate((50.0, 50.0)) * Affine::rotate(PI / 6.0) * Affine::scale(1.4),
+    );
+    ctx.set_paint(image);
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "image_with_transform");
+}
+
+#[test]
+fn image_from_peniko() {
+    // A 2x2 image with separate alpha.
+    let data = [RED, LIME, BLUE.with_alpha(0.5), YELLOW]
+        .iter()
+        .flat_map(|c| c.to_rgba8().to_u8_array())
+        .collect::<Vec<_>>();
+    let image = peniko::Image::new(Blob::from(data), ImageFormat::Rgba8, 2, 2)
+        .with_extend(Extend::Repeat)
+        .with_quality(ImageQuality::Low)
+        .with_alpha(0.8);
+    let image = Image::from(&image).with_transform(Affine::scale(10.0));
+
+    image_impl(image, "image_from_peniko");
+}
+
+#[test]
+fn image_from_peniko_with_short_data() {
+    let image = peniko::Image::new(Blob::from(vec![255; 12]), ImageFormat::Rgba8, 2, 2);
+    let image = Image::from(&image);
+    assert_eq!(image.pixmap().data().len(), 0);
+
+    let mut ctx = get_ctx(20, 20, true);
+    ctx.set_paint(image);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 20.0, 20.0));
+
+    assert!(render_pixmap(&ctx).data().iter().all(|c| *c == 0));
+}
+
+#[test]
+fn image_huge_sample_coordinates() {
+    for quality in [ImageQuality::Low, ImageQuality::Medium, ImageQuality::High] {
+        for extend in [Extend::Pad, Extend::Repeat, Extend::Reflect] {
+            for transform in [
+                Affine::translate((-1e12, 1e12)),
+                Affine::scale(1e-12),
+                Affine::scale(1e-40),
+            ] {
+                let mut ctx = get_ctx(20, 20, true);
+                let image = Image::new(test_image())
+                    .with_extend(extend)
+                    .with_quality(quality)
+                    .with_transform(transform);
+
+                ctx.set_paint(image);
+                ctx.fill_rect(&Rect::new(0.0, 0.0, 20.0, 20.0));
+                render_pixmap(&ctx);
+            }
+        }
+    }
+}
+
+#[test]
+fn brush_solid() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((50.0, 50.0), 45.0);
+    let brush = Brush::from(REBECCA_PURPLE.with_alpha(0.5));
+
+    ctx.set_paint(brush);
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "filled_circle_with_opacity");
+}
+
+#[test]
+fn brush_solid_multiply_alpha() {
+    let mut ctx = get_ctx(100, 100, false);
+    let circle = Circle::new((50.0, 50.0), 45.0);
+    let brush = BrushRef::from(REBECCA_PURPLE);
+
+    ctx.set_paint(Paint::from(brush).multiply_alpha(0.5));
+    ctx.fill_path(&circle.to_path(0.1).into());
+
+    check_ref(&ctx, "filled_circle_with_opacity");
+}
+
+fn brush_gradient_impl(gradient: Gradient, name: &str) {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+
+    ctx.set_paint(Brush::Gradient(gradient));
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, name);
+}
+
+#[test]
+fn brush_linear_gradient() {
+    let gradient = Gradient::new_linear((35.0, 35.0), (65.0, 50.0))
+        .with_stops([RED, YELLOW, BLUE])
+        .with_extend(Extend::Reflect);
+
+    brush_gradient_impl(gradient, "linear_gradient_reflect");
+}
+
+#[test]
+fn brush_radial_gradient() {
+    let gradient = Gradient::new_two_point_radial((25.0, 50.0), 5.0, (60.0, 50.0), 20.0)
+        .with_stops([RED, YELLOW, BLUE]);
+
+    brush_gradient_impl(gradient, "two_point_gradient_focal_outside");
+}
+
+#[test]
+fn brush_sweep_gradient() {
+    let gradient = Gradient::new_sweep((50.0, 50.0), PI as f32 / 4.0, PI as f32)
+        .with_stops([RED, YELLOW, BLUE])
+        .with_extend(Extend::Repeat);
+
+    brush_gradient_impl(gradient, "sweep_gradient_repeat");
+}
+
+#[test]
+fn brush_sweep_gradient_orientation() {
+    // The angles of peniko gradients use the same convention.
+    let gradient = Gradient::new_sweep((50.0, 50.0), PI as f32 / 8.0, PI as f32 / 2.0)
+        .with_stops([RED, YELLOW, BLUE]);
+
+    brush_gradient_impl(gradient, "sweep_gradient_orientation");
+}
+
+#[test]
+fn brush_image() {
+    let data = [RED, LIME, BLUE.with_alpha(0.5), YELLOW]
+        .iter()
+        .flat_map(|c| c.to_rgba8().to_u8_array())
+        .collect::<Vec<_>>();
+    let image = peniko::Image::new(Blob::from(data), ImageFormat::Rgba8, 2, 2)
+        .with_extend(Extend::Repeat)
+        .with_quality(ImageQuality::Low);
+    let mut ctx = get_ctx(100, 100, false);
+
+    ctx.set_paint(Brush::Image(image));
+    ctx.fill_rect(&Rect::new(10.0, 10.0, 90.0, 90.0));
+
+    check_ref(&ctx, "brush_image");
+}
+
+#[test]
+fn gradient_multiply_alpha() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let gradient = LinearGradient::new((10.0, 0.0), (90.0, 0.0), [RED, BLUE.with_alpha(0.5)]);
+
+    ctx.set_paint(Paint::from(gradient).multiply_alpha(0.5));
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "gradient_multiply_alpha");
+}
+
+#[test]
+fn image_multiply_alpha() {
+    let mut ctx = get_ctx(100, 100, false);
+    let rect = Rect::new(10.0, 10.0, 90.0, 90.0);
+    let image = scaled_image(ImageQuality::Low);
+
+    ctx.set_paint(Paint::from(image).multiply_alpha(0.5));
+    ctx.fill_rect(&rect);
+
+    check_ref(&ctx, "image_multiply_alpha");
+}
+
+fn miter_stroke_2() -> Stroke {
+    Stroke {
+        width: 2.0,
+        join: Join::Miter,
+        ..Default::default()
+    }
+}
+
+fn bevel_stroke_2() -> Stroke {
+    Stroke {
+        width: 2.0,
+        join: Join::Bevel,
+        ..Default::default()
+    }
+}
+
+fn compose_destination() -> RenderContext {
+    let mut ctx = get_ctx(50, 50, true);
+    let rect = Rect::new(4.5, 4.5, 35.5, 35.5);
+    ctx.set_paint(YELLOW.with_alpha(0.35));
+    ctx.set_stroke(bevel_stroke_2());
+    ctx.fill_rect(&rect);
+
+    ctx
+}
+
+fn compose_source(ctx: &mut RenderContext) {
+    let rect = Rect::new(14.5, 14.5, 45.5, 45.5);
+    ctx.set_paint(DARK_GREEN.with_alpha(0.8));
+    ctx.fill_rect(&rect);
+}
+
+fn compose_gradient_source(ctx: &mut RenderContext) {
+    let rect = Rect::new(14.5, 14.5, 45.5, 45.5);
+    let gradient = LinearGradient::new(
+        (14.5, 0.0),
+        (45.5, 0.0),
+        [DARK_GREEN, BLUE.with_alpha(0.6), RED.with_alpha(0.2)],
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+}
+
+macro_rules! compose_impl {
+    ($mode:path, $name:expr) => {
+        compose_impl!($mode, $name, compose_source);
+    };
+    ($mode:path, $name:expr, $source:ident) => {
+        let mut ctx = compose_destination();
+        ctx.set_blend_mode(BlendMode::new(Mix::Normal, $mode));
+        $source(&mut ctx);
+
+        check_ref(&ctx, $name);
+    };
+}
+
+#[test]
+fn compose_solid_src_over() {
+    compose_impl!(Compose::SrcOver, "compose_solid_src_over");
+}
+
+#[test]
+fn compose_solid_clear() {
+    compose_impl!(Compose::Clear, "compose_solid_clear");
+}
+
+#[test]
+fn compose_solid_copy() {
+    compose_impl!(Compose::Copy, "compose_solid_copy");
+}
+
+#[test]
+fn compose_solid_dest() {
+    compose_impl!(Compose::Dest, "compose_solid_dest");
+}
+
+#[test]
+fn compose_solid_dest_over() {
+    compose_impl!(Compose::DestOver, "compose_solid_dest_over");
+}
+
+#[test]
+fn compose_solid_src_in() {
+    compose_impl!(Compose::SrcIn, "compose_solid_src_in");
+}
+
+#[test]
+fn compose_solid_dest_in() {
+    compose_impl!(Compose::DestIn, "compose_solid_dest_in");
+}
+
+#[test]
+fn compose_solid_src_out() {
+    compose_impl!(Compose::SrcOut, "compose_solid_src_out");
+}
+
+#[test]
+fn compose_solid_dest_out() {
+    compose_impl!(Compose::DestOut, "compose_solid_dest_out");
+}
+
+#[test]
+fn compose_solid_src_atop() {
+    compose_impl!(Compose::SrcAtop, "compose_solid_src_atop");
+}
+
+#[test]
+fn compose_solid_dest_atop() {
+    compose_impl!(Compose::DestAtop, "compose_solid_dest_atop");
+}
+
+#[test]
+fn compose_solid_xor() {
+    compose_impl!(Compose::Xor, "compose_solid_xor");
+}
+
+#[test]
+fn compose_solid_plus() {
+    compose_impl!(Compose::Plus, "compose_solid_plus");
+}
+
+#[test]
+fn compose_solid_plus_lighter() {
+    compose_impl!(Compose::PlusLighter, "compose_solid_plus_lighter");
+}
+
+#[test]
+fn compose_gradient_src_over() {
+    compose_impl!(
+        Compose::SrcOver,
+        "compose_gradient_src_over",
+        compose_gradient_source
+    );
+}
+
+#[test]
+fn compose_gradient_copy() {
+    compose_impl!(
+        Compose::Copy,
+        "compose_gradient_copy",
+        compose_gradient_source
+    );
+}
+
+#[test]
+fn compose_gradient_src_in() {
+    compose_impl!(
+        Compose::SrcIn,
+        "compose_gradient_src_in",
+        compose_gradient_source
+    );
+}
+
+#[test]
+fn compose_gradient_dest_out() {
+    compose_impl!(
+        Compose::DestOut,
+        "compose_gradient_dest_out",
+        compose_gradient_source
+    );
+}
+
+#[test]
+fn compose_gradient_xor() {
+    compose_impl!(
+        Compose::Xor,
+        "compose_gradient_xor",
+        compose_gradient_source
+    );
+}
+
+#[test]
+fn compose_gradient_plus() {
+    compose_impl!(
+        Compose::Plus,
+        "compose_gradient_plus",
+        compose_gradient_source
+    );
+}
+
+fn mix_destination() -> RenderContext {
+    let mut ctx = get_ctx(50, 50, true);
+    let rect = Rect::new(4.5, 4.5, 35.5, 35.5);
+    let gradient = LinearGradient::new(
+        (4.5, 0.0),
+        (35.5, 0.0),
+        [BLUE, YELLOW.with_alpha(0.7), RED.with_alpha(0.4)],
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+
+    ctx
+}
+
+fn mix_source(ctx: &mut RenderContext) {
+    let rect = Rect::new(14.5, 14.5, 45.5, 45.5);
+    let gradient = LinearGradient::new(
+        (0.0, 14.5),
+        (0.0, 45.5),
+        [
+            DARK_GREEN,
+            BEIGE.with_alpha(0.8),
+            REBECCA_PURPLE.with_alpha(0.5),
+        ],
+    );
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&rect);
+}
+
+macro_rules! mix_impl {
+    ($mode:path, $name:expr) => {
+        let mut ctx = mix_destination();
+        ctx.set_blend_mode(BlendMode::new($mode, Compose::SrcOver));
+        mix_source(&mut ctx);
+
+        check_ref(&ctx, $name);
+    };
+}
+
+#[test]
+fn mix_multiply() {
+    mix_impl!(Mix::Multiply, "mix_multiply");
+}
+
+#[test]
+fn mix_screen() {
+    mix_impl!(Mix::Screen, "mix_screen");
+}
+
+#[test]
+fn mix_overlay() {
+    mix_impl!(Mix::Overlay, "mix_overlay");
+}
+
+#[test]
+fn mix_darken() {
+    mix_impl!(Mix::Darken, "mix_darken");
+}
+
+#[test]
+fn mix_lighten() {
+    mix_impl!(Mix::Lighten, "mix_lighten");
+}
+
+#[test]
+fn mix_color_dodge() {
+    mix_impl!(Mix::ColorDodge, "mix_color_dodge");
+}
+
+#[test]
+fn mix_color_burn() {
+    mix_impl!(Mix::ColorBurn, "mix_color_burn");
+}
+
+#[test]
+fn mix_hard_light() {
+    mix_impl!(Mix::HardLight, "mix_hard_light");
+}
+
+#[test]
+fn mix_soft_light() {
+    mix_impl!(Mix::SoftLight, "mix_soft_light");
+}
+
+#[test]
+fn mix_difference() {
+    mix_impl!(Mix::Difference, "mix_difference");
+}
+
+#[test]
+fn mix_exclusion() {
+    mix_impl!(Mix::Exclusion, "mix_exclusion");
+}
+
+#[test]
+fn mix_hue() {
+    mix_impl!(Mix::Hue, "mix_hue");
+}
+
+#[test]
+fn mix_saturation() {
+    mix_impl!(Mix::Saturation, "mix_saturation");
+}
+
+#[test]
+fn mix_color() {
+    mix_impl!(Mix::Color, "mix_color");
+}
+
+#[test]
+fn mix_luminosity() {
+    mix_impl!(Mix::Luminosity, "mix_luminosity");
+}
+
+#[test]
+fn mix_solid_multiply() {
+    let mut ctx = compose_destination();
+    ctx.set_blend_mode(BlendMode::new(Mix::Multiply, Compose::SrcOver));
+    compose_source(&mut ctx);
+
+    check_ref(&ctx, "mix_solid_multiply");
+}
+
+#[test]
+fn mix_with_compose() {
+    let mut ctx = mix_destination();
+    ctx.set_blend_mode(BlendMode::new(Mix::Screen, Compose::SrcAtop));
+    mix_source(&mut ctx);
+
+    check_ref(&ctx, "mix_with_compose");
+}
+
+#[test]
+fn mix_solid_hue() {
+    let mut ctx = compose_destination();
+    ctx.set_blend_mode(BlendMode::new(Mix::Hue, Compose::SrcOver));
+    compose_source(&mut ctx);
+
+    check_ref(&ctx, "mix_solid_hue");
+}
+
+fn layer_content(ctx: &mut RenderContext) {
+    ctx.set_paint(RED);
+    ctx.fill_rect(&Rect::new(5.5, 5.5, 30.5, 30.5));
+    ctx.set_paint(BLUE);
+    ctx.fill_rect(&Rect::new(19.5, 19.5, 44.5, 44.5));
+}
+
+#[test]
+fn layer_opacity() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::SrcOver), 0.5);
+    layer_content(&mut ctx);
+    ctx.pop_layer();
+
+    check_ref(&ctx, "layer_opacity");
+}
+
+#[test]
+fn layer_implicit_pop() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::SrcOver), 0.5);
+    layer_content(&mut ctx);
+
+    check_ref(&ctx, "layer_opacity");
+}
+
+#[test]
+fn layer_nested() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.set_paint(YELLOW);
+    ctx.fill_rect(&Rect::new(0.0, 20.0, 50.0, 30.0));
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::SrcOver), 0.7);
+    ctx.set_paint(DARK_GREEN);
+    ctx.fill_rect(&Rect::new(2.5, 2.5, 25.5, 47.5));
+    ctx.push_layer(BlendMode::new(Mix::Multiply, Compose::SrcOver), 0.8);
+    layer_content(&mut ctx);
+    ctx.pop_layer();
+    ctx.pop_layer();
+
+    check_ref(&ctx, "layer_nested");
+}
+
+#[test]
+fn layer_mix() {
+    let mut ctx = mix_destination();
+    ctx.push_layer(BlendMode::new(Mix::Difference, Compose::SrcOver), 1.0);
+    layer_content(&mut ctx);
+    ctx.pop_layer();
+
+    check_ref(&ctx, "layer_mix");
+}
+
+#[test]
+fn layer_compose() {
+    let mut ctx = mix_destination();
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::SrcIn), 1.0);
+    layer_content(&mut ctx);
+    ctx.pop_layer();
+
+    check_ref(&ctx, "layer_compose");
+}
+
+#[test]
+fn layer_copy_outside_content() {
+    let mut ctx = get_ctx(300, 20, true);
+    ctx.set_paint(YELLOW);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 300.0, 20.0));
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::Copy), 1.0);
+    ctx.set_paint(BLUE.with_alpha(0.5));
+    ctx.fill_rect(&Rect::new(2.5, 2.5, 17.5, 17.5));
+    ctx.pop_layer();
+
+    check_ref(&ctx, "layer_copy_outside_content");
+}
+
+fn clip_content(ctx: &mut RenderContext) {
+    let (width, height) = (ctx.width() as f64, ctx.height() as f64);
+    let gradient = LinearGradient::new((0.0, 0.0), (width, height), [RED, YELLOW, BLUE]);
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, width, height));
+}
+
+#[test]
+fn clip_circle() {
+    let mut ctx = get_ctx(100, 100, true);
+    ctx.push_clip(&Circle::new((50.0, 50.0), 35.0).to_path(0.1), Fill::NonZero);
+    clip_content(&mut ctx);
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_circle");
+}
+
+#[test]
+fn clip_implicit_pop() {
+    let mut ctx = get_ctx(100, 100, true);
+    ctx.push_clip(&Circle::new((50.0, 50.0), 35.0).to_path(0.1), Fill::NonZero);
+    clip_content(&mut ctx);
+
+    check_ref(&ctx, "clip_circle");
+}
+
+#[test]
+fn clip_even_odd() {
+    let mut ctx = get_ctx(100, 100, true);
+    ctx.push_clip(&star_path(), Fill::EvenOdd);
+    clip_content(&mut ctx);
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_even_odd");
+}
+
+#[test]
+fn clip_nested() {
+    let mut ctx = get_ctx(100, 100, true);
+    ctx.push_clip(&Circle::new((50.0, 50.0), 35.0).to_path(0.1), Fill::NonZero);
+    ctx.set_transform(Affine::rotate_about(PI / 4.0, Point::new(50.0, 50.0)));
+    ctx.push_clip(
+        &Rect::new(25.0, 25.0, 75.0, 75.0).to_path(0.1),
+        Fill::NonZero,
+    );
+    ctx.reset_transform();
+    clip_content(&mut ctx);
+    ctx.pop_clip();
+    // Drawn with only the outer clip applied.
+    ctx.set_paint(DARK_GREEN);
+    ctx.fill_rect(&Rect::new(0.0, 80.0, 100.0, 100.0));
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_nested");
+}
+
+#[test]
+fn clip_outside_viewport() {
+    let mut ctx = get_ctx(100, 100, true);
+    ctx.push_clip(&Circle::new((20.0, 30.0), 70.0).to_path(0.1), Fill::NonZero);
+    clip_content(&mut ctx);
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_outside_viewport");
+}
+
+#[test]
+fn clip_multiple_wide_tiles() {
+    let mut ctx = get_ctx(600, 40, true);
+    ctx.push_clip(
+        &Rect::new(10.5, 10.5, 530.5, 29.5).to_path(0.1),
+        Fill::NonZero,
+    );
+    clip_content(&mut ctx);
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_multiple_wide_tiles");
+}
+
+/// Fill the whole canvas with the given blend mode inside of a clip path that completely
+/// covers some wide tiles and partially covers others.
+fn clip_blend_mode(blend_mode: BlendMode) -> RenderContext {
+    let mut ctx = get_ctx(600, 40, true);
+    ctx.set_paint(BEIGE);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 600.0, 40.0));
+    ctx.set_paint(MAROON);
+    ctx.fill_rect(&Rect::new(0.0, 15.0, 600.0, 25.0));
+
+    ctx.push_clip(
+        &Rect::new(10.5, 10.5, 530.5, 29.5).to_path(0.1),
+        Fill::NonZero,
+    );
+    ctx.set_blend_mode(blend_mode);
+    ctx.set_paint(BLUE.with_alpha(0.5));
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 600.0, 40.0));
+    ctx.pop_clip();
+
+    // The pixels in the first wide tile are only partially covered by the clip path, while
+    // the ones in the second one are completely covered, but they must be blended the same.
+    let pixmap = render_pixmap(&ctx);
+    let pixel = |x: usize, y: usize| &pixmap.data()[(y * 600 + x) * 4..][..4];
+
+    for y in [12, 20] {
+        assert_eq!(pixel(100, y), pixel(300, y), "{y}");
+    }
+
+    ctx
+}
+
+#[test]
+fn clip_compose_clear() {
+    let ctx = clip_blend_mode(BlendMode::new(Mix::Normal, Compose::Clear));
+
+    check_ref(&ctx, "clip_compose_clear");
+}
+
+#[test]
+fn clip_mix_multiply() {
+    let ctx = clip_blend_mode(BlendMode::new(Mix::Multiply, Compose::SrcOver));
+
+    check_ref(&ctx, "clip_mix_multiply");
+}
+
+#[test]
+fn clip_with_layer() {
+    let mut ctx = mix_destination();
+    ctx.push_clip(&Circle::new((25.0, 25.0), 18.0).to_path(0.1), Fill::NonZero);
+    ctx.push_layer(BlendMode::new(Mix::Normal, Compose::Copy), 0.8);
+    layer_content(&mut ctx);
+    ctx.pop_layer();
+    ctx.pop_clip();
+
+    check_ref(&ctx, "clip_with_layer");
+}
+
+fn mask_content(ctx: &mut RenderContext) {
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 50.0, 50.0));
+    layer_content(ctx);
+}
+
+#[test]
+fn mask_alpha() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_mask(MaskType::Alpha);
+    let gradient = RadialGradient::new((25.0, 25.0), 22.0, [RED, RED.with_alpha(0.0)]);
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 50.0, 50.0));
+    ctx.apply_mask();
+    mask_content(&mut ctx);
+    ctx.pop_mask();
+
+    check_ref(&ctx, "mask_alpha");
+}
+
+#[test]
+fn mask_luminance() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_mask(MaskType::Luminance);
+    let gradient = LinearGradient::new((5.0, 0.0), (45.0, 0.0), [WHITE, BLACK]);
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 50.0, 50.0));
+    // Only the luminance matters, not the hue.
+    ctx.set_paint(GREEN);
+    ctx.fill_rect(&Rect::new(0.0, 35.0, 50.0, 50.0));
+    ctx.apply_mask();
+    mask_content(&mut ctx);
+    ctx.pop_mask();
+
+    check_ref(&ctx, "mask_luminance");
+}
+
+#[test]
+fn mask_luminance_semi_transparent() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_mask(MaskType::Luminance);
+    ctx.set_paint(WHITE.with_alpha(0.5));
+    ctx.fill_path(&Circle::new((25.0, 25.0), 20.0).to_path(0.1));
+    ctx.apply_mask();
+    mask_content(&mut ctx);
+    ctx.pop_mask();
+
+    check_ref(&ctx, "mask_luminance_semi_transparent");
+}
+
+#[test]
+fn mask_implicit_pop() {
+    let mut ctx = get_ctx(50, 50, true);
+    ctx.push_mask(MaskType::Alpha);
+    let gradient = RadialGradient::new((25.0, 25.0), 22.0, [RED, RED.with_alpha(0.0)]);
+    ctx.set_paint(gradient);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 50.0, 50.0));
+    ctx.apply_mask();
+    mask_content(&mut ctx);
+
+    check_ref(&ctx, "mask_alpha");
+}
+
+#[test]
+fn mask_in_clip() {
+    let mut ctx = mix_destination();
+    ctx.set_transform(Affine::scale(0.5));
+    ctx.push_clip(&star_path(), Fill::NonZero);
+    ctx.reset_transform();
+    ctx.push_mask(MaskType::Alpha);
+    ctx.set_paint(BLACK.with_alpha(0.7));
+    ctx.fill_path(&Circle::new((20.0, 20.0), 15.0).to_path(0.1));
+    ctx.apply_mask();
+    mask_content(&mut ctx);
+    ctx.pop_mask();
+    ctx.pop_clip();
+
+    check_ref(&ctx, "mask_in_clip");
+}
+
+/// Check that rendering with multiple threads yields the same result as rendering
+/// with a single thread.
+fn check_parallel(ctx: &RenderContext) {
+    let expected = render_pixmap(ctx);
+
+    for num_threads in [1, 2, 3, 8, 100] {
+        let mut pixmap = Pixmap::new(ctx.width(), ctx.height());
+        ctx.render_to_pixmap_parallel(&mut pixmap, num_threads);
+
+        assert_eq!(pixmap.data(), expected.data(), "{num_threads} threads");
+    }
+}
+
+#[test]
+fn parallel_basic() {
+    let mut ctx = get_ctx(300, 70, false);
+    let circle = Circle::new((150.0, 35.0), 30.0);
+
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_path(&circle.to_path(0.1));
+    ctx.set_paint(LIME);
+    ctx.stroke_path(&star_path());
+    ctx.fill_rect(&Rect::new(200.5, 10.5, 290.5, 60.5));
+
+    check_parallel(&ctx);
+}
+
+#[test]
+fn render_to_taller_pixmap() {
+    let mut ctx = get_ctx(20, 10, true);
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 20.0, 10.0));
+    let expected = render_pixmap(&ctx);
+
+    for num_threads in [1, 2, 8] {
+        let mut pixmap = Pixmap::new(20, 30);
+        ctx.render_to_pixmap_parallel(&mut pixmap, num_threads);
+
+        // The rows below the canvas are left untouched.
+        let (canvas, below) = pixmap.data().split_at(expected.data().len());
+        assert_eq!(canvas, expected.data(), "{num_threads} threads");
+        assert!(below.iter().all(|c| *c == 0), "{num_threads} threads");
+    }
+}
+
+#[test]
+fn parallel_fewer_rows_than_threads() {
+    let mut ctx = get_ctx(50, 3, false);
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_path(&Circle::new((25.0, 1.5), 10.0).to_path(0.1));
+
+    check_parallel(&ctx);
+}
+
+#[test]
+fn parallel_with_layers() {
+    let mut ctx = get_ctx(600, 90, true);
+    let (width, height) = (ctx.width() as f64, ctx.height() as f64);
+
+    ctx.push_clip(
+        &Circle::new((300.0, 45.0), 40.0).to_path(0.1),
+        Fill::NonZero,
+    );
+    ctx.push_layer(BlendMode::new(Mix::Multiply, Compose::SrcOver), 0.7);
+    clip_content(&mut ctx);
+    ctx.push_mask(MaskType::Luminance);
+    ctx.set_paint(LinearGradient::new(
+        (250.0, 0.0),
+        (350.0, 0.0),
+        [WHITE, BLACK],
+    ));
+    ctx.fill_rect(&Rect::new(0.0, 0.0, width, height));
+    ctx.apply_mask();
+    ctx.set_paint(RadialGradient::new((300.0, 45.0), 30.0, [BLUE, GREEN]));
+    ctx.fill_rect(&Rect::new(0.0, 0.0, width, height));
+
+    check_parallel(&ctx);
+}
+
+fn target_content() -> RenderContext {
+    let mut ctx = get_ctx(300, 70, true);
+
+    ctx.set_paint(REBECCA_PURPLE.with_alpha(0.5));
+    ctx.fill_path(&Circle::new((150.0, 35.0), 30.0).to_path(0.1));
+    ctx.set_paint(LinearGradient::new((0.0, 0.0), (300.0, 0.0), [RED, BLUE]));
+    ctx.stroke_path(&star_path());
+
+    ctx
+}
+
+/// Check that rendering into a render target with the given layout yields the same
+/// pixels as rendering into a pixmap, with the padding left untouched.
+fn check_target(format: PixelFormat, alpha_mode: AlphaMode, num_threads: usize) {
+    const PADDING: u8 = 0xAB;
+
+    let ctx = target_content();
+    let mut expected = render_pixmap(&ctx);
+
+    if alpha_mode == AlphaMode::Unpremultiplied {
+        expected.unpremultiply();
+    }
+
+    let (width, height) = (ctx.width(), ctx.height());
+    let stride = width * 4 + 12;
+    let mut buf = vec![PADDING; stride * height];
+
+    let mut target = RenderTarget::new(&mut buf, width, height, stride)
+        .with_format(format)
+        .with_alpha_mode(alpha_mode);
+    ctx.render_to_target_parallel(&mut target, num_threads);
+
+    for (row, expected_row) in buf.chunks(stride).zip(expected.data().chunks(width * 4)) {
+        let (pixels, padding) = row.split_at(width * 4);
+
+        for (actual, expected) in pixels.chunks(4).zip(expected_row.chunks(4)) {
+            let expected = match format {
+                PixelFormat::Rgba8 => [expected[0], expected[1], expected[2], expected[3]],
+                PixelFormat::Bgra8 => [expected[2], expected[1], expected[0], expected[3]],
+            };
+
+            assert_eq!(actual, expected);
+        }
+
+        assert!(padding.iter().all(|p| *p == PADDING));
+    }
+}
+
+#[test]
+fn target_rgba8_premultiplied() {
+    check_target(PixelFormat::Rgba8, AlphaMode::Premultiplied, 1);
+}
+
+#[test]
+fn target_bgra8_premultiplied() {
+    check_target(PixelFormat::Bgra8, AlphaMode::Premultiplied, 1);
+}
+
+#[test]
+fn target_rgba8_unpremultiplied() {
+    check_target(PixelFormat::Rgba8, AlphaMode::Unpremultiplied, 1);
+}
+
+#[test]
+fn target_bgra8_unpremultiplied_parallel() {
+    check_target(PixelFormat::Bgra8, AlphaMode::Unpremultiplied, 3);
+}
+
+/// Check that rendering into a render target whose size differs from the one of the render
+/// context only writes the pixels that are covered by both.
+fn check_target_size(width: usize, height: usize) {
+    const UNTOUCHED: u8 = 0xAB;
+
+    let ctx = target_content();
+    let expected = render_pixmap(&ctx);
+
+    for num_threads in [1, 3] {
+        let mut buf = vec![UNTOUCHED; width * height * 4];
+        let mut target = RenderTarget::new(&mut buf, width, height, width * 4);
+        ctx.render_to_target_parallel(&mut target, num_threads);
+
+        for (y, row) in buf.chunks(width * 4).enumerate() {
+            for (x, actual) in row.chunks(4).enumerate() {
+                if x < ctx.width() && y < ctx.height() {
+                    let ix = (y * ctx.width() + x) * 4;
+                    assert_eq!(actual, &expected.data()[ix..][..4], "{x}, {y}");
+                } else {
+                    assert!(actual.iter().all(|c| *c == UNTOUCHED), "{x}, {y}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn target_smaller_than_canvas() {
+    check_target_size(100, 30);
+    check_target_size(257, 70);
+    check_target_size(300, 1);
+}
+
+#[test]
+fn target_larger_than_canvas() {
+    check_target_size(400, 90);
+    check_target_size(300, 71);
+    check_target_size(301, 70);
+}
+
+#[test]
+#[should_panic]
+fn target_too_small() {
+    let mut buf = vec![0; 100];
+    RenderTarget::new(&mut buf, 10, 3, 40);
+}
+
+/// Check that rendering a region yields the same pixels as cropping the full render.
+fn check_region(region: Rect, width: usize, height: usize) {
+    let ctx = target_content();
+    let full = render_pixmap(&ctx);
+
+    let mut pixmap = Pixmap::new(width, height);
+    ctx.render_region(&region, &mut pixmap);
+
+    let (x0, y0) = (region.x0.floor() as isize, region.y0.floor() as isize);
+    let (x1, y1) = (region.x1.ceil() as isize, region.y1.ceil() as isize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (cx, cy) = (x0 + x as isize, y0 + y as isize);
+            let actual = &pixmap.data()[(y * width + x) * 4..][..4];
+
+            let inside_canvas =
+                (0..ctx.width() as isize).contains(&cx) && (0..ctx.height() as isize).contains(&cy);
+            let inside_region = cx < x1 && cy < y1;
+
+            if inside_canvas && inside_region {
+                let ix = (cy as usize * ctx.width() + cx as usize) * 4;
+                assert_eq!(actual, &full.data()[ix..][..4], "pixel ({x}, {y})");
+            } else {
+                assert_eq!(actual, [0, 0, 0, 0], "pixel ({x}, {y})");
+            }
+        }
+    }
+}
+
+#[test]
+fn region_full_canvas() {
+    check_region(Rect::new(0.0, 0.0, 300.0, 70.0), 300, 70);
+}
+
+#[test]
+fn region_within_wide_tile() {
+    check_region(Rect::new(120.0, 20.0, 180.0, 50.0), 60, 30);
+}
+
+#[test]
+fn region_across_wide_tiles() {
+    check_region(Rect::new(201.0, 3.0, 290.0, 67.0), 89, 64);
+}
+
+#[test]
+fn region_fractional() {
+    check_region(Rect::new(130.5, 10.2, 170.7, 40.9), 41, 31);
+}
+
+#[test]
+fn region_outside_canvas() {
+    check_region(Rect::new(-20.0, -10.0, 30.0, 20.0), 50, 30);
+    check_region(Rect::new(280.0, 60.0, 320.0, 90.0), 40, 30);
+    check_region(Rect::new(400.0, 0.0, 450.0, 30.0), 50, 30);
+}
+
+#[test]
+fn region_larger_than_pixmap() {
+    check_region(Rect::new(100.0, 10.0, 200.0, 60.0), 30, 20);
+}
+
+#[test]
+fn region_unbounded() {
+    check_region(Rect::new(100.0, 10.0, f64::INFINITY, f64::INFINITY), 30, 20);
+}
+
+#[test]
+fn region_huge_coordinates() {
+    let ctx = target_content();
+
+    for region in [
+        Rect::new(1e300, 0.0, f64::INFINITY, 70.0),
+        Rect::new(isize::MAX as f64, 0.0, f64::MAX, 70.0),
+        Rect::new(-1e300, -1e300, 1e300, 1e300),
+        Rect::new(f64::NEG_INFINITY, 0.0, 300.0, 70.0),
+        Rect::new(f64::NAN, 0.0, 300.0, 70.0),
+    ] {
+        let mut pixmap = Pixmap::new(30, 20);
+        ctx.render_region(&region, &mut pixmap);
+
+        assert!(pixmap.data().iter().all(|c| *c == 0), "{region:?}");
+    }
+}
+
+/// Draw the same content once with and once without batching and check that the
+/// results are identical.
+fn check_batched(draw: impl Fn(&mut RenderContext)) {
+    let mut ctx = get_ctx(300, 70, false);
+    draw(&mut ctx);
+    let expected = render_pixmap(&ctx);
+
+    for num_threads in [2, 3, 8] {
+        let mut ctx = get_ctx(300, 70, false);
+        ctx.set_batch_threads(num_threads);
+        draw(&mut ctx);
+        ctx.flush();
+
+        assert_eq!(
+            render_pixmap(&ctx).data(),
+            expected.data(),
+            "{num_threads} threads"
+        );
+    }
+}
+
+#[test]
+fn batched_paths() {
+    check_batched(|ctx| {
+        for i in 0..50 {
+            let (x, y) = ((i * 37 % 300) as f64, (i * 13 % 70) as f64);
+            let circle = Circle::new((x, y), 5.0 + (i % 7) as f64 * 4.0);
+
+            ctx.set_paint([RED, GREEN, BLUE][i % 3].with_alpha(0.5));
+            ctx.set_fill_rule([Fill::NonZero, Fill::EvenOdd][i % 2]);
+            ctx.set_transform(Affine::translate((i as f64 * 0.3, 0.0)));
+
+            if i % 4 == 0 {
+                ctx.set_stroke(Stroke::new(1.0 + (i % 3) as f64));
+                ctx.stroke_path(&circle.to_path(0.1));
+            } else {
+                ctx.fill_path(&circle.to_path(0.1));
+            }
+        }
+    });
+}
+
+#[test]
+fn batched_paths_with_layers() {
+    check_batched(|ctx| {
+        ctx.set_paint(BLUE);
+        ctx.fill_path(&star_path());
+        ctx.push_clip(
+            &Circle::new((150.0, 35.0), 30.0).to_path(0.1),
+            Fill::NonZero,
+        );
+        ctx.set_blend_mode(BlendMode::new(Mix::Multiply, Compose::SrcOver));
+        ctx.set_paint(RED.with_alpha(0.7));
+        ctx.fill_path(&Circle::new((140.0, 35.0), 20.0).to_path(0.1));
+        ctx.fill_rect(&Rect::new(145.5, 20.5, 200.5, 50.5));
+        ctx.set_paint(GREEN);
+        ctx.fill_path(&Circle::new((160.0, 35.0), 20.0).to_path(0.1));
+        ctx.pop_clip();
+        ctx.fill_path(&Circle::new((250.0, 35.0), 20.0).to_path(0.1));
+    });
+}
+
+#[test]
+#[should_panic]
+fn batched_paths_without_flush() {
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_batch_threads(2);
+    ctx.fill_path(&star_path());
+
+    render_pixmap(&ctx);
+}
+
+#[test]
+#[should_panic]
+fn wide_tiles_without_flush() {
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_batch_threads(2);
+    ctx.fill_path(&star_path());
+
+    ctx.wide_tiles();
+}
+
+/// Draw the same operations into a render context directly and via a scene, and check
+/// that the results are identical.
+macro_rules! check_scene {
+    (|$target:ident| $draw:block) => {{
+        let mut ctx = get_ctx(300, 70, false);
+        {
+            let $target = &mut ctx;
+            $draw
+        }
+        let expected = render_pixmap(&ctx);
+
+        let mut scene = Scene::new();
+        {
+            let $target = &mut scene;
+            $draw
+        }
+        let mut ctx = get_ctx(300, 70, false);
+        ctx.render_scene(&scene, Affine::IDENTITY);
+
+        assert_eq!(render_pixmap(&ctx).data(), expected.data());
+    }};
+}
+
+#[test]
+fn scene_matches_direct_drawing() {
+    check_scene!(|target| {
+        target.set_paint(BLUE);
+        target.fill_path(&star_path());
+        target.set_transform(Affine::translate((100.0, 0.0)));
+        target.set_fill_rule(Fill::EvenOdd);
+        target.set_paint(RED.with_alpha(0.5));
+        target.fill_path(&star_path());
+        target.post_concat_transform(Affine::translate((0.0, -15.0)));
+        target.pre_concat_transform(Affine::scale(0.5));
+        target.set_stroke(Stroke::new(3.0));
+        target.stroke_rect(&Rect::new(10.0, 40.0, 90.0, 90.0));
+        target.reset_transform();
+        target.set_tolerance(2.0);
+        target.set_anti_aliasing(false);
+        target.push_clip(
+            &Circle::new((250.0, 35.0), 30.0).to_path(0.1),
+            Fill::NonZero,
+        );
+        target.set_blend_mode(BlendMode::new(Mix::Multiply, Compose::SrcOver));
+        target.set_paint(GREEN);
+        target.fill_rect(&Rect::new(230.5, 0.0, 300.0, 50.5));
+        target.pop_clip();
+    });
+}
+
+#[test]
+fn scene_matches_direct_drawing_with_layers() {
+    check_scene!(|target| {
+        target.push_layer(BlendMode::new(Mix::Normal, Compose::SrcOver), 0.5);
+        target.set_paint(MAROON);
+        target.fill_rect(&Rect::new(10.0, 10.0, 290.0, 60.0));
+        target.push_mask(MaskType::Alpha);
+        target.fill_path(&Circle::new((150.0, 35.0), 30.0).to_path(0.1));
+        target.apply_mask();
+        target.set_paint(LIME);
+        target.stroke_path(&star_path());
+        target.fill_rect(&Rect::new(100.0, 0.0, 200.0, 70.0));
+        target.pop_mask();
+        target.pop_layer();
+    });
+}
+
+fn scene_content() -> Scene {
+    let mut scene = Scene::new();
+    scene.fill_path(&star_path());
+    scene.set_paint(REBECCA_PURPLE.with_alpha(0.7));
+    scene.set_transform(Affine::translate((20.0, 0.0)));
+    scene.fill_path(&Circle::new((50.0, 50.0), 25.0).to_path(0.1));
+    scene.set_paint(LinearGradient::new((10.0, 0.0), (90.0, 0.0), [RED, BLUE]));
+    scene.set_stroke(Stroke::new(4.0));
+    scene.stroke_rect(&Rect::new(10.0, 10.0, 80.0, 80.0));
+
+    scene
+}
+
+#[test]
+fn scene_replay_with_transforms() {
+    let mut ctx = get_ctx(300, 100, false);
+    let scene = scene_content();
+
+    ctx.render_scene(&scene, Affine::IDENTITY);
+    ctx.render_scene(
+        &scene,
+        Affine::translate((110.0, 30.0)) * Affine::scale(0.5),
+    );
+    ctx.render_scene(
+        &scene,
+        Affine::rotate_about(0.3, (250.0, 50.0)) * Affine::translate((180.0, 0.0)),
+    );
+
+    check_ref(&ctx, "scene_replay_with_transforms");
+}
+
+#[test]
+fn scene_replay_keeps_state() {
+    let mut ctx = get_ctx(100, 100, false);
+    let transform = Affine::translate((0.0, 50.0)) * Affine::scale(0.5);
+
+    ctx.set_paint(GREEN);
+    ctx.set_transform(transform);
+    ctx.set_tolerance(0.5);
+    ctx.set_anti_aliasing(false);
+    ctx.render_scene(&scene_content(), Affine::IDENTITY);
+    assert_eq!(ctx.current_transform(), transform);
+    assert_eq!(ctx.tolerance(), 0.5);
+    assert!(!ctx.anti_aliasing());
+
+    ctx.fill_rect(&Rect::new(110.0, 10.0, 190.0, 90.0));
+
+    check_ref(&ctx, "scene_replay_keeps_state");
+}
+
+#[test]
+fn scene_append() {
+    let mut ctx = get_ctx(200, 100, false);
+    let mut scene = Scene::new();
+
+    scene.set_paint(GREEN);
+    scene.set_transform(Affine::translate((0.0, 50.0)) * Affine::scale(0.5));
+    scene.append(&scene_content(), Affine::translate((100.0, 0.0)));
+    // The state of the scene must not be affected by the appended scene.
+    scene.fill_rect(&Rect::new(10.0, 10.0, 90.0, 90.0));
+    scene.append(
+        &scene_content(),
+        Affine::translate((100.0, 50.0)) * Affine::scale(0.5),
+    );
+    assert_eq!(
+        scene.current_transform(),
+        Affine::translate((0.0, 50.0)) * Affine::scale(0.5)
+    );
+
+    ctx.render_scene(&scene, Affine::IDENTITY);
+
+    check_ref(&ctx, "scene_append");
+}
+
+#[test]
+fn scene_replay_unbalanced_layers() {
+    let circle = Circle::new((150.0, 35.0), 30.0).to_path(0.1);
+    let blend_mode = BlendMode::new(Mix::Normal, Compose::SrcOver);
+
+    let mut scene = Scene::new();
+    // These must not pop the layer of the render context.
+    scene.pop_layer();
+    scene.pop_clip();
+    scene.pop_mask();
+    scene.push_clip(&circle, Fill::NonZero);
+    scene.push_layer(blend_mode, 0.5);
+    scene.push_mask(MaskType::Alpha);
+    scene.fill_rect(&Rect::new(0.0, 0.0, 150.0, 70.0));
+    scene.apply_mask();
+    scene.set_paint(BLUE);
+    scene.fill_rect(&Rect::new(0.0, 0.0, 300.0, 70.0));
+
+    let mut ctx = get_ctx(300, 70, false);
+    ctx.push_layer(blend_mode, 0.7);
+    ctx.push_clip(&circle, Fill::NonZero);
+    ctx.push_layer(blend_mode, 0.5);
+    ctx.push_mask(MaskType::Alpha);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 150.0, 70.0));
+    ctx.apply_mask();
+    ctx.set_paint(BLUE);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 300.0, 70.0));
+    ctx.pop_mask();
+    ctx.pop_layer();
+    ctx.pop_clip();
+    ctx.set_paint(RED);
+    ctx.fill_rect(&Rect::new(200.0, 10.0, 280.0, 60.0));
+    ctx.pop_layer();
+    let expected = render_pixmap(&ctx);
+
+    let mut ctx = get_ctx(300, 70, false);
+    ctx.push_layer(blend_mode, 0.7);
+    ctx.render_scene(&scene, Affine::IDENTITY);
+    ctx.set_paint(RED);
+    ctx.fill_rect(&Rect::new(200.0, 10.0, 280.0, 60.0));
+    ctx.pop_layer();
+
+    assert_eq!(render_pixmap(&ctx).data(), expected.data());
+}
+
+fn aliased_content(ctx: &mut RenderContext) {
+    let mut star = BezPath::new();
+    star.move_to((50.0, 5.0));
+    star.line_to((76.5, 91.0));
+    star.line_to((7.5, 36.0));
+    star.line_to((92.5, 36.0));
+    star.line_to((23.5, 91.0));
+    star.close_path();
+
+    ctx.set_anti_aliasing(false);
+    ctx.set_paint(RED);
+    ctx.set_fill_rule(Fill::EvenOdd);
+    ctx.fill_path(&star);
+    ctx.set_paint(BLUE);
+    ctx.set_fill_rule(Fill::NonZero);
+    ctx.fill_path(&Circle::new((70.3, 65.7), 20.2).to_path(0.1));
+    ctx.set_paint(LIME);
+    ctx.set_stroke(Stroke::new(0.3));
+    ctx.stroke_path(&Circle::new((35.0, 60.0), 25.0).to_path(0.1));
+    ctx.set_stroke(Stroke::new(3.7).with_caps(Cap::Round));
+    ctx.stroke_path(&Line::new((5.3, 95.1), (95.8, 4.2)).to_path(0.1));
+}
+
+#[test]
+fn aliased_fill() {
+    let mut ctx = get_ctx(100, 100, false);
+    aliased_content(&mut ctx);
+
+    check_ref(&ctx, "aliased_fill");
+}
+
+#[test]
+fn aliased_no_blending() {
+    let mut ctx = get_ctx(100, 100, true);
+    aliased_content(&mut ctx);
+
+    let colors = [RED, BLUE, LIME].map(|c| c.to_rgba8().to_u8_array());
+
+    for pixel in render_pixmap(&ctx).data().chunks_exact(4) {
+        assert!(
+            pixel == [0, 0, 0, 0] || colors.iter().any(|c| c == pixel),
+            "{pixel:?}"
+        );
+    }
+}
+
+#[test]
+fn aliased_pixel_centers() {
+    let mut ctx = get_ctx(20, 20, true);
+    ctx.set_anti_aliasing(false);
+    ctx.set_paint(BLACK);
+    ctx.fill_rect(&Rect::new(2.5, 3.6, 10.4, 12.5));
+
+    let pixmap = render_pixmap(&ctx);
+
+    for y in 0..20 {
+        for x in 0..20 {
+            let covered = (3..10).contains(&x) && (4..12).contains(&y);
+            let expected = if covered { 255 } else { 0 };
+            assert_eq!(pixmap.data()[(y * 20 + x) * 4 + 3], expected, "{x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn aliased_clip() {
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_anti_aliasing(false);
+    ctx.push_clip(&Circle::new((50.0, 50.0), 40.3).to_path(0.1), Fill::NonZero);
+    ctx.set_paint(REBECCA_PURPLE);
+    ctx.fill_rect(&Rect::new(0.0, 0.0, 100.0, 100.0));
+    ctx.pop_clip();
+
+    check_ref(&ctx, "aliased_clip");
+}
+
+#[test]
+fn aliased_hairline_is_opaque() {
+    let mut ctx = get_ctx(100, 20, true);
+    ctx.set_anti_aliasing(false);
+    ctx.set_paint(BLACK);
+    ctx.set_stroke(Stroke::new(0.5));
+    ctx.stroke_path(&Line::new((10.0, 10.5), (90.0, 10.5)).to_path(0.1));
+
+    let pixmap = render_pixmap(&ctx);
+    let alpha = |y: usize| pixmap.data()[(y * 100 + 50) * 4 + 3];
+
+    assert_eq!([alpha(9), alpha(10), alpha(11)], [0, 255, 0]);
+}
+
+#[test]
+fn anti_aliasing_setting() {
+    let mut ctx = get_ctx(10, 10, true);
+    assert!(ctx.anti_aliasing());
+
+    ctx.set_anti_aliasing(false);
+    assert!(!ctx.anti_aliasing());
+}
+
+#[test]
+fn aliased_batched() {
+    check_batched(|ctx| {
+        aliased_content(ctx);
+    });
+}
+
+#[test]
+fn aliased_rect_matches_path() {
+    for rect in [
+        Rect::new(2.5, 3.6, 10.4, 12.5),
+        Rect::new(0.2, 0.7, 7.5, 19.9),
+        Rect::new(4.0, 5.0, 4.3, 5.2),
+    ] {
+        let render = |path: bool| {
+            let mut ctx = get_ctx(20, 20, true);
+            ctx.set_anti_aliasing(false);
+            ctx.set_paint(BLACK);
+
+            if path {
+                ctx.fill_path(&rect.to_path(0.1));
+            } else {
+                ctx.fill_rect(&rect);
+            }
+
+            render_pixmap(&ctx)
+        };
+
+        assert_eq!(render(true).data(), render(false).data(), "{rect:?}");
+    }
 }
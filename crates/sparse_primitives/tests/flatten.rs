@@ -0,0 +1,195 @@
+use peniko::kurbo::{Affine, BezPath, Circle, Shape, Stroke};
+use sparse_primitives::color::palette::css::BLUE;
+use sparse_primitives::flatten::{self, DEFAULT_TOLERANCE};
+use sparse_primitives::tiling::{FlatLine, Point};
+use sparse_primitives::{Pixmap, RenderContext};
+
+const TOLERANCES: [f64; 5] = [2.0, 1.0, DEFAULT_TOLERANCE, 0.05, 0.01];
+const CENTER: (f64, f64) = (50.0, 50.0);
+/// Accounts for the conversion of the lines to `f32`.
+const EPSILON: f64 = 1e-3;
+
+fn distance_to_center(p: Point) -> f64 {
+    (p.x as f64 - CENTER.0).hypot(p.y as f64 - CENTER.1)
+}
+
+fn midpoint(line: &FlatLine) -> Point {
+    Point::new((line.p0.x + line.p1.x) * 0.5, (line.p0.y + line.p1.y) * 0.5)
+}
+
+/// The maximum distance of the endpoints and midpoints of the lines to the closest of the
+/// circles around `CENTER` with the given radii.
+///
+/// For a flattened convex curve, the midpoints are the points that deviate the most.
+fn max_deviation(lines: &[FlatLine], radii: &[f64]) -> f64 {
+    lines
+        .iter()
+        .flat_map(|l| [l.p0, l.p1, midpoint(l)])
+        .map(|p| {
+            let d = distance_to_center(p);
+            radii
+                .iter()
+                .map(|r| (d - r).abs())
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+fn circle() -> Circle {
+    Circle::new(CENTER, 40.0)
+}
+
+#[test]
+fn fill_deviation() {
+    let path = circle().to_path(1e-9);
+    let mut prev_len = 0;
+
+    for tolerance in TOLERANCES {
+        let mut lines = vec![];
+        flatten::fill(&path, Affine::IDENTITY, tolerance, &mut lines);
+
+        let deviation = max_deviation(&lines, &[40.0]);
+        assert!(deviation <= tolerance + EPSILON, "{tolerance}: {deviation}");
+        assert!(lines.len() >= prev_len, "{tolerance}");
+        prev_len = lines.len();
+    }
+}
+
+#[test]
+fn fill_deviation_transformed() {
+    let path = Circle::new((0.0, 0.0), 10.0).to_path(1e-9);
+    let transform = Affine::translate(CENTER) * Affine::scale(4.0);
+
+    for tolerance in TOLERANCES {
+        let mut lines = vec![];
+        flatten::fill(&path, transform, tolerance, &mut lines);
+
+        let deviation = max_deviation(&lines, &[40.0]);
+        assert!(deviation <= tolerance + EPSILON, "{tolerance}: {deviation}");
+    }
+}
+
+#[test]
+fn stroke_deviation() {
+    let path = circle().to_path(1e-9);
+    let stroke = Stroke::new(10.0);
+    let mut prev_len = 0;
+
+    for tolerance in TOLERANCES {
+        let mut lines = vec![];
+        flatten::stroke(&path, &stroke, Affine::IDENTITY, tolerance, &mut lines);
+
+        let deviation = max_deviation(&lines, &[35.0, 45.0]);
+        assert!(deviation <= tolerance + EPSILON, "{tolerance}: {deviation}");
+        assert!(lines.len() >= prev_len, "{tolerance}");
+        prev_len = lines.len();
+    }
+}
+
+#[test]
+fn stroke_deviation_transformed() {
+    let path = Circle::new((0.0, 0.0), 10.0).to_path(1e-9);
+    let stroke = Stroke::new(2.5);
+    let transform = Affine::translate(CENTER) * Affine::rotate(0.3) * Affine::scale(4.0);
+
+    for tolerance in TOLERANCES {
+        let mut lines = vec![];
+        flatten::stroke(&path, &stroke, transform, tolerance, &mut lines);
+
+        let deviation = max_deviation(&lines, &[35.0, 45.0]);
+        assert!(deviation <= tolerance + EPSILON, "{tolerance}: {deviation}");
+    }
+}
+
+#[test]
+fn context_tolerance() {
+    let path = circle().to_path(1e-9);
+    let mut prev_len = 0;
+
+    for tolerance in TOLERANCES {
+        let mut ctx = RenderContext::new(100, 100);
+        ctx.set_tolerance(tolerance);
+        assert_eq!(ctx.tolerance(), tolerance);
+
+        ctx.fill_path(&path);
+
+        let deviation = max_deviation(ctx.line_buf(), &[40.0]);
+        assert!(deviation <= tolerance + EPSILON, "{tolerance}: {deviation}");
+        assert!(ctx.line_buf().len() >= prev_len, "{tolerance}");
+        prev_len = ctx.line_buf().len();
+    }
+}
+
+#[test]
+fn default_tolerance() {
+    let ctx = RenderContext::new(100, 100);
+    assert_eq!(ctx.tolerance(), DEFAULT_TOLERANCE);
+}
+
+#[test]
+#[should_panic]
+fn invalid_tolerance() {
+    let mut ctx = RenderContext::new(100, 100);
+    ctx.set_tolerance(0.0);
+}
+
+/// A loop around a 100x100 viewport, consisting of curves above, right of, below and left
+/// of it.
+fn loop_around_viewport() -> BezPath {
+    let mut path = BezPath::new();
+    path.move_to((-10.0, -10.0));
+    path.curve_to((50.0, -100.0), (150.0, -50.0), (110.0, -10.0));
+    path.curve_to((200.0, 20.0), (150.0, 120.0), (110.0, 110.0));
+    path.curve_to((50.0, 200.0), (20.0, 150.0), (-10.0, 110.0));
+    path.curve_to((-100.0, 50.0), (-50.0, 20.0), (-10.0, -10.0));
+    path.close_path();
+
+    path
+}
+
+#[test]
+fn fill_in_viewport_collapses_curves_outside() {
+    let path = loop_around_viewport();
+
+    let mut lines = vec![];
+    flatten::fill(&path, Affine::IDENTITY, DEFAULT_TOLERANCE, &mut lines);
+    assert!(lines.len() > 20);
+
+    flatten::fill_in_viewport(
+        &path,
+        Affine::IDENTITY,
+        DEFAULT_TOLERANCE,
+        100,
+        100,
+        &mut lines,
+    );
+    assert_eq!(lines.len(), 4);
+
+    // Curves that intersect the viewport are still flattened.
+    let path = circle().to_path(1e-9);
+    let mut expected = vec![];
+    flatten::fill(&path, Affine::IDENTITY, DEFAULT_TOLERANCE, &mut expected);
+    flatten::fill_in_viewport(
+        &path,
+        Affine::IDENTITY,
+        DEFAULT_TOLERANCE,
+        100,
+        100,
+        &mut lines,
+    );
+    assert_eq!(lines.len(), expected.len());
+}
+
+#[test]
+fn fill_loop_around_viewport() {
+    let mut ctx = RenderContext::new(100, 100);
+    ctx.set_paint(BLUE);
+    ctx.fill_path(&loop_around_viewport());
+
+    let mut pixmap = Pixmap::new(100, 100);
+    ctx.render_to_pixmap(&mut pixmap);
+
+    for pixel in pixmap.data().chunks_exact(4) {
+        assert_eq!(pixel, [0, 0, 255, 255]);
+    }
+}
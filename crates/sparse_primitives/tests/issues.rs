@@ -16,7 +16,7 @@ fn issue_2_incorrect_filling_1() {
 
     let mut ctx = get_ctx(8, 8, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&p.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_1");
@@ -33,7 +33,7 @@ fn issue_2_incorrect_filling_2() {
 
     let mut ctx = get_ctx(64, 64, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&p.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_2");
@@ -50,7 +50,7 @@ fn issue_2_incorrect_filling_3() {
 
     let mut ctx = get_ctx(9, 9, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_3");
@@ -73,7 +73,7 @@ fn issue_2_incorrect_filling_4() {
 
     let mut ctx = get_ctx(64, 64, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_4");
@@ -90,7 +90,7 @@ fn issue_2_incorrect_filling_5() {
 
     let mut ctx = get_ctx(32, 32, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_5");
@@ -107,7 +107,7 @@ fn issue_2_incorrect_filling_6() {
 
     let mut ctx = get_ctx(32, 32, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_6");
@@ -124,7 +124,7 @@ fn issue_2_incorrect_filling_7() {
 
     let mut ctx = get_ctx(32, 32, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_7");
@@ -147,7 +147,7 @@ fn issue_2_incorrect_filling_8() {
 
     let mut ctx = get_ctx(32, 32, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_2_incorrect_filling_8");
@@ -162,7 +162,7 @@ fn issue_11_out_of_bound_strip() {
 
     let mut ctx = get_ctx(256, 256, true);
 
-    ctx.set_paint(DARK_BLUE.into());
+    ctx.set_paint(DARK_BLUE);
     ctx.set_stroke(stroke);
     // Just make sure we don't panic.
     ctx.stroke_path(&path.into());
@@ -176,7 +176,7 @@ fn issue_12_filling_unclosed_path_1() {
     path.line_to((25.0, 75.0));
     let mut ctx = get_ctx(100, 100, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_12_filling_unclosed_path_1");
@@ -197,7 +197,7 @@ fn issue_12_filling_unclosed_path_2() {
 
     let mut ctx = get_ctx(100, 100, false);
 
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_12_filling_unclosed_path_2");
@@ -214,7 +214,7 @@ fn issue_28_triangle_exceeding_viewport_1() {
     let mut ctx = get_ctx(15, 8, false);
 
     ctx.set_fill_rule(Fill::EvenOdd);
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_28_triangle_exceeding_viewport_1");
@@ -231,7 +231,7 @@ fn issue_28_triangle_exceeding_viewport_2() {
     let mut ctx = get_ctx(15, 8, false);
 
     ctx.set_fill_rule(Fill::EvenOdd);
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_28_triangle_exceeding_viewport_2");
@@ -249,7 +249,7 @@ fn issue_30_shape_at_wide_tile_boundary() {
     let mut ctx = get_ctx(256, 4, false);
 
     ctx.set_fill_rule(Fill::EvenOdd);
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     // Just make sure we don't panic.
@@ -268,7 +268,7 @@ fn issue_eo_filling_missing_anti_aliasing() {
     let mut ctx = get_ctx(50, 50, false);
 
     ctx.set_fill_rule(Fill::EvenOdd);
-    ctx.set_paint(LIME.into());
+    ctx.set_paint(LIME);
     ctx.fill_path(&path.into());
 
     check_ref(&ctx, "issue_eo_filling_missing_anti_aliasing");
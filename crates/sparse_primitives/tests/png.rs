@@ -0,0 +1,110 @@
+use crate::util::{check_ref, get_ctx, render_pixmap};
+use peniko::kurbo::{Circle, Shape};
+use sparse_primitives::color::palette::css::{BLUE, RED, YELLOW};
+use sparse_primitives::paint::{Image, RadialGradient};
+use sparse_primitives::Pixmap;
+
+mod util;
+
+fn encode(width: u32, height: u32, color_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(color_type);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(data).unwrap();
+    writer.finish().unwrap();
+
+    out
+}
+
+#[test]
+fn png_decode_rgba() {
+    let data = encode(
+        2,
+        1,
+        png::ColorType::Rgba,
+        &[255, 0, 0, 255, 200, 100, 50, 128],
+    );
+    let pixmap = Pixmap::from_png(&data).unwrap();
+
+    assert_eq!(pixmap.data(), &[255, 0, 0, 255, 100, 50, 25, 128]);
+}
+
+#[test]
+fn png_decode_rgb() {
+    let data = encode(2, 1, png::ColorType::Rgb, &[255, 0, 0, 10, 20, 30]);
+    let pixmap = Pixmap::from_png(&data).unwrap();
+
+    assert_eq!(pixmap.data(), &[255, 0, 0, 255, 10, 20, 30, 255]);
+}
+
+#[test]
+fn png_decode_gray() {
+    let data = encode(2, 1, png::ColorType::Grayscale, &[0, 200]);
+    let pixmap = Pixmap::from_png(&data).unwrap();
+
+    assert_eq!(pixmap.data(), &[0, 0, 0, 255, 200, 200, 200, 255]);
+}
+
+#[test]
+fn png_decode_gray_alpha() {
+    let data = encode(2, 1, png::ColorType::GrayscaleAlpha, &[200, 255, 200, 0]);
+    let pixmap = Pixmap::from_png(&data).unwrap();
+
+    assert_eq!(pixmap.data(), &[200, 200, 200, 255, 0, 0, 0, 0]);
+}
+
+#[test]
+fn png_decode_invalid() {
+    assert!(Pixmap::from_png(&[]).is_err());
+    assert!(Pixmap::from_png(b"not a png").is_err());
+}
+
+#[test]
+fn png_encode_unpremultiplies() {
+    let mut pixmap = Pixmap::new(2, 1);
+    pixmap
+        .data_mut()
+        .copy_from_slice(&[100, 50, 25, 128, 0, 0, 0, 0]);
+
+    let decoded = image::load_from_memory(&pixmap.encode_png().unwrap())
+        .unwrap()
+        .into_rgba8();
+
+    assert_eq!((decoded.width(), decoded.height()), (2, 1));
+    assert_eq!(decoded.as_raw(), &[199, 100, 50, 128, 0, 0, 0, 0]);
+}
+
+#[test]
+fn png_round_trip() {
+    let mut ctx = get_ctx(64, 64, true);
+    ctx.set_paint(RadialGradient::new(
+        (32.0, 32.0),
+        32.0,
+        [RED, YELLOW.with_alpha(0.6), BLUE.with_alpha(0.05)],
+    ));
+    ctx.fill_path(&Circle::new((32.0, 32.0), 30.0).to_path(0.1));
+    let pixmap = render_pixmap(&ctx);
+
+    let decoded = Pixmap::from_png(&pixmap.encode_png().unwrap()).unwrap();
+
+    assert_eq!(decoded.data(), pixmap.data());
+}
+
+#[test]
+fn png_decode_as_image_paint() {
+    let data = encode(
+        2,
+        2,
+        png::ColorType::Rgb,
+        &[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 0],
+    );
+    let image = Image::new(Pixmap::from_png(&data).unwrap())
+        .with_transform(peniko::kurbo::Affine::scale(50.0));
+
+    let mut ctx = get_ctx(100, 100, false);
+    ctx.set_paint(image);
+    ctx.fill_path(&Circle::new((50.0, 50.0), 45.0).to_path(0.1));
+
+    check_ref(&ctx, "png_decode_as_image_paint");
+}
@@ -0,0 +1,393 @@
+use crate::util::{check_ref, get_ctx, render_pixmap};
+use peniko::kurbo::{Affine, BezPath, Cap, Circle, Join, Rect, Shape, Stroke};
+use peniko::{BlendMode, Compose, Extend, ImageQuality, Mix};
+use sparse_primitives::color::palette::css::{BLUE, GREEN, LIME, MAROON, RED, YELLOW};
+use sparse_primitives::color::{AlphaColor, Srgb};
+use sparse_primitives::paint::{Image, LinearGradient, RadialGradient, SweepGradient};
+use sparse_primitives::serialize::ReadError;
+use sparse_primitives::{Fill, MaskType, Pixmap, Scene};
+
+mod util;
+
+fn write(scene: &Scene) -> String {
+    let mut buf = vec![];
+    scene.write(&mut buf).unwrap();
+
+    String::from_utf8(buf).unwrap()
+}
+
+fn read(data: &str) -> Result<Scene, ReadError> {
+    Scene::read(data.as_bytes())
+}
+
+fn render(scene: &Scene) -> Pixmap {
+    let mut ctx = get_ctx(200, 100, false);
+    ctx.render_scene(scene, Affine::IDENTITY);
+
+    render_pixmap(&ctx)
+}
+
+/// Check that the scene stays the same when writing it and reading it back.
+fn check_round_trip(scene: &Scene) {
+    let written = write(scene);
+    let read_back = read(&written).unwrap();
+
+    assert_eq!(write(&read_back), written);
+    assert_eq!(render(&read_back).data(), render(scene).data());
+}
+
+fn curvy_path() -> BezPath {
+    let mut path = BezPath::new();
+    path.move_to((10.1, 10.2));
+    path.quad_to((50.0, 90.0), (90.3, 10.0));
+    path.curve_to((120.0, 0.0), (150.0, 100.0), (1.0 / 3.0, 80.0));
+    path.close_path();
+    path.move_to((150.0, 10.0));
+    path.line_to((190.0, 90.0));
+
+    path
+}
+
+fn test_image() -> Pixmap {
+    let mut pixmap = Pixmap::new(3, 2);
+
+    for (i, pixel) in pixmap.data_mut().chunks_exact_mut(4).enumerate() {
+        let c = [RED, LIME, BLUE.with_alpha(0.5)][i % 3].premultiply();
+        pixel.copy_from_slice(&c.to_rgba8().to_u8_array());
+    }
+
+    pixmap
+}
+
+#[test]
+fn round_trip_empty() {
+    check_round_trip(&Scene::new());
+}
+
+#[test]
+fn round_trip_paths_and_rects() {
+    let mut scene = Scene::new();
+    scene.fill_path(&curvy_path());
+    scene.set_fill_rule(Fill::EvenOdd);
+    scene.set_transform(Affine::new([0.9, 0.1, -0.2, 1.1, 0.1 + 0.2, -5.5]));
+    scene.fill_rect(&Rect::new(100.5, 10.25, 180.0, 60.0));
+    scene.set_stroke(Stroke {
+        width: 2.5,
+        join: Join::Miter,
+        miter_limit: 7.0,
+        start_cap: Cap::Round,
+        end_cap: Cap::Square,
+        dash_pattern: [4.0, 2.0, 1.5].into_iter().collect(),
+        dash_offset: 0.75,
+    });
+    scene.set_tolerance(0.6);
+    scene.stroke_path(&curvy_path());
+    scene.set_anti_aliasing(false);
+    scene.set_stroke(Stroke::new(0.5).with_join(Join::Round));
+    scene.stroke_rect(&Rect::new(5.0, 5.0, 195.0, 95.0));
+
+    check_round_trip(&scene);
+}
+
+#[test]
+fn round_trip_paints() {
+    let mut scene = Scene::new();
+    let circle = Circle::new((100.0, 50.0), 45.0).to_path(0.1);
+
+    scene.set_paint(RED.with_alpha(0.3));
+    scene.fill_path(&circle);
+    scene.set_paint(
+        LinearGradient::new((10.0, 0.0), (190.0, 0.0), [RED, YELLOW, BLUE])
+            .with_extend(Extend::Repeat)
+            .with_transform(Affine::rotate(0.3)),
+    );
+    scene.fill_path(&circle);
+    scene.set_paint(
+        RadialGradient::new_two_point((80.0, 50.0), 5.0, (100.0, 50.0), 40.0, [GREEN, MAROON])
+            .with_extend(Extend::Reflect),
+    );
+    scene.fill_path(&circle);
+    scene.set_paint(SweepGradient::new(
+        (100.0, 50.0),
+        0.5,
+        4.0,
+        [LIME, BLUE.with_alpha(0.5)],
+    ));
+    scene.fill_path(&circle);
+
+    let mut image = Image::new(test_image())
+        .with_extend(Extend::Repeat)
+        .with_quality(ImageQuality::Low)
+        .with_transform(Affine::scale(7.0));
+    image.y_extend = Extend::Reflect;
+    image.alpha = 0.8;
+    scene.set_paint(image);
+    scene.fill_path(&circle);
+    scene.set_paint(Image::new(Pixmap::new(0, 0)));
+    scene.fill_path(&circle);
+
+    check_round_trip(&scene);
+}
+
+#[test]
+fn round_trip_layers_masks_and_clips() {
+    let mut scene = Scene::new();
+
+    scene.push_clip(&curvy_path(), Fill::EvenOdd);
+    scene.push_layer(BlendMode::new(Mix::ColorDodge, Compose::SrcAtop), 0.6);
+    scene.set_blend_mode(BlendMode::new(Mix::Multiply, Compose::PlusLighter));
+    scene.set_paint(MAROON);
+    scene.fill_rect(&Rect::new(0.0, 0.0, 200.0, 100.0));
+    scene.push_mask(MaskType::Luminance);
+    scene.fill_path(&Circle::new((100.0, 50.0), 30.0).to_path(0.1));
+    scene.apply_mask();
+    scene.set_paint(LIME);
+    scene.fill_rect(&Rect::new(50.0, 0.0, 150.0, 100.0));
+    scene.pop_mask();
+    scene.push_mask(MaskType::Alpha);
+    scene.apply_mask();
+    scene.pop_mask();
+    scene.pop_layer();
+    scene.pop_clip();
+
+    check_round_trip(&scene);
+}
+
+#[test]
+fn round_trip_appended_scene() {
+    let mut inner = Scene::new();
+    inner.set_paint(BLUE);
+    inner.set_transform(Affine::scale(0.5));
+    inner.fill_path(&curvy_path());
+
+    let mut scene = Scene::new();
+    scene.set_paint(RED);
+    scene.append(&inner, Affine::translate((50.0, 20.0)));
+    scene.fill_rect(&Rect::new(0.0, 0.0, 30.0, 30.0));
+
+    check_round_trip(&scene);
+
+    let read_back = read(&write(&scene)).unwrap();
+    assert_eq!(read_back.current_transform(), scene.current_transform());
+}
+
+#[test]
+fn read_hand_written_scene() {
+    let scene = read(
+        "sparse-scene 1
+
+         # A red triangle with a dashed outline.
+         set_paint solid 1 0 0 1
+         fill_path M 10 10 L 90 10 L 50 90 Z
+         set_stroke width 2 join miter miter_limit 4 start_cap round end_cap round dash_offset 0 dashes 4 2
+         set_transform 1 0 0 1 0 0.5
+         stroke_path M 10 10 L 90 10 L 50 90 Z",
+    )
+    .unwrap();
+
+    let mut path = BezPath::new();
+    path.move_to((10.0, 10.0));
+    path.line_to((90.0, 10.0));
+    path.line_to((50.0, 90.0));
+    path.close_path();
+
+    let mut ctx = get_ctx(200, 100, false);
+    ctx.set_paint(RED);
+    ctx.fill_path(&path);
+    ctx.set_stroke(Stroke {
+        width: 2.0,
+        join: Join::Miter,
+        miter_limit: 4.0,
+        start_cap: Cap::Round,
+        end_cap: Cap::Round,
+        dash_pattern: [4.0, 2.0].into_iter().collect(),
+        dash_offset: 0.0,
+    });
+    ctx.set_transform(Affine::translate((0.0, 0.5)));
+    ctx.stroke_path(&path);
+    let expected = render_pixmap(&ctx);
+
+    let mut ctx = get_ctx(200, 100, false);
+    ctx.render_scene(&scene, Affine::IDENTITY);
+    assert_eq!(render_pixmap(&ctx).data(), expected.data());
+
+    check_ref(&ctx, "serialize_hand_written_scene");
+}
+
+fn read_error(data: &str) -> ReadError {
+    match read(data) {
+        Ok(_) => panic!("reading the scene should fail"),
+        Err(err) => err,
+    }
+}
+
+fn assert_invalid_command(data: &str, expected_line: usize) {
+    match read_error(data) {
+        ReadError::InvalidCommand { line, .. } => assert_eq!(line, expected_line),
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test]
+fn read_invalid_header() {
+    assert!(matches!(read_error(""), ReadError::InvalidHeader));
+    assert!(matches!(
+        read_error("fill_rect 0 0 1 1"),
+        ReadError::InvalidHeader
+    ));
+    assert!(matches!(
+        read_error("sparse-scene one"),
+        ReadError::InvalidHeader
+    ));
+}
+
+#[test]
+fn read_unsupported_version() {
+    assert!(matches!(
+        read_error("sparse-scene 2\nfill_rect 0 0 1 1"),
+        ReadError::UnsupportedVersion(2)
+    ));
+}
+
+#[test]
+fn read_invalid_commands() {
+    assert_invalid_command("sparse-scene 1\nfill_rect 0 0 1 1\ndraw_circle 1 2 3", 3);
+    assert_invalid_command("sparse-scene 1\n\n# Comment\nfill_rect 0 0 1", 4);
+    assert_invalid_command("sparse-scene 1\nfill_rect 0 0 1 1 1", 2);
+    assert_invalid_command("sparse-scene 1\nfill_rect 0 0 one 1", 2);
+    assert_invalid_command("sparse-scene 1\nfill_path M 0 0 X 1 1", 2);
+    assert_invalid_command("sparse-scene 1\nset_fill_rule winding", 2);
+    assert_invalid_command("sparse-scene 1\nset_tolerance 0", 2);
+    assert_invalid_command("sparse-scene 1\nset_tolerance inf", 2);
+    assert_invalid_command("sparse-scene 1\nset_anti_aliasing 0", 2);
+    assert_invalid_command(
+        "sparse-scene 1\npush_layer compose src_over mix normal alpha 1",
+        2,
+    );
+    assert_invalid_command("sparse-scene 1\nset_paint solid 1 0 0", 2);
+    assert_invalid_command(
+        "sparse-scene 1\nset_paint image x_extend pad y_extend pad quality low alpha 1 \
+         transform 1 0 0 1 0 0 size 1 1 data ff0000",
+        2,
+    );
+}
+
+#[test]
+fn read_path_without_move_to() {
+    assert_invalid_command(
+        "sparse-scene 1
+fill_path L 1 1",
+        2,
+    );
+    assert_invalid_command(
+        "sparse-scene 1
+fill_path Q 1 1 2 2",
+        2,
+    );
+    assert_invalid_command(
+        "sparse-scene 1
+stroke_path C 1 1 2 2 3 3",
+        2,
+    );
+    assert_invalid_command(
+        "sparse-scene 1
+push_clip nonzero Z",
+        2,
+    );
+}
+
+#[test]
+fn read_huge_image_size() {
+    for size in [
+        "4294967296 4294967296",
+        "18446744073709551615 2",
+        "1000000 1000000",
+    ] {
+        assert_invalid_command(
+            &format!(
+                "sparse-scene 1\nset_paint image x_extend pad y_extend pad quality low \
+                 alpha 1 transform 1 0 0 1 0 0 size {size} data ff0000ff"
+            ),
+            2,
+        );
+    }
+}
+
+#[test]
+fn read_image_not_premultiplied() {
+    assert_invalid_command(
+        "sparse-scene 1\nset_paint image x_extend pad y_extend pad quality low alpha 1 \
+         transform 1 0 0 1 0 0 size 2 1 data 40404080ff000080",
+        2,
+    );
+}
+
+#[test]
+fn read_non_finite_numbers() {
+    for number in ["NaN", "inf", "-inf", "infinity"] {
+        assert_invalid_command(&format!("sparse-scene 1\nfill_rect 0 0 {number} 1"), 2);
+        assert_invalid_command(
+            &format!("sparse-scene 1\nset_transform 1 0 0 {number} 0 0"),
+            2,
+        );
+        assert_invalid_command(
+            &format!("sparse-scene 1\npush_layer mix normal compose src_over alpha {number}"),
+            2,
+        );
+        assert_invalid_command(
+            &format!(
+                "sparse-scene 1\nset_paint linear start 0 0 end 1 0 extend pad \
+                 transform 1 0 0 1 0 0 stops 0 1 0 0 1 1 0 0 {number} 1"
+            ),
+            2,
+        );
+    }
+}
+
+#[test]
+fn read_out_of_range_colors() {
+    let scene = read(
+        "sparse-scene 1
+         set_paint solid 2 -1 0.5 1.5
+         fill_rect 10 10 90 90
+         set_paint linear start 110 0 end 190 0 extend pad transform 1 0 0 1 0 0 \
+         stops 0 -0.5 3 0 1 1 1 0 2 0.5
+         fill_rect 110 10 200 90",
+    )
+    .unwrap();
+
+    let mut expected = Scene::new();
+    expected.set_paint(AlphaColor::<Srgb>::new([1.0, 0.0, 0.5, 1.0]));
+    expected.fill_rect(&Rect::new(10.0, 10.0, 90.0, 90.0));
+    expected.set_paint(LinearGradient::new(
+        (110.0, 0.0),
+        (190.0, 0.0),
+        [
+            AlphaColor::<Srgb>::new([0.0, 1.0, 0.0, 1.0]),
+            AlphaColor::<Srgb>::new([1.0, 0.0, 1.0, 0.5]),
+        ],
+    ));
+    expected.fill_rect(&Rect::new(110.0, 10.0, 200.0, 90.0));
+
+    assert_eq!(render(&scene).data(), render(&expected).data());
+}
+
+#[test]
+fn write_to_failing_writer() {
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::Other.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut scene = Scene::new();
+    scene.fill_rect(&Rect::new(0.0, 0.0, 1.0, 1.0));
+
+    assert!(scene.write(FailingWriter).is_err());
+}
@@ -39,7 +39,7 @@ pub fn get_ctx(width: usize, height: usize, transparent: bool) -> RenderContext
     if !transparent {
         let path = Rect::new(0.0, 0.0, width as f64, height as f64).to_path(0.1);
 
-        ctx.set_paint(palette::css::WHITE.into());
+        ctx.set_paint(palette::css::WHITE);
         ctx.fill_path(&path.into());
     }
 
@@ -54,19 +54,7 @@ pub fn render_pixmap(ctx: &RenderContext) -> Pixmap {
 }
 
 pub fn check_ref(ctx: &RenderContext, name: &str) {
-    let mut pixmap = render_pixmap(ctx);
-    pixmap.unpremultiply();
-
-    let encoded_image = {
-        let mut out = vec![];
-        let mut encoder = png::Encoder::new(&mut out, ctx.width() as u32, ctx.height() as u32);
-        encoder.set_color(png::ColorType::Rgba);
-        let mut writer = encoder.write_header().unwrap();
-        writer.write_image_data(pixmap.data()).unwrap();
-        writer.finish().unwrap();
-
-        out
-    };
+    let encoded_image = render_pixmap(ctx).encode_png().unwrap();
 
     let ref_path = REFS_PATH.join(format!("{}.png", name));
 